@@ -0,0 +1,20 @@
+use crate::db::audit as db;
+use crate::session::AuthenticatedUser;
+use actix_web::{get, web, HttpResponse, Responder};
+use shared::audit::AuditQuery;
+
+/// Lists the user's change log, newest first, filtered by the query string, e.g.
+/// `/api/audit?machine=<id>&magazine=0&slot=3&from=2024-05-01T00:00:00Z`.
+#[get("/api/audit")]
+pub async fn list_entries(
+    session: AuthenticatedUser,
+    query: web::Query<AuditQuery>,
+) -> impl Responder {
+    match db::list_entries(&session.username, &query) {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(_) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": "Database error"
+        })),
+    }
+}
@@ -1,15 +1,20 @@
 use crate::db;
+use crate::session::{issue_token, AuthenticatedUser};
 use actix_web::{post, web, HttpResponse, Responder};
-use serde_json;
-use shared::User;
+use shared::{User, UserData};
 
 #[post("/api/login")]
 pub async fn login(user: web::Json<User>) -> impl Responder {
     match db::verify_user(&user.username, &user.password) {
-        Ok(Some(user_data)) => HttpResponse::Ok().json(serde_json::json!({
-            "status": "success",
-            "user_data": user_data
-        })),
+        Ok(Some(user_data)) => {
+            let session = issue_token(&user.username);
+            HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "user_data": user_data,
+                "token": session.token,
+                "expires_at": session.expires_at
+            }))
+        }
         Ok(None) => HttpResponse::Unauthorized().json(serde_json::json!({
             "status": "error",
             "message": "Invalid credentials"
@@ -36,8 +41,11 @@ pub async fn register_user(user: web::Json<User>) -> impl Responder {
 }
 
 #[post("/api/update_user_data")]
-pub async fn update_user_data(user: web::Json<User>) -> impl Responder {
-    match db::update_user_data(&user.username, &user.user_data) {
+pub async fn update_user_data(
+    session: AuthenticatedUser,
+    user_data: web::Json<UserData>,
+) -> impl Responder {
+    match db::update_user_data(&session.username, &user_data) {
         Ok(_) => HttpResponse::Ok().json(serde_json::json!({
             "status": "success",
             "message": "User data updated successfully"
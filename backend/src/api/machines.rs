@@ -0,0 +1,208 @@
+use crate::db::machines as db;
+use crate::session::AuthenticatedUser;
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use rusqlite::ErrorCode;
+use serde::Serialize;
+use shared::{machine::Machine, magazine::Magazine, slot::Slot};
+
+fn not_found(message: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "status": "error",
+        "message": message
+    }))
+}
+
+fn database_error() -> HttpResponse {
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "status": "error",
+        "message": "Database error"
+    }))
+}
+
+fn respond<T: Serialize>(result: rusqlite::Result<Option<T>>, missing: &str) -> HttpResponse {
+    match result {
+        Ok(Some(resource)) => HttpResponse::Ok().json(resource),
+        Ok(None) => not_found(missing),
+        Err(_) => database_error(),
+    }
+}
+
+fn respond_slot(result: rusqlite::Result<Option<db::SlotUpdate>>) -> HttpResponse {
+    match result {
+        Ok(Some(db::SlotUpdate::Updated(slot))) => HttpResponse::Ok().json(slot),
+        Ok(Some(db::SlotUpdate::Locked)) => HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "The slot is reserved, unlock it first"
+        })),
+        Ok(None) => not_found("Slot not found"),
+        Err(_) => database_error(),
+    }
+}
+
+fn respond_deleted(result: rusqlite::Result<bool>, missing: &str) -> HttpResponse {
+    match result {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => not_found(missing),
+        Err(_) => database_error(),
+    }
+}
+
+#[get("/api/machines")]
+pub async fn list_machines(session: AuthenticatedUser) -> impl Responder {
+    match db::list_machines(&session.username) {
+        Ok(machines) => HttpResponse::Ok().json(machines),
+        Err(_) => database_error(),
+    }
+}
+
+#[post("/api/machines")]
+pub async fn create_machine(
+    session: AuthenticatedUser,
+    machine: web::Json<Machine>,
+) -> impl Responder {
+    match db::create_machine(&session.username, &machine) {
+        Ok(machine) => HttpResponse::Created().json(machine),
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::ConstraintViolation => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "status": "error",
+                "message": "A machine with this id already exists"
+            }))
+        }
+        Err(_) => database_error(),
+    }
+}
+
+#[get("/api/machines/{machine_id}")]
+pub async fn get_machine(session: AuthenticatedUser, path: web::Path<String>) -> impl Responder {
+    respond(
+        db::get_machine(&session.username, &path),
+        "Machine not found",
+    )
+}
+
+#[put("/api/machines/{machine_id}")]
+pub async fn update_machine(
+    session: AuthenticatedUser,
+    path: web::Path<String>,
+    machine: web::Json<Machine>,
+) -> impl Responder {
+    respond(
+        db::update_machine(&session.username, &path, &machine),
+        "Machine not found",
+    )
+}
+
+#[delete("/api/machines/{machine_id}")]
+pub async fn delete_machine(session: AuthenticatedUser, path: web::Path<String>) -> impl Responder {
+    respond_deleted(
+        db::delete_machine(&session.username, &path),
+        "Machine not found",
+    )
+}
+
+#[get("/api/machines/{machine_id}/magazines")]
+pub async fn list_magazines(session: AuthenticatedUser, path: web::Path<String>) -> impl Responder {
+    respond(
+        db::list_magazines(&session.username, &path),
+        "Machine not found",
+    )
+}
+
+#[post("/api/machines/{machine_id}/magazines")]
+pub async fn create_magazine(
+    session: AuthenticatedUser,
+    path: web::Path<String>,
+    magazine: web::Json<Magazine>,
+) -> impl Responder {
+    match db::create_magazine(&session.username, &path, &magazine) {
+        Ok(Some(magazine)) => HttpResponse::Created().json(magazine),
+        Ok(None) => not_found("Machine not found"),
+        Err(_) => database_error(),
+    }
+}
+
+#[get("/api/machines/{machine_id}/magazines/{index}")]
+pub async fn get_magazine(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize)>,
+) -> impl Responder {
+    let (machine_id, index) = path.into_inner();
+    respond(
+        db::get_magazine(&session.username, &machine_id, index),
+        "Magazine not found",
+    )
+}
+
+#[put("/api/machines/{machine_id}/magazines/{index}")]
+pub async fn update_magazine(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize)>,
+    magazine: web::Json<Magazine>,
+) -> impl Responder {
+    let (machine_id, index) = path.into_inner();
+    respond(
+        db::update_magazine(&session.username, &machine_id, index, &magazine),
+        "Magazine not found",
+    )
+}
+
+#[delete("/api/machines/{machine_id}/magazines/{index}")]
+pub async fn delete_magazine(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize)>,
+) -> impl Responder {
+    let (machine_id, index) = path.into_inner();
+    respond_deleted(
+        db::delete_magazine(&session.username, &machine_id, index),
+        "Magazine not found",
+    )
+}
+
+#[get("/api/machines/{machine_id}/magazines/{index}/slots")]
+pub async fn list_slots(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize)>,
+) -> impl Responder {
+    let (machine_id, index) = path.into_inner();
+    respond(
+        db::list_slots(&session.username, &machine_id, index),
+        "Magazine not found",
+    )
+}
+
+#[get("/api/machines/{machine_id}/magazines/{index}/slots/{slot}")]
+pub async fn get_slot(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize, usize)>,
+) -> impl Responder {
+    let (machine_id, index, slot) = path.into_inner();
+    respond(
+        db::get_slot(&session.username, &machine_id, index, slot),
+        "Slot not found",
+    )
+}
+
+#[put("/api/machines/{machine_id}/magazines/{index}/slots/{slot}")]
+pub async fn update_slot(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize, usize)>,
+    slot: web::Json<Slot>,
+) -> impl Responder {
+    let (machine_id, index, slot_index) = path.into_inner();
+    respond_slot(db::update_slot(
+        &session.username,
+        &machine_id,
+        index,
+        slot_index,
+        &slot,
+    ))
+}
+
+#[delete("/api/machines/{machine_id}/magazines/{index}/slots/{slot}")]
+pub async fn clear_slot(
+    session: AuthenticatedUser,
+    path: web::Path<(String, usize, usize)>,
+) -> impl Responder {
+    let (machine_id, index, slot) = path.into_inner();
+    respond_slot(db::clear_slot(&session.username, &machine_id, index, slot))
+}
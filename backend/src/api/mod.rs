@@ -1 +1,4 @@
+pub mod audit;
 pub mod auth;
+pub mod machines;
+pub mod sync;
@@ -0,0 +1,33 @@
+use crate::db::sync as db;
+use crate::session::AuthenticatedUser;
+use actix_web::{get, post, web, HttpResponse, Responder};
+use shared::sync::SyncOperation;
+
+fn database_error() -> HttpResponse {
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "status": "error",
+        "message": "Database error"
+    }))
+}
+
+/// The user's machines with their revisions, what a client merges its own changes against.
+#[get("/api/sync")]
+pub async fn list_revisions(session: AuthenticatedUser) -> impl Responder {
+    match db::list(&session.username) {
+        Ok(machines) => HttpResponse::Ok().json(machines),
+        Err(_) => database_error(),
+    }
+}
+
+/// Applies a client's changes and answers with one outcome for each. Changes whose revision
+/// is out of date are refused with the current machine, so the client can merge again.
+#[post("/api/sync")]
+pub async fn apply_operations(
+    session: AuthenticatedUser,
+    operations: web::Json<Vec<SyncOperation>>,
+) -> impl Responder {
+    match db::apply(&session.username, &operations) {
+        Ok(outcomes) => HttpResponse::Ok().json(outcomes),
+        Err(_) => database_error(),
+    }
+}
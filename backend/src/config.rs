@@ -0,0 +1,187 @@
+use clap::{ArgAction, Parser};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_LISTEN_ADDR: &str = "[::]:443";
+const DEFAULT_TLS_CERT: &str = "/etc/letsencrypt/live/rasmushogslatt.com/fullchain.pem";
+const DEFAULT_TLS_KEY: &str = "/etc/letsencrypt/live/rasmushogslatt.com/privkey.pem";
+const DEFAULT_DATABASE_PATH: &str = "/var/www/your_app/backend/users.db";
+
+/// Command line flags. Every flag can also be given through the environment variable next to it.
+/// Flags win over the environment, which wins over the config file.
+#[derive(Debug, Parser)]
+#[command(about = "Backend for the machine magazine application")]
+struct Cli {
+    /// TOML file with the same settings as the flags below
+    #[arg(long, env = "BACKEND_CONFIG")]
+    config: Option<PathBuf>,
+    /// Address to listen on, e.g. 127.0.0.1:8080
+    #[arg(long, env = "BACKEND_LISTEN_ADDR")]
+    listen_addr: Option<String>,
+    /// Serve HTTPS (true) or plain HTTP (false)
+    #[arg(long, env = "BACKEND_TLS", action = ArgAction::Set)]
+    tls: Option<bool>,
+    /// PEM certificate chain used when TLS is on
+    #[arg(long, env = "BACKEND_TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+    /// PEM private key used when TLS is on
+    #[arg(long, env = "BACKEND_TLS_KEY")]
+    tls_key: Option<PathBuf>,
+    /// SQLite database file
+    #[arg(long, env = "BACKEND_DATABASE_PATH")]
+    database_path: Option<PathBuf>,
+    /// Comma separated list of allowed CORS origins, any origin is allowed when empty
+    #[arg(long, env = "BACKEND_CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    listen_addr: Option<String>,
+    database_path: Option<PathBuf>,
+    cors_origins: Option<Vec<String>>,
+    tls: FileTlsConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileTlsConfig {
+    enabled: Option<bool>,
+    cert_path: Option<PathBuf>,
+    key_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub listen_addr: String,
+    pub tls: Option<TlsConfig>,
+    pub database_path: PathBuf,
+    pub cors_origins: Vec<String>,
+}
+
+impl Config {
+    pub fn load() -> std::io::Result<Self> {
+        let cli = Cli::parse();
+        let file = match &cli.config {
+            Some(path) => read_file_config(path)?,
+            None => FileConfig::default(),
+        };
+        Ok(Self::merge(cli, file))
+    }
+
+    fn merge(cli: Cli, file: FileConfig) -> Self {
+        let tls_enabled = cli.tls.or(file.tls.enabled).unwrap_or(true);
+        let tls = tls_enabled.then(|| TlsConfig {
+            cert_path: cli
+                .tls_cert
+                .or(file.tls.cert_path)
+                .unwrap_or_else(|| DEFAULT_TLS_CERT.into()),
+            key_path: cli
+                .tls_key
+                .or(file.tls.key_path)
+                .unwrap_or_else(|| DEFAULT_TLS_KEY.into()),
+        });
+
+        Self {
+            listen_addr: cli
+                .listen_addr
+                .or(file.listen_addr)
+                .unwrap_or_else(|| DEFAULT_LISTEN_ADDR.to_string()),
+            tls,
+            database_path: cli
+                .database_path
+                .or(file.database_path)
+                .unwrap_or_else(|| DEFAULT_DATABASE_PATH.into()),
+            cors_origins: cli
+                .cors_origins
+                .or(file.cors_origins)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+        }
+    }
+}
+
+fn read_file_config(path: &Path) -> std::io::Result<FileConfig> {
+    let contents = std::fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Invalid config file {}: {}", path.display(), e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+        listen_addr = "127.0.0.1:8000"
+        database_path = "file.db"
+        cors_origins = ["https://file.example"]
+
+        [tls]
+        enabled = false
+    "#;
+
+    fn merged(args: &[&str], file: &str) -> Config {
+        let cli =
+            Cli::try_parse_from(std::iter::once("backend").chain(args.iter().copied())).unwrap();
+        Config::merge(cli, toml::from_str(file).unwrap())
+    }
+
+    // One test, as the environment is shared by every test in the process
+    #[test]
+    fn flags_win_over_the_environment_over_the_file_over_the_defaults() {
+        let config = merged(&[], "");
+        assert_eq!(config.listen_addr, DEFAULT_LISTEN_ADDR);
+        assert_eq!(config.database_path, PathBuf::from(DEFAULT_DATABASE_PATH));
+        assert!(config.cors_origins.is_empty());
+        let tls = config.tls.unwrap();
+        assert_eq!(tls.cert_path, PathBuf::from(DEFAULT_TLS_CERT));
+        assert_eq!(tls.key_path, PathBuf::from(DEFAULT_TLS_KEY));
+
+        let config = merged(&[], FILE);
+        assert_eq!(config.listen_addr, "127.0.0.1:8000");
+        assert_eq!(config.database_path, PathBuf::from("file.db"));
+        assert_eq!(config.cors_origins, ["https://file.example"]);
+        assert!(config.tls.is_none());
+
+        std::env::set_var("BACKEND_LISTEN_ADDR", "127.0.0.1:9000");
+        std::env::set_var("BACKEND_TLS", "true");
+        std::env::set_var("BACKEND_CORS_ORIGINS", "https://env.example,");
+        let config = merged(&[], FILE);
+        assert_eq!(config.listen_addr, "127.0.0.1:9000");
+        assert_eq!(config.database_path, PathBuf::from("file.db"));
+        assert_eq!(config.cors_origins, ["https://env.example"]);
+        assert!(config.tls.is_some());
+
+        let config = merged(
+            &[
+                "--listen-addr",
+                "127.0.0.1:10000",
+                "--tls",
+                "false",
+                "--database-path",
+                "flag.db",
+            ],
+            FILE,
+        );
+        std::env::remove_var("BACKEND_LISTEN_ADDR");
+        std::env::remove_var("BACKEND_TLS");
+        std::env::remove_var("BACKEND_CORS_ORIGINS");
+        assert_eq!(config.listen_addr, "127.0.0.1:10000");
+        assert_eq!(config.database_path, PathBuf::from("flag.db"));
+        assert_eq!(config.cors_origins, ["https://env.example"]);
+        assert!(config.tls.is_none());
+    }
+}
@@ -1,23 +1,121 @@
-use argon2::{
-    self,
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
-};
 use chrono::Utc;
-use lazy_static::lazy_static;
 use rusqlite::{params, Connection, Result};
-use serde_json;
-use shared::UserData;
-use std::sync::Mutex;
-
-lazy_static! {
-    static ref DB_CONNECTION: Mutex<Connection> = Mutex::new(
-        Connection::open("/var/www/your_app/backend/users.db").expect("Failed to open database")
-    );
+use shared::{
+    credentials::{hash_password, verify_password},
+    migrations::{run_migrations, Migration, MigrationError},
+    upgrade::{user_data_from_json, CURRENT_USER_DATA_VERSION},
+    UserData,
+};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+pub mod audit;
+pub mod machines;
+pub mod sync;
+
+static DB_CONNECTION: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+fn connection() -> MutexGuard<'static, Connection> {
+    DB_CONNECTION
+        .get()
+        .expect("init_db must be called before using the database")
+        .lock()
+        .unwrap()
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create users table",
+        up: create_users_table,
+    },
+    Migration {
+        version: 2,
+        description: "create machine, magazine and slot tables",
+        up: machines::create_tables,
+    },
+    Migration {
+        version: 3,
+        description: "move machines out of users.user_data",
+        up: machines::move_machines_out_of_user_data,
+    },
+    Migration {
+        version: 4,
+        description: "add machine capabilities and magazine kinds",
+        up: machines::add_capabilities,
+    },
+    Migration {
+        version: 5,
+        description: "create append-only audit log",
+        up: audit::create_table,
+    },
+    Migration {
+        version: 6,
+        description: "add revision counters to machines and slots",
+        up: machines::add_revisions,
+    },
+    Migration {
+        version: 7,
+        description: "key machines by user and id",
+        up: machines::key_machines_by_user,
+    },
+];
+
+#[derive(Debug)]
+pub enum InitError {
+    /// The process already has a database, it keeps using that one.
+    AlreadyInitialized,
+    Migration(MigrationError),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::AlreadyInitialized => write!(f, "The database is already open"),
+            InitError::Migration(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InitError::AlreadyInitialized => None,
+            InitError::Migration(e) => Some(e),
+        }
+    }
+}
+
+impl From<MigrationError> for InitError {
+    fn from(e: MigrationError) -> Self {
+        InitError::Migration(e)
+    }
+}
+
+impl From<rusqlite::Error> for InitError {
+    fn from(e: rusqlite::Error) -> Self {
+        InitError::Migration(e.into())
+    }
 }
 
-pub fn init_db() -> Result<()> {
-    let conn = DB_CONNECTION.lock().unwrap();
+/// Opens the database and upgrades its schema. The server must not start if this fails.
+/// Only the first call opens a database, later calls return [`InitError::AlreadyInitialized`].
+pub fn init_db(path: &Path) -> Result<(), InitError> {
+    if DB_CONNECTION.get().is_some() {
+        return Err(InitError::AlreadyInitialized);
+    }
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    let version = run_migrations(&mut conn, MIGRATIONS)?;
+    log::info!("Database {} at schema version {}", path.display(), version);
+    DB_CONNECTION
+        .set(Mutex::new(conn))
+        .map_err(|_| InitError::AlreadyInitialized)
+}
+
+// Existing databases already have this table, hence IF NOT EXISTS.
+fn create_users_table(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS users (
             id INTEGER PRIMARY KEY,
@@ -34,13 +132,9 @@ pub fn init_db() -> Result<()> {
 }
 
 pub fn add_user(username: &str, password: &str, email: &str) -> Result<(), rusqlite::Error> {
-    let conn = DB_CONNECTION.lock().unwrap();
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt)
-        .unwrap()
-        .to_string();
+    let conn = connection();
+    let password_hash = hash_password(password)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
 
     let now = Utc::now().to_rfc3339();
 
@@ -56,29 +150,29 @@ pub fn add_user(username: &str, password: &str, email: &str) -> Result<(), rusql
 }
 
 pub fn verify_user(username: &str, password: &str) -> Result<Option<UserData>> {
-    let conn = DB_CONNECTION.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT password, user_data FROM users WHERE username = ?1")?;
+    let conn = connection();
+    let mut stmt = conn.prepare("SELECT id, password, user_data FROM users WHERE username = ?1")?;
     let mut rows = stmt.query([username])?;
 
     if let Some(row) = rows.next()? {
-        let stored_hash: String = row.get(0)?;
-        let user_data_json: String = row.get(1)?;
-
-        // Verify password
-        let parsed_hash = PasswordHash::new(&stored_hash).unwrap();
-        let is_valid = Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok();
-
-        if is_valid {
-            // Parse user_data JSON into UserData struct
-            let user_data: UserData = serde_json::from_str(&user_data_json).map_err(|e| {
+        let user_id: i64 = row.get(0)?;
+        let stored_hash: String = row.get(1)?;
+        let user_data_json: String = row.get(2)?;
+
+        if verify_password(password, &stored_hash) {
+            // Blobs written by older builds are upgraded to the current shape
+            let mut user_data = user_data_from_json(&user_data_json).map_err(|e| {
                 rusqlite::Error::FromSqlConversionFailure(
                     0,
                     rusqlite::types::Type::Text,
                     Box::new(e),
                 )
             })?;
+            // Machines live in their own tables
+            user_data.machines = machines::load_machines(&conn, user_id)?;
+            user_data.tool_library.adopt_loaded(&user_data.machines);
+            // Shortages are the client's to show, the inventory lists them
+            user_data.holder_inventory.adopt_loaded(&user_data.machines);
             Ok(Some(user_data))
         } else {
             Ok(None)
@@ -88,10 +182,17 @@ pub fn verify_user(username: &str, password: &str) -> Result<Option<UserData>> {
     }
 }
 
+/// Stores everything but the machines, which are written through the machine resources.
 pub fn update_user_data(username: &str, user_data: &UserData) -> Result<()> {
-    let conn = DB_CONNECTION.lock().unwrap();
+    let conn = connection();
     let now = Utc::now().to_rfc3339();
 
+    // Anything that deserialized into `UserData` already has the current shape
+    let user_data = UserData {
+        version: CURRENT_USER_DATA_VERSION,
+        machines: vec![],
+        ..user_data.clone()
+    };
     let serialized_data = serde_json::to_string(&user_data).map_err(|e| {
         rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
     })?;
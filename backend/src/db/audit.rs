@@ -0,0 +1,176 @@
+use super::{connection, machines::user_id};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, Result};
+use serde_json::Value;
+use shared::audit::{AuditAction, AuditEntry, AuditQuery};
+
+/// At most this many entries are listed, the newest first.
+const MAX_ENTRIES: usize = 1000;
+
+/// Triggers refuse to change or remove a row once it is written.
+pub fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            changed_by TEXT NOT NULL,
+            changed_at TEXT NOT NULL,
+            action TEXT NOT NULL,
+            machine_id TEXT NOT NULL,
+            machine_name TEXT NOT NULL,
+            magazine INTEGER,
+            slot INTEGER,
+            before TEXT,
+            after TEXT
+        );
+        CREATE INDEX IF NOT EXISTS audit_log_machine ON audit_log (user_id, machine_id);
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;",
+    )
+}
+
+/// A mutation to record. `before` and `after` are the machine, magazine or slot as JSON.
+pub struct Change {
+    pub action: AuditAction,
+    pub machine_id: String,
+    pub magazine: Option<usize>,
+    pub slot: Option<usize>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl Change {
+    pub fn new<T: serde::Serialize>(
+        machine_id: &str,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Result<Self> {
+        let action = match (before, after) {
+            (None, _) => AuditAction::Created,
+            (Some(_), None) => AuditAction::Deleted,
+            (Some(_), Some(_)) => AuditAction::Updated,
+        };
+        Ok(Self {
+            action,
+            machine_id: machine_id.to_string(),
+            magazine: None,
+            slot: None,
+            before: before.map(to_value).transpose()?,
+            after: after.map(to_value).transpose()?,
+        })
+    }
+
+    pub fn at(mut self, magazine: usize, slot: Option<usize>) -> Self {
+        self.magazine = Some(magazine);
+        self.slot = slot;
+        self
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    // One fixed width, so the text sorts like the time it stands for
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Appends `change` to the log. Call it inside the transaction that makes the change, while
+/// the machine still exists, so its name is recorded.
+pub fn record(conn: &Connection, user_id: i64, changed_by: &str, change: Change) -> Result<()> {
+    conn.execute(
+        "INSERT INTO audit_log
+            (user_id, changed_by, changed_at, action, machine_id, machine_name, magazine, slot,
+             before, after)
+         VALUES (?1, ?2, ?3, ?4, ?5,
+            COALESCE((SELECT name FROM machines WHERE user_id = ?1 AND id = ?5), ''), ?6, ?7, ?8,
+            ?9)",
+        params![
+            user_id,
+            changed_by,
+            timestamp(&Utc::now()),
+            to_value(&change.action)?.as_str(),
+            change.machine_id,
+            change.magazine,
+            change.slot,
+            change.before.map(|value| value.to_string()),
+            change.after.map(|value| value.to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_entries(username: &str, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+    let conn = connection();
+    let user_id = user_id(&conn, username)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, changed_at, changed_by, action, machine_id, machine_name, magazine, slot,
+            before, after
+         FROM audit_log
+         WHERE user_id = ?1
+            AND (?2 IS NULL OR machine_id = ?2)
+            AND (?3 IS NULL OR magazine = ?3)
+            AND (?4 IS NULL OR slot = ?4)
+            AND (?5 IS NULL OR changed_at >= ?5)
+            AND (?6 IS NULL OR changed_at < ?6)
+         ORDER BY id DESC
+         LIMIT ?7",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            user_id,
+            query.machine,
+            query.magazine,
+            query.slot,
+            query.from.as_ref().map(timestamp),
+            query.to.as_ref().map(timestamp),
+            MAX_ENTRIES
+        ],
+        |row| {
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                changed_at: parse_timestamp(&row.get::<_, String>(1)?)?,
+                changed_by: row.get(2)?,
+                action: from_value(Value::String(row.get(3)?))?,
+                machine_id: row.get(4)?,
+                machine_name: row.get(5)?,
+                magazine: row.get(6)?,
+                slot: row.get(7)?,
+                before: row
+                    .get::<_, Option<String>>(8)?
+                    .map(|json| from_json(&json))
+                    .transpose()?,
+                after: row
+                    .get::<_, Option<String>>(9)?
+                    .map(|json| from_json(&json))
+                    .transpose()?,
+            })
+        },
+    )?;
+    rows.collect()
+}
+
+fn conversion_failure<E: std::error::Error + Send + Sync + 'static>(e: E) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+}
+
+fn from_json(json: &str) -> Result<Value> {
+    serde_json::from_str(json).map_err(conversion_failure)
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(conversion_failure)
+}
+
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(conversion_failure)
+}
@@ -0,0 +1,730 @@
+use super::{
+    audit::{self, Change},
+    connection,
+};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::Deserialize;
+use shared::{description::Description, machine::Machine, magazine::Magazine, slot::Slot};
+use uuid::Uuid;
+
+pub fn create_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS machines (
+            id TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            manufacturer TEXT NOT NULL,
+            model TEXT NOT NULL,
+            description TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS magazines (
+            id INTEGER PRIMARY KEY,
+            machine_id TEXT NOT NULL REFERENCES machines(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            capacity INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS magazines_machine ON magazines (machine_id, position);
+        CREATE TABLE IF NOT EXISTS slots (
+            magazine_id INTEGER NOT NULL REFERENCES magazines(id) ON DELETE CASCADE,
+            slot_index INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            PRIMARY KEY (magazine_id, slot_index)
+        );",
+    )
+}
+
+/// Gives machines their capabilities, as JSON, and magazines a kind.
+pub fn add_capabilities(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE machines ADD COLUMN capabilities TEXT NOT NULL DEFAULT '{}';
+         ALTER TABLE magazines ADD COLUMN kind TEXT NOT NULL DEFAULT 'null';",
+    )
+}
+
+/// Every change to a machine's fields or magazines raises its revision, every change to a
+/// slot the slot's. Sync compares them to tell whose copy is newer.
+pub fn add_revisions(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE machines ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;
+         ALTER TABLE slots ADD COLUMN revision INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+/// Machine ids are only unique per user, so a taken id does not tell that another user has
+/// it. Magazines carry the user to reference their machine by. The tables are rebuilt
+/// children first, dropping a parent would take its children along.
+pub fn key_machines_by_user(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE machines_by_user (
+            id TEXT NOT NULL,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            manufacturer TEXT NOT NULL,
+            model TEXT NOT NULL,
+            description TEXT NOT NULL,
+            capabilities TEXT NOT NULL DEFAULT '{}',
+            revision INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (user_id, id)
+        );
+        INSERT INTO machines_by_user
+            SELECT id, user_id, position, name, manufacturer, model, description, capabilities,
+                revision
+            FROM machines;
+        CREATE TABLE magazines_by_user (
+            id INTEGER PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            machine_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            capacity INTEGER NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'null',
+            FOREIGN KEY (user_id, machine_id)
+                REFERENCES machines_by_user(user_id, id) ON DELETE CASCADE
+        );
+        INSERT INTO magazines_by_user
+            SELECT magazines.id, machines.user_id, magazines.machine_id, magazines.position,
+                magazines.name, magazines.capacity, magazines.kind
+            FROM magazines JOIN machines ON machines.id = magazines.machine_id;
+        CREATE TABLE slots_by_user (
+            magazine_id INTEGER NOT NULL REFERENCES magazines_by_user(id) ON DELETE CASCADE,
+            slot_index INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            revision INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (magazine_id, slot_index)
+        );
+        INSERT INTO slots_by_user SELECT magazine_id, slot_index, content, revision FROM slots;
+        DROP TABLE slots;
+        DROP TABLE magazines;
+        DROP TABLE machines;
+        ALTER TABLE machines_by_user RENAME TO machines;
+        ALTER TABLE magazines_by_user RENAME TO magazines;
+        ALTER TABLE slots_by_user RENAME TO slots;
+        CREATE INDEX magazines_machine ON magazines (user_id, machine_id, position);",
+    )
+}
+
+/// Machines as the `users.user_data` blob held them before they had tables of their own.
+/// Frozen, later changes to [`Machine`] must not change what this migration reads.
+#[derive(Deserialize)]
+struct V0UserData {
+    #[serde(default)]
+    machines: Vec<V0Machine>,
+}
+
+#[derive(Deserialize)]
+struct V0Machine {
+    /// Only blobs written after the machines moved have one
+    uuid: Option<String>,
+    name: String,
+    manufacturer: String,
+    model: String,
+    description: V0Description,
+    magazines: Vec<V0Magazine>,
+}
+
+#[derive(Deserialize)]
+struct V0Description {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct V0Magazine {
+    name: String,
+    capacity: usize,
+    /// `(tool number, tool, holder, overhang, description)` tuples, which [`Slot`] still reads
+    content: Vec<serde_json::Value>,
+}
+
+/// One-off move of machines stored in the `users.user_data` blob into the machine tables.
+/// Fails on a blob it cannot read, rather than dropping that user's machines.
+pub fn move_machines_out_of_user_data(conn: &Connection) -> Result<()> {
+    let users = {
+        let mut stmt = conn.prepare("SELECT id, user_data FROM users")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+
+    for (user_id, user_data_json) in users {
+        let (mut blob, user_data) = serde_json::from_str::<serde_json::Value>(&user_data_json)
+            .and_then(|blob| Ok((blob.clone(), V0UserData::deserialize(blob)?)))
+            .map_err(|e| {
+                log::error!("The user data of user {} cannot be read: {}", user_id, e);
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
+        if user_data.machines.is_empty() {
+            continue;
+        }
+        for (position, machine) in user_data.machines.iter().enumerate() {
+            let machine_id = machine
+                .uuid
+                .clone()
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            conn.execute(
+                "INSERT INTO machines (id, user_id, position, name, manufacturer, model, description)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    machine_id,
+                    user_id,
+                    position,
+                    machine.name,
+                    machine.manufacturer,
+                    machine.model,
+                    machine.description.text
+                ],
+            )?;
+            for (position, magazine) in machine.magazines.iter().enumerate() {
+                conn.execute(
+                    "INSERT INTO magazines (machine_id, position, name, capacity)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![machine_id, position, magazine.name, magazine.capacity],
+                )?;
+                let magazine_id = conn.last_insert_rowid();
+                for slot_index in 0..magazine.capacity {
+                    let content = match magazine.content.get(slot_index) {
+                        Some(content) => content.to_string(),
+                        None => "{}".to_string(),
+                    };
+                    conn.execute(
+                        "INSERT INTO slots (magazine_id, slot_index, content) VALUES (?1, ?2, ?3)",
+                        params![magazine_id, slot_index, content],
+                    )?;
+                }
+            }
+        }
+        // The rest of the blob stays as it was, it is upgraded when it is read
+        blob["machines"] = serde_json::Value::Array(vec![]);
+        conn.execute(
+            "UPDATE users SET user_data = ?1 WHERE id = ?2",
+            params![blob.to_string(), user_id],
+        )?;
+    }
+    Ok(())
+}
+
+pub(super) fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+pub(super) fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> Result<T> {
+    serde_json::from_str(json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+pub(super) fn user_id(conn: &Connection, username: &str) -> Result<i64> {
+    conn.query_row(
+        "SELECT id FROM users WHERE username = ?1",
+        [username],
+        |row| row.get(0),
+    )
+}
+
+/// Raises the revision of a machine and returns the new one.
+pub(super) fn bump_machine_revision(
+    conn: &Connection,
+    user_id: i64,
+    machine_id: &str,
+) -> Result<u64> {
+    conn.query_row(
+        "UPDATE machines SET revision = revision + 1 WHERE user_id = ?1 AND id = ?2
+         RETURNING revision",
+        params![user_id, machine_id],
+        |row| row.get(0),
+    )
+}
+
+/// Raises the revision of a slot and returns the new one.
+pub(super) fn bump_slot_revision(
+    conn: &Connection,
+    magazine_id: i64,
+    slot_index: usize,
+) -> Result<u64> {
+    conn.query_row(
+        "UPDATE slots SET revision = revision + 1 WHERE magazine_id = ?1 AND slot_index = ?2
+         RETURNING revision",
+        params![magazine_id, slot_index],
+        |row| row.get(0),
+    )
+}
+
+fn owns_machine(conn: &Connection, user_id: i64, machine_id: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM machines WHERE id = ?1 AND user_id = ?2",
+        params![machine_id, user_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Returns the row id and capacity of a magazine, if it belongs to one of the user's machines.
+pub(super) fn magazine_row(
+    conn: &Connection,
+    user_id: i64,
+    machine_id: &str,
+    index: usize,
+) -> Result<Option<(i64, usize)>> {
+    conn.query_row(
+        "SELECT id, capacity FROM magazines
+         WHERE machine_id = ?1 AND user_id = ?2 AND position = ?3",
+        params![machine_id, user_id, index],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+pub(super) fn insert_machine_rows(
+    conn: &Connection,
+    user_id: i64,
+    machine: &Machine,
+) -> Result<()> {
+    let position: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(position) + 1, 0) FROM machines WHERE user_id = ?1",
+        [user_id],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "INSERT INTO machines (id, user_id, position, name, manufacturer, model, description)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            machine.uuid,
+            user_id,
+            position,
+            machine.name,
+            machine.manufacturer,
+            machine.model,
+            machine.description.text
+        ],
+    )?;
+    for (position, magazine) in machine.magazines.iter().enumerate() {
+        insert_magazine_rows(conn, user_id, &machine.uuid, position, magazine)?;
+    }
+    Ok(())
+}
+
+/// Writes the capabilities and magazine kinds, which [`insert_machine_rows`] leaves at their
+/// defaults.
+pub(super) fn write_capabilities(conn: &Connection, user_id: i64, machine: &Machine) -> Result<()> {
+    conn.execute(
+        "UPDATE machines SET capabilities = ?1 WHERE user_id = ?2 AND id = ?3",
+        params![to_json(&machine.capabilities)?, user_id, machine.uuid],
+    )?;
+    for (position, magazine) in machine.magazines.iter().enumerate() {
+        conn.execute(
+            "UPDATE magazines SET kind = ?1 WHERE user_id = ?2 AND machine_id = ?3 AND position = ?4",
+            params![to_json(&magazine.kind)?, user_id, machine.uuid, position],
+        )?;
+    }
+    Ok(())
+}
+
+pub(super) fn insert_magazine_rows(
+    conn: &Connection,
+    user_id: i64,
+    machine_id: &str,
+    position: usize,
+    magazine: &Magazine,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO magazines (user_id, machine_id, position, name, capacity)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            user_id,
+            machine_id,
+            position,
+            magazine.name,
+            magazine.capacity
+        ],
+    )?;
+    let magazine_id = conn.last_insert_rowid();
+    for slot_index in 0..magazine.capacity {
+        let slot = magazine.slots.get(slot_index).cloned().unwrap_or_default();
+        conn.execute(
+            "INSERT INTO slots (magazine_id, slot_index, content) VALUES (?1, ?2, ?3)",
+            params![magazine_id, slot_index, to_json(&slot)?],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn load_machines(conn: &Connection, user_id: i64) -> Result<Vec<Machine>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, manufacturer, model, description, capabilities FROM machines
+         WHERE user_id = ?1 ORDER BY position",
+    )?;
+    let rows = stmt.query_map([user_id], |row| {
+        Ok(Machine {
+            uuid: row.get(0)?,
+            name: row.get(1)?,
+            manufacturer: row.get(2)?,
+            model: row.get(3)?,
+            description: Description::new(row.get(4)?),
+            capabilities: from_json(&row.get::<_, String>(5)?)?,
+            magazine_count: 0,
+            magazines: vec![],
+        })
+    })?;
+    let mut machines = rows.collect::<Result<Vec<_>>>()?;
+    for machine in &mut machines {
+        machine.magazines = load_magazines(conn, user_id, &machine.uuid)?;
+        machine.magazine_count = machine.magazines.len();
+    }
+    Ok(machines)
+}
+
+fn load_magazines(conn: &Connection, user_id: i64, machine_id: &str) -> Result<Vec<Magazine>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, position, name, capacity, kind FROM magazines
+         WHERE user_id = ?1 AND machine_id = ?2 ORDER BY position",
+    )?;
+    let rows = stmt.query_map(params![user_id, machine_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            Magazine {
+                index_in_machine: row.get(1)?,
+                name: row.get(2)?,
+                capacity: row.get(3)?,
+                kind: from_json(&row.get::<_, String>(4)?)?,
+                slots: vec![],
+            },
+        ))
+    })?;
+    let mut magazines = vec![];
+    for row in rows {
+        let (magazine_id, mut magazine) = row?;
+        magazine.slots = load_slots(conn, magazine_id)?;
+        magazines.push(magazine);
+    }
+    Ok(magazines)
+}
+
+pub(super) fn load_slots(conn: &Connection, magazine_id: i64) -> Result<Vec<Slot>> {
+    let mut stmt =
+        conn.prepare("SELECT content FROM slots WHERE magazine_id = ?1 ORDER BY slot_index")?;
+    let rows = stmt.query_map([magazine_id], |row| row.get::<_, String>(0))?;
+    rows.map(|content| from_json(&content?)).collect()
+}
+
+pub(super) fn find_machine(
+    conn: &Connection,
+    user_id: i64,
+    machine_id: &str,
+) -> Result<Option<Machine>> {
+    Ok(load_machines(conn, user_id)?
+        .into_iter()
+        .find(|machine| machine.uuid == machine_id))
+}
+
+pub fn list_machines(username: &str) -> Result<Vec<Machine>> {
+    let conn = connection();
+    let user_id = user_id(&conn, username)?;
+    load_machines(&conn, user_id)
+}
+
+pub fn get_machine(username: &str, machine_id: &str) -> Result<Option<Machine>> {
+    let conn = connection();
+    let user_id = user_id(&conn, username)?;
+    find_machine(&conn, user_id, machine_id)
+}
+
+pub fn create_machine(username: &str, machine: &Machine) -> Result<Machine> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    insert_machine_rows(&tx, user_id, machine)?;
+    write_capabilities(&tx, user_id, machine)?;
+    let created = find_machine(&tx, user_id, &machine.uuid)?.expect("machine was just inserted");
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(&machine.uuid, None, Some(&created))?,
+    )?;
+    tx.commit()?;
+    Ok(created)
+}
+
+/// Updates name, manufacturer, model, description and capabilities.
+/// Magazines have their own resource.
+pub fn update_machine(
+    username: &str,
+    machine_id: &str,
+    machine: &Machine,
+) -> Result<Option<Machine>> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    let Some(before) = find_machine(&tx, user_id, machine_id)? else {
+        return Ok(None);
+    };
+    tx.execute(
+        "UPDATE machines SET name = ?1, manufacturer = ?2, model = ?3, description = ?4,
+            capabilities = ?5
+         WHERE id = ?6 AND user_id = ?7",
+        params![
+            machine.name,
+            machine.manufacturer,
+            machine.model,
+            machine.description.text,
+            to_json(&machine.capabilities)?,
+            machine_id,
+            user_id
+        ],
+    )?;
+    bump_machine_revision(&tx, user_id, machine_id)?;
+    let after = find_machine(&tx, user_id, machine_id)?.expect("machine was just updated");
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(machine_id, Some(&before), Some(&after))?,
+    )?;
+    tx.commit()?;
+    Ok(Some(after))
+}
+
+pub fn delete_machine(username: &str, machine_id: &str) -> Result<bool> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    let Some(before) = find_machine(&tx, user_id, machine_id)? else {
+        return Ok(false);
+    };
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(machine_id, Some(&before), None)?,
+    )?;
+    tx.execute(
+        "DELETE FROM machines WHERE id = ?1 AND user_id = ?2",
+        params![machine_id, user_id],
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+
+pub fn list_magazines(username: &str, machine_id: &str) -> Result<Option<Vec<Magazine>>> {
+    let conn = connection();
+    let user_id = user_id(&conn, username)?;
+    if !owns_machine(&conn, user_id, machine_id)? {
+        return Ok(None);
+    }
+    load_magazines(&conn, user_id, machine_id).map(Some)
+}
+
+pub fn get_magazine(username: &str, machine_id: &str, index: usize) -> Result<Option<Magazine>> {
+    Ok(
+        list_magazines(username, machine_id)?
+            .and_then(|magazines| magazines.into_iter().nth(index)),
+    )
+}
+
+/// Appends a magazine to the machine, its `index_in_machine` is assigned by the server.
+pub fn create_magazine(
+    username: &str,
+    machine_id: &str,
+    magazine: &Magazine,
+) -> Result<Option<Magazine>> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    if !owns_machine(&tx, user_id, machine_id)? {
+        return Ok(None);
+    }
+    let position: usize = tx.query_row(
+        "SELECT COUNT(*) FROM magazines WHERE user_id = ?1 AND machine_id = ?2",
+        params![user_id, machine_id],
+        |row| row.get(0),
+    )?;
+    insert_magazine_rows(&tx, user_id, machine_id, position, magazine)?;
+    tx.execute(
+        "UPDATE magazines SET kind = ?1 WHERE user_id = ?2 AND machine_id = ?3 AND position = ?4",
+        params![to_json(&magazine.kind)?, user_id, machine_id, position],
+    )?;
+    bump_machine_revision(&tx, user_id, machine_id)?;
+    let created = load_magazines(&tx, user_id, machine_id)?
+        .into_iter()
+        .nth(position);
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(machine_id, None, created.as_ref())?.at(position, None),
+    )?;
+    tx.commit()?;
+    Ok(created)
+}
+
+/// Renames, resizes and sets the kind of a magazine. Growing adds empty slots, shrinking drops the trailing ones.
+pub fn update_magazine(
+    username: &str,
+    machine_id: &str,
+    index: usize,
+    magazine: &Magazine,
+) -> Result<Option<Magazine>> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    let Some((magazine_id, capacity)) = magazine_row(&tx, user_id, machine_id, index)? else {
+        return Ok(None);
+    };
+    let before = load_magazines(&tx, user_id, machine_id)?
+        .into_iter()
+        .nth(index);
+    tx.execute(
+        "UPDATE magazines SET name = ?1, capacity = ?2, kind = ?3 WHERE id = ?4",
+        params![
+            magazine.name,
+            magazine.capacity,
+            to_json(&magazine.kind)?,
+            magazine_id
+        ],
+    )?;
+    tx.execute(
+        "DELETE FROM slots WHERE magazine_id = ?1 AND slot_index >= ?2",
+        params![magazine_id, magazine.capacity],
+    )?;
+    for slot_index in capacity..magazine.capacity {
+        tx.execute(
+            "INSERT INTO slots (magazine_id, slot_index, content) VALUES (?1, ?2, ?3)",
+            params![magazine_id, slot_index, to_json(&Slot::default())?],
+        )?;
+    }
+    bump_machine_revision(&tx, user_id, machine_id)?;
+    let after = load_magazines(&tx, user_id, machine_id)?
+        .into_iter()
+        .nth(index);
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(machine_id, before.as_ref(), after.as_ref())?.at(index, None),
+    )?;
+    tx.commit()?;
+    Ok(after)
+}
+
+/// Removes a magazine and moves the following magazines one index down.
+pub fn delete_magazine(username: &str, machine_id: &str, index: usize) -> Result<bool> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    let Some((magazine_id, _)) = magazine_row(&tx, user_id, machine_id, index)? else {
+        return Ok(false);
+    };
+    let before = load_magazines(&tx, user_id, machine_id)?
+        .into_iter()
+        .nth(index);
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(machine_id, before.as_ref(), None)?.at(index, None),
+    )?;
+    tx.execute("DELETE FROM magazines WHERE id = ?1", [magazine_id])?;
+    tx.execute(
+        "UPDATE magazines SET position = position - 1
+         WHERE user_id = ?1 AND machine_id = ?2 AND position > ?3",
+        params![user_id, machine_id, index],
+    )?;
+    bump_machine_revision(&tx, user_id, machine_id)?;
+    tx.commit()?;
+    Ok(true)
+}
+
+pub fn list_slots(username: &str, machine_id: &str, index: usize) -> Result<Option<Vec<Slot>>> {
+    let conn = connection();
+    let user_id = user_id(&conn, username)?;
+    match magazine_row(&conn, user_id, machine_id, index)? {
+        Some((magazine_id, _)) => load_slots(&conn, magazine_id).map(Some),
+        None => Ok(None),
+    }
+}
+
+pub fn get_slot(
+    username: &str,
+    machine_id: &str,
+    index: usize,
+    slot_index: usize,
+) -> Result<Option<Slot>> {
+    Ok(
+        list_slots(username, machine_id, index)?
+            .and_then(|slots| slots.into_iter().nth(slot_index)),
+    )
+}
+
+/// What became of a slot update.
+#[derive(Debug, PartialEq)]
+pub enum SlotUpdate {
+    Updated(Box<Slot>),
+    /// The slot is reserved. Only unlocking it is allowed, its contents stay as they are.
+    Locked,
+}
+
+pub fn update_slot(
+    username: &str,
+    machine_id: &str,
+    index: usize,
+    slot_index: usize,
+    slot: &Slot,
+) -> Result<Option<SlotUpdate>> {
+    let mut conn = connection();
+    let tx = conn.transaction()?;
+    let user_id = user_id(&tx, username)?;
+    let Some((magazine_id, capacity)) = magazine_row(&tx, user_id, machine_id, index)? else {
+        return Ok(None);
+    };
+    if slot_index >= capacity {
+        return Ok(None);
+    }
+    let before = load_slots(&tx, magazine_id)?.into_iter().nth(slot_index);
+    if before
+        .as_ref()
+        .is_some_and(|before| before.locked && contents(before) != contents(slot))
+    {
+        return Ok(Some(SlotUpdate::Locked));
+    }
+    let mut slot = slot.clone();
+    slot.touch(username);
+    tx.execute(
+        "UPDATE slots SET content = ?1 WHERE magazine_id = ?2 AND slot_index = ?3",
+        params![to_json(&slot)?, magazine_id, slot_index],
+    )?;
+    bump_slot_revision(&tx, magazine_id, slot_index)?;
+    audit::record(
+        &tx,
+        user_id,
+        username,
+        Change::new(machine_id, before.as_ref(), Some(&slot))?.at(index, Some(slot_index)),
+    )?;
+    tx.commit()?;
+    Ok(Some(SlotUpdate::Updated(Box::new(slot))))
+}
+
+/// The slot without whether it is reserved and who changed it last.
+fn contents(slot: &Slot) -> Slot {
+    Slot {
+        locked: false,
+        last_changed_at: None,
+        last_changed_by: None,
+        ..slot.clone()
+    }
+}
+
+pub fn clear_slot(
+    username: &str,
+    machine_id: &str,
+    index: usize,
+    slot_index: usize,
+) -> Result<Option<SlotUpdate>> {
+    update_slot(username, machine_id, index, slot_index, &Slot::default())
+}
@@ -0,0 +1,254 @@
+use super::{
+    audit::{self, Change},
+    connection,
+    machines::{
+        bump_machine_revision, bump_slot_revision, find_machine, insert_machine_rows,
+        insert_magazine_rows, load_machines, load_slots, magazine_row, to_json, user_id,
+        write_capabilities,
+    },
+};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use shared::{
+    machine::Machine,
+    slot::Slot,
+    sync::{RevisedMachine, Revision, SyncOperation, SyncOutcome},
+};
+
+/// The user's machines with the revisions of each machine and slot.
+pub fn list(username: &str) -> Result<Vec<RevisedMachine>> {
+    let conn = connection();
+    let user_id = user_id(&conn, username)?;
+    load_machines(&conn, user_id)?
+        .into_iter()
+        .map(|machine| with_revisions(&conn, user_id, machine))
+        .collect()
+}
+
+/// Applies each operation in its own transaction, in order. Once an operation on a machine
+/// is refused, the ones after it on the same machine are refused as well, they were made
+/// on top of it.
+pub fn apply(username: &str, operations: &[SyncOperation]) -> Result<Vec<SyncOutcome>> {
+    let mut conn = connection();
+    let user_id = user_id(&conn, username)?;
+    let mut refused: Vec<&str> = vec![];
+    let mut outcomes = vec![];
+    for operation in operations {
+        let machine_id = operation.machine_id();
+        let outcome = if refused.contains(&machine_id) {
+            None
+        } else {
+            let tx = conn.transaction()?;
+            let applied = apply_one(&tx, user_id, username, operation)?;
+            tx.commit()?;
+            applied
+        };
+        outcomes.push(match outcome {
+            Some(revision) => SyncOutcome::Applied { revision },
+            None => {
+                refused.push(machine_id);
+                SyncOutcome::Conflict {
+                    current: find_revised(&conn, user_id, machine_id)?.map(Box::new),
+                }
+            }
+        });
+    }
+    Ok(outcomes)
+}
+
+fn with_revisions(conn: &Connection, user_id: i64, machine: Machine) -> Result<RevisedMachine> {
+    let revision = conn.query_row(
+        "SELECT revision FROM machines WHERE user_id = ?1 AND id = ?2",
+        params![user_id, machine.uuid],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(
+        "SELECT magazines.position, slots.revision FROM slots
+         JOIN magazines ON magazines.id = slots.magazine_id
+         WHERE magazines.user_id = ?1 AND magazines.machine_id = ?2
+         ORDER BY magazines.position, slots.slot_index",
+    )?;
+    let mut slot_revisions = vec![vec![]; machine.magazines.len()];
+    let rows = stmt.query_map(params![user_id, machine.uuid], |row| {
+        Ok((row.get::<_, usize>(0)?, row.get::<_, Revision>(1)?))
+    })?;
+    for row in rows {
+        let (magazine, revision) = row?;
+        slot_revisions[magazine].push(revision);
+    }
+    Ok(RevisedMachine {
+        machine,
+        revision,
+        slot_revisions,
+    })
+}
+
+fn find_revised(
+    conn: &Connection,
+    user_id: i64,
+    machine_id: &str,
+) -> Result<Option<RevisedMachine>> {
+    find_machine(conn, user_id, machine_id)?
+        .map(|machine| with_revisions(conn, user_id, machine))
+        .transpose()
+}
+
+/// The new revision, or `None` when the operation is refused.
+fn apply_one(
+    conn: &Connection,
+    user_id: i64,
+    username: &str,
+    operation: &SyncOperation,
+) -> Result<Option<Revision>> {
+    match operation {
+        SyncOperation::CreateMachine { machine } => {
+            let taken: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM machines WHERE user_id = ?1 AND id = ?2",
+                params![user_id, machine.uuid],
+                |row| row.get(0),
+            )?;
+            if taken {
+                return Ok(None);
+            }
+            insert_machine_rows(conn, user_id, machine)?;
+            write_capabilities(conn, user_id, machine)?;
+            let created = find_machine(conn, user_id, &machine.uuid)?;
+            audit::record(
+                conn,
+                user_id,
+                username,
+                Change::new(&machine.uuid, None, created.as_ref())?,
+            )?;
+            Ok(Some(0))
+        }
+        SyncOperation::UpdateMachine { machine, expected } => {
+            let Some(before) = find_revised(conn, user_id, &machine.uuid)? else {
+                return Ok(None);
+            };
+            if before.revision != *expected {
+                return Ok(None);
+            }
+            reshape(conn, user_id, machine)?;
+            let revision = bump_machine_revision(conn, user_id, &machine.uuid)?;
+            let after = find_machine(conn, user_id, &machine.uuid)?;
+            audit::record(
+                conn,
+                user_id,
+                username,
+                Change::new(&machine.uuid, Some(&before.machine), after.as_ref())?,
+            )?;
+            Ok(Some(revision))
+        }
+        SyncOperation::DeleteMachine {
+            machine_id,
+            expected,
+            expected_slots,
+        } => {
+            let Some(before) = find_revised(conn, user_id, machine_id)? else {
+                return Ok(None);
+            };
+            if before.revision != *expected || before.slot_revisions != *expected_slots {
+                return Ok(None);
+            }
+            audit::record(
+                conn,
+                user_id,
+                username,
+                Change::new(machine_id, Some(&before.machine), None)?,
+            )?;
+            conn.execute(
+                "DELETE FROM machines WHERE id = ?1 AND user_id = ?2",
+                params![machine_id, user_id],
+            )?;
+            Ok(Some(before.revision))
+        }
+        SyncOperation::UpdateSlot {
+            machine_id,
+            address,
+            slot,
+            expected,
+        } => {
+            let Some((magazine_id, _)) = magazine_row(conn, user_id, machine_id, address.magazine)?
+            else {
+                return Ok(None);
+            };
+            let current: Option<Revision> = conn
+                .query_row(
+                    "SELECT revision FROM slots WHERE magazine_id = ?1 AND slot_index = ?2",
+                    params![magazine_id, address.slot],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if current != Some(*expected) {
+                return Ok(None);
+            }
+            let before = load_slots(conn, magazine_id)?.into_iter().nth(address.slot);
+            // The slot keeps who changed it and when, which happened offline on the client
+            conn.execute(
+                "UPDATE slots SET content = ?1 WHERE magazine_id = ?2 AND slot_index = ?3",
+                params![to_json(slot)?, magazine_id, address.slot],
+            )?;
+            let revision = bump_slot_revision(conn, magazine_id, address.slot)?;
+            audit::record(
+                conn,
+                user_id,
+                username,
+                Change::new(machine_id, before.as_ref(), Some(slot))?
+                    .at(address.magazine, Some(address.slot)),
+            )?;
+            Ok(Some(revision))
+        }
+    }
+}
+
+/// Sets the fields and magazines of a machine. Magazines are renamed and resized in place,
+/// new ones start empty and the ones past the end are removed.
+fn reshape(conn: &Connection, user_id: i64, machine: &Machine) -> Result<()> {
+    conn.execute(
+        "UPDATE machines SET name = ?1, manufacturer = ?2, model = ?3, description = ?4,
+            capabilities = ?5
+         WHERE id = ?6 AND user_id = ?7",
+        params![
+            machine.name,
+            machine.manufacturer,
+            machine.model,
+            machine.description.text,
+            to_json(&machine.capabilities)?,
+            machine.uuid,
+            user_id
+        ],
+    )?;
+    for (position, magazine) in machine.magazines.iter().enumerate() {
+        match magazine_row(conn, user_id, &machine.uuid, position)? {
+            Some((magazine_id, capacity)) => {
+                conn.execute(
+                    "UPDATE magazines SET name = ?1, capacity = ?2 WHERE id = ?3",
+                    params![magazine.name, magazine.capacity, magazine_id],
+                )?;
+                conn.execute(
+                    "DELETE FROM slots WHERE magazine_id = ?1 AND slot_index >= ?2",
+                    params![magazine_id, magazine.capacity],
+                )?;
+                for slot_index in capacity..magazine.capacity {
+                    conn.execute(
+                        "INSERT INTO slots (magazine_id, slot_index, content) VALUES (?1, ?2, ?3)",
+                        params![magazine_id, slot_index, to_json(&Slot::default())?],
+                    )?;
+                }
+            }
+            None => {
+                let mut empty = magazine.clone();
+                empty.slots.clear();
+                insert_magazine_rows(conn, user_id, &machine.uuid, position, &empty)?;
+            }
+        }
+        conn.execute(
+            "UPDATE magazines SET kind = ?1 WHERE user_id = ?2 AND machine_id = ?3 AND position = ?4",
+            params![to_json(&magazine.kind)?, user_id, machine.uuid, position],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM magazines WHERE user_id = ?1 AND machine_id = ?2 AND position >= ?3",
+        params![user_id, machine.uuid, machine.magazines.len()],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,31 @@
+pub mod api;
+pub mod config;
+pub mod db;
+pub mod session;
+
+use crate::api::{audit, auth, machines, sync};
+use actix_web::web;
+
+/// Registers every endpoint, for the server and for tests that run it in-process.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(auth::login)
+        .service(auth::register_user)
+        .service(auth::update_user_data)
+        .service(machines::list_machines)
+        .service(machines::create_machine)
+        .service(machines::get_machine)
+        .service(machines::update_machine)
+        .service(machines::delete_machine)
+        .service(machines::list_magazines)
+        .service(machines::create_magazine)
+        .service(machines::get_magazine)
+        .service(machines::update_magazine)
+        .service(machines::delete_magazine)
+        .service(machines::list_slots)
+        .service(machines::get_slot)
+        .service(machines::update_slot)
+        .service(machines::clear_slot)
+        .service(audit::list_entries)
+        .service(sync::list_revisions)
+        .service(sync::apply_operations);
+}
@@ -1,43 +1,52 @@
-mod api;
-mod db;
-
-use crate::api::auth;
 use actix_cors::Cors;
 use actix_web::{App, HttpServer};
-use db::*;
+use backend::{config::Config, db::init_db};
 use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    init_db().expect("Failed to initialize database");
-
-    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
-    builder
-        .set_private_key_file(
-            "/etc/letsencrypt/live/rasmushogslatt.com/privkey.pem",
-            SslFiletype::PEM,
-        )
-        .unwrap();
+    dotenv::dotenv().ok();
+    env_logger::init();
+    let config = Config::load()?;
+    if let Err(e) = init_db(&config.database_path) {
+        log::error!("Refusing to start: {}", e);
+        return Err(std::io::Error::new(std::io::ErrorKind::Other, e));
+    }
 
-    builder
-        .set_certificate_chain_file("/etc/letsencrypt/live/rasmushogslatt.com/fullchain.pem")
-        .unwrap();
+    let cors_origins = config.cors_origins.clone();
+    let server = HttpServer::new(move || {
+        let mut cors = Cors::default().allow_any_method().allow_any_header();
+        if cors_origins.is_empty() {
+            cors = cors.allow_any_origin();
+        }
+        for origin in &cors_origins {
+            cors = cors.allowed_origin(origin);
+        }
 
-    HttpServer::new(|| {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header();
+        App::new().wrap(cors).configure(backend::configure)
+    });
 
-        App::new()
-            .wrap(cors)
-            .service(auth::login)
-            .service(auth::register_user)
-            .service(auth::update_user_data)
-    })
-    .bind_openssl("[::]:443", builder)?
-    .run()
-    .await
+    let server = match &config.tls {
+        Some(tls) => {
+            let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+            builder
+                .set_private_key_file(&tls.key_path, SslFiletype::PEM)
+                .unwrap();
+            builder.set_certificate_chain_file(&tls.cert_path).unwrap();
+            server.bind_openssl(&config.listen_addr, builder)?
+        }
+        None => server.bind(&config.listen_addr)?,
+    };
+    log::info!(
+        "Listening on {} ({})",
+        config.listen_addr,
+        if config.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    );
+    server.run().await
 }
 
 //
@@ -0,0 +1,271 @@
+use actix_web::{
+    dev::Payload, http::header, http::StatusCode, FromRequest, HttpRequest, HttpResponse,
+    ResponseError,
+};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::future::{ready, Ready};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DEFAULT_TTL_SECONDS: i64 = 12 * 60 * 60;
+
+lazy_static! {
+    static ref SESSION_KEYS: SessionKeys = SessionKeys::from_env();
+}
+
+struct SessionKeys {
+    secret: Vec<u8>,
+    ttl_seconds: i64,
+}
+
+impl SessionKeys {
+    fn from_env() -> Self {
+        let secret = match std::env::var("SESSION_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+            _ => {
+                log::warn!("SESSION_SECRET is not set, sessions will not survive a restart");
+                let mut secret = vec![0u8; 32];
+                OsRng.fill_bytes(&mut secret);
+                secret
+            }
+        };
+        let ttl_seconds = std::env::var("SESSION_TTL_SECONDS")
+            .ok()
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECONDS);
+        Self {
+            secret,
+            ttl_seconds,
+        }
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String, // username
+    pub iat: i64,    // issued at, unix seconds
+    pub exp: i64,    // expires at, unix seconds
+}
+
+pub struct SessionToken {
+    pub token: String,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenError {
+    Missing,
+    Malformed,
+    InvalidSignature,
+    Expired,
+}
+
+impl TokenError {
+    fn code(&self) -> &'static str {
+        match self {
+            TokenError::Missing => "token_missing",
+            TokenError::Malformed => "token_malformed",
+            TokenError::InvalidSignature => "token_invalid",
+            TokenError::Expired => "token_expired",
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::Missing => write!(f, "Missing session token"),
+            TokenError::Malformed => write!(f, "Malformed session token"),
+            TokenError::InvalidSignature => write!(f, "Invalid session token"),
+            TokenError::Expired => write!(f, "Session token has expired"),
+        }
+    }
+}
+
+impl ResponseError for TokenError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .json(serde_json::json!({
+                "status": "error",
+                "code": self.code(),
+                "message": self.to_string()
+            }))
+    }
+}
+
+/// Issues a signed token of the form `<claims>.<signature>`, both base64url encoded.
+pub fn issue_token(username: &str) -> SessionToken {
+    let keys = &*SESSION_KEYS;
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        sub: username.to_string(),
+        iat: now,
+        exp: now + keys.ttl_seconds,
+    };
+    SessionToken {
+        token: sign(&claims),
+        expires_at: claims.exp,
+    }
+}
+
+fn sign(claims: &Claims) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap());
+    let mut mac = SESSION_KEYS.mac();
+    mac.update(payload.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}.{}", payload, signature)
+}
+
+pub fn validate_token(token: &str) -> Result<Claims, TokenError> {
+    let (payload, signature) = token.split_once('.').ok_or(TokenError::Malformed)?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| TokenError::Malformed)?;
+
+    let mut mac = SESSION_KEYS.mac();
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| TokenError::InvalidSignature)?;
+
+    let claims: Claims = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or(TokenError::Malformed)?;
+
+    if claims.exp <= Utc::now().timestamp() {
+        return Err(TokenError::Expired);
+    }
+    Ok(claims)
+}
+
+/// Extractor for endpoints that require a valid `Authorization: Bearer <token>` header.
+pub struct AuthenticatedUser {
+    pub username: String,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = TokenError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        ready(match token {
+            Some(token) => validate_token(token.trim()).map(|claims| AuthenticatedUser {
+                username: claims.sub,
+            }),
+            None => Err(TokenError::Missing),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{body::to_bytes, test::TestRequest};
+
+    async fn extract(request: TestRequest) -> Result<AuthenticatedUser, TokenError> {
+        let request = request.to_http_request();
+        AuthenticatedUser::from_request(&request, &mut Payload::None).await
+    }
+
+    #[test]
+    fn issued_tokens_validate() {
+        let issued = issue_token("ada");
+        let claims = validate_token(&issued.token).unwrap();
+        assert_eq!(claims.sub, "ada");
+        assert_eq!(claims.exp, issued.expires_at);
+        assert_eq!(claims.exp - claims.iat, SESSION_KEYS.ttl_seconds);
+    }
+
+    #[actix_web::test]
+    async fn expired_tokens_get_a_structured_401() {
+        let now = Utc::now().timestamp();
+        let token = sign(&Claims {
+            sub: "ada".to_string(),
+            iat: now - 20,
+            exp: now - 10,
+        });
+        let error = validate_token(&token).unwrap_err();
+        assert_eq!(error, TokenError::Expired);
+
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["code"], "token_expired");
+        assert_eq!(body["message"], "Session token has expired");
+    }
+
+    #[test]
+    fn tampered_tokens_are_refused() {
+        let token = issue_token("ada").token;
+        let (payload, signature) = token.split_once('.').unwrap();
+
+        let claims = Claims {
+            sub: "root".to_string(),
+            ..validate_token(&token).unwrap()
+        };
+        let forged = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        assert_eq!(
+            validate_token(&format!("{}.{}", forged, signature)).unwrap_err(),
+            TokenError::InvalidSignature
+        );
+
+        let mut other_signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        other_signature[0] ^= 1;
+        let other_signature = URL_SAFE_NO_PAD.encode(other_signature);
+        assert_eq!(
+            validate_token(&format!("{}.{}", payload, other_signature)).unwrap_err(),
+            TokenError::InvalidSignature
+        );
+    }
+
+    #[actix_web::test]
+    async fn the_authorization_header_needs_a_bearer_token() {
+        assert_eq!(
+            extract(TestRequest::default()).await.err(),
+            Some(TokenError::Missing)
+        );
+        for (value, error) in [
+            ("Basic YWRhOnNlY3JldA==", TokenError::Missing),
+            ("Bearer", TokenError::Missing),
+            ("Bearer not-a-token", TokenError::Malformed),
+            ("Bearer abc.not base64", TokenError::Malformed),
+        ] {
+            let request = TestRequest::default().insert_header((header::AUTHORIZATION, value));
+            assert_eq!(extract(request).await.err(), Some(error), "{}", value);
+        }
+
+        let token = issue_token("ada").token;
+        let request = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+        assert_eq!(extract(request).await.unwrap().username, "ada");
+    }
+}
@@ -0,0 +1,17 @@
+use backend::db::{self, InitError};
+
+#[test]
+fn the_database_is_opened_once() {
+    let path = std::env::temp_dir().join(format!("backend-init-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    db::init_db(&path).unwrap();
+
+    let other = path.with_extension("other.db");
+    assert!(matches!(
+        db::init_db(&other),
+        Err(InitError::AlreadyInitialized)
+    ));
+    assert!(!other.exists());
+    // The first database is still in use
+    db::add_user("once", "secret", "").unwrap();
+}
@@ -0,0 +1,104 @@
+use backend::db::machines::{
+    add_capabilities, add_revisions, create_tables, key_machines_by_user, load_machines,
+    move_machines_out_of_user_data,
+};
+use rusqlite::{params, Connection};
+use shared::{slot::Slot, upgrade::user_data_from_json};
+
+const V0_WITH_MACHINE: &str =
+    include_str!("../../shared/tests/fixtures/user_data_v0_with_machine.json");
+
+/// The users table and machine tables as migration 3 found them.
+fn database(user_data: &str) -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (
+            id INTEGER PRIMARY KEY,
+            username TEXT NOT NULL UNIQUE,
+            password TEXT NOT NULL,
+            user_data TEXT NOT NULL
+        )",
+    )
+    .unwrap();
+    create_tables(&conn).unwrap();
+    conn.execute(
+        "INSERT INTO users (username, password, user_data) VALUES ('old', '', ?1)",
+        params![user_data],
+    )
+    .unwrap();
+    conn
+}
+
+#[test]
+fn v0_machines_move_into_their_tables() {
+    let conn = database(V0_WITH_MACHINE);
+    move_machines_out_of_user_data(&conn).unwrap();
+
+    let (machine_id, name): (String, String) = conn
+        .query_row("SELECT id, name FROM machines", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+    assert!(uuid::Uuid::parse_str(&machine_id).is_ok());
+    assert_eq!(name, "DMU 50");
+    let slots: Vec<Slot> = {
+        let mut stmt = conn
+            .prepare("SELECT content FROM slots ORDER BY slot_index")
+            .unwrap();
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0)).unwrap();
+        rows.map(|content| serde_json::from_str(&content.unwrap()).unwrap())
+            .collect()
+    };
+    assert_eq!(slots.len(), 3);
+    assert_eq!(slots[0].tool_number.as_deref(), Some("T1"));
+    assert!(slots[2].is_empty());
+
+    // The rest of the blob is left for the upgrade on read
+    let user_data: String = conn
+        .query_row("SELECT user_data FROM users", [], |row| row.get(0))
+        .unwrap();
+    let user_data = user_data_from_json(&user_data).unwrap();
+    assert!(user_data.machines.is_empty());
+    assert!(user_data.settings.dark_mode);
+}
+
+#[test]
+fn an_unreadable_blob_fails_the_migration() {
+    let conn = database(r#"{"machines": [{"name": "No magazines"}]}"#);
+    assert!(move_machines_out_of_user_data(&conn).is_err());
+}
+
+#[test]
+fn keying_machines_by_user_keeps_magazines_and_slots() {
+    let conn = database(V0_WITH_MACHINE);
+    conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+    move_machines_out_of_user_data(&conn).unwrap();
+    add_capabilities(&conn).unwrap();
+    add_revisions(&conn).unwrap();
+    let machine_id: String = conn
+        .query_row("SELECT id FROM machines", [], |row| row.get(0))
+        .unwrap();
+
+    key_machines_by_user(&conn).unwrap();
+
+    let machines = load_machines(&conn, 1).unwrap();
+    assert_eq!(machines.len(), 1);
+    assert_eq!(machines[0].uuid, machine_id);
+    assert_eq!(machines[0].magazines[0].slots.len(), 3);
+    assert_eq!(
+        machines[0].magazines[0].slots[0].tool_number.as_deref(),
+        Some("T1")
+    );
+    // Another user may have a machine with the same id
+    conn.execute(
+        "INSERT INTO users (username, password, user_data) VALUES ('new', '', '{}')",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO machines (id, user_id, position, name, manufacturer, model, description)
+         VALUES (?1, 2, 0, '', '', '', '')",
+        [&machine_id],
+    )
+    .unwrap();
+}
@@ -0,0 +1,364 @@
+use actix_web::{http::StatusCode, test, App};
+use backend::{db, session::issue_token};
+use shared::{machine::Machine, magazine::Magazine, slot::Slot};
+use std::sync::Once;
+
+static INIT: Once = Once::new();
+
+/// A fresh database shared by the tests in this file, each test with users of its own.
+fn user(username: &str) -> String {
+    INIT.call_once(|| {
+        let path = std::env::temp_dir().join(format!("backend-machines-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        db::init_db(&path).unwrap();
+    });
+    db::add_user(username, "secret", "").unwrap();
+    format!("Bearer {}", issue_token(username).token)
+}
+
+fn machine(uuid: &str) -> Machine {
+    Machine {
+        uuid: uuid.to_string(),
+        name: uuid.to_string(),
+        magazine_count: 1,
+        magazines: vec![Magazine::new(0, 3)],
+        ..Machine::default()
+    }
+}
+
+fn numbered(tool_number: &str) -> Slot {
+    Slot {
+        tool_number: Some(tool_number.to_string()),
+        ..Slot::default()
+    }
+}
+
+macro_rules! service {
+    () => {
+        test::init_service(App::new().configure(backend::configure)).await
+    };
+}
+
+/// Sends a request with the token and returns its status.
+macro_rules! status {
+    ($app:expr, $request:expr, $token:expr) => {{
+        let request = $request
+            .insert_header(("Authorization", $token.as_str()))
+            .to_request();
+        test::call_service(&$app, request).await.status()
+    }};
+}
+
+/// Sends a request with the token and reads the JSON it answers with.
+macro_rules! read {
+    ($app:expr, $request:expr, $token:expr) => {{
+        let request = $request
+            .insert_header(("Authorization", $token.as_str()))
+            .to_request();
+        test::call_and_read_body_json(&$app, request).await
+    }};
+}
+
+#[actix_web::test]
+async fn machines_are_created_updated_and_deleted() {
+    let token = user("machine-owner");
+    let app = service!();
+    let uri = "/api/machines/round-trip-mill";
+
+    let created: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("round-trip-mill")),
+        token
+    );
+    assert_eq!(created.name, "round-trip-mill");
+    assert_eq!(created.magazines.len(), 1);
+    assert_eq!(
+        status!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/machines")
+                .set_json(machine("round-trip-mill")),
+            token
+        ),
+        StatusCode::CONFLICT
+    );
+
+    let mut renamed = machine("round-trip-mill");
+    renamed.name = "Mill".to_string();
+    renamed.manufacturer = "Haas".to_string();
+    let updated: Machine = read!(
+        app,
+        test::TestRequest::put().uri(uri).set_json(&renamed),
+        token
+    );
+    assert_eq!(updated.name, "Mill");
+    assert_eq!(updated.manufacturer, "Haas");
+    let listed: Vec<Machine> = read!(app, test::TestRequest::get().uri("/api/machines"), token);
+    assert_eq!(listed, [updated.clone()]);
+    let fetched: Machine = read!(app, test::TestRequest::get().uri(uri), token);
+    assert_eq!(fetched, updated);
+
+    assert_eq!(
+        status!(app, test::TestRequest::delete().uri(uri), token),
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        status!(app, test::TestRequest::get().uri(uri), token),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        status!(app, test::TestRequest::delete().uri(uri), token),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn magazines_are_created_updated_and_deleted() {
+    let token = user("magazine-owner");
+    let app = service!();
+    let uri = "/api/machines/magazine-mill/magazines";
+    let _: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("magazine-mill")),
+        token
+    );
+
+    let created: Magazine = read!(
+        app,
+        test::TestRequest::post()
+            .uri(uri)
+            .set_json(Magazine::new(7, 2)),
+        token
+    );
+    // The server puts it at the end
+    assert_eq!(created.index_in_machine, 1);
+    assert_eq!(created.slots.len(), 2);
+
+    let mut resized = created.clone();
+    resized.name = "Chain".to_string();
+    resized.capacity = 4;
+    let updated: Magazine = read!(
+        app,
+        test::TestRequest::put()
+            .uri(&format!("{uri}/1"))
+            .set_json(&resized),
+        token
+    );
+    assert_eq!(updated.name, "Chain");
+    assert_eq!(updated.slots, vec![Slot::default(); 4]);
+    let fetched: Magazine = read!(
+        app,
+        test::TestRequest::get().uri(&format!("{uri}/1")),
+        token
+    );
+    assert_eq!(fetched, updated);
+
+    assert_eq!(
+        status!(
+            app,
+            test::TestRequest::delete().uri(&format!("{uri}/0")),
+            token
+        ),
+        StatusCode::NO_CONTENT
+    );
+    // The magazines after it move down
+    let listed: Vec<Magazine> = read!(app, test::TestRequest::get().uri(uri), token);
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "Chain");
+    assert_eq!(
+        status!(
+            app,
+            test::TestRequest::delete().uri(&format!("{uri}/1")),
+            token
+        ),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn slots_are_updated_and_cleared() {
+    let token = user("slot-owner");
+    let app = service!();
+    let uri = "/api/machines/slot-mill/magazines/0/slots";
+    let _: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("slot-mill")),
+        token
+    );
+
+    let updated: Slot = read!(
+        app,
+        test::TestRequest::put()
+            .uri(&format!("{uri}/2"))
+            .set_json(numbered("T3")),
+        token
+    );
+    assert_eq!(updated.tool_number.as_deref(), Some("T3"));
+    assert_eq!(updated.last_changed_by.as_deref(), Some("slot-owner"));
+    let listed: Vec<Slot> = read!(app, test::TestRequest::get().uri(uri), token);
+    assert_eq!(listed.len(), 3);
+    assert_eq!(listed[2], updated);
+
+    let cleared: Slot = read!(
+        app,
+        test::TestRequest::delete().uri(&format!("{uri}/2")),
+        token
+    );
+    assert!(cleared.is_empty());
+    let fetched: Slot = read!(
+        app,
+        test::TestRequest::get().uri(&format!("{uri}/2")),
+        token
+    );
+    assert!(fetched.is_empty());
+
+    assert_eq!(
+        status!(
+            app,
+            test::TestRequest::put()
+                .uri(&format!("{uri}/3"))
+                .set_json(numbered("T4")),
+            token
+        ),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn machines_of_other_users_are_out_of_reach() {
+    let owner = user("reach-owner");
+    let other = user("reach-other");
+    let app = service!();
+    let created: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("private-mill")),
+        owner
+    );
+
+    let listed: Vec<Machine> = read!(app, test::TestRequest::get().uri("/api/machines"), other);
+    assert!(listed.is_empty());
+    let uri = "/api/machines/private-mill";
+    let requests = [
+        test::TestRequest::get().uri(uri),
+        test::TestRequest::put()
+            .uri(uri)
+            .set_json(machine("private-mill")),
+        test::TestRequest::delete().uri(uri),
+        test::TestRequest::get().uri(&format!("{uri}/magazines")),
+        test::TestRequest::post()
+            .uri(&format!("{uri}/magazines"))
+            .set_json(Magazine::new(1, 2)),
+        test::TestRequest::put()
+            .uri(&format!("{uri}/magazines/0"))
+            .set_json(Magazine::new(0, 1)),
+        test::TestRequest::delete().uri(&format!("{uri}/magazines/0")),
+        test::TestRequest::get().uri(&format!("{uri}/magazines/0/slots/0")),
+        test::TestRequest::put()
+            .uri(&format!("{uri}/magazines/0/slots/0"))
+            .set_json(numbered("T1")),
+        test::TestRequest::delete().uri(&format!("{uri}/magazines/0/slots/0")),
+    ];
+    for request in requests {
+        assert_eq!(status!(app, request, other), StatusCode::NOT_FOUND);
+    }
+
+    let fetched: Machine = read!(app, test::TestRequest::get().uri(uri), owner);
+    assert_eq!(fetched, created);
+}
+
+#[actix_web::test]
+async fn reserved_slots_only_change_once_unlocked() {
+    let token = user("reserved-owner");
+    let app = service!();
+    let uri = "/api/machines/reserved-mill/magazines/0/slots/0";
+    let _: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("reserved-mill")),
+        token
+    );
+    let reserved = Slot {
+        locked: true,
+        ..numbered("T1")
+    };
+    let _: Slot = read!(
+        app,
+        test::TestRequest::put().uri(uri).set_json(&reserved),
+        token
+    );
+
+    for request in [
+        test::TestRequest::put().uri(uri).set_json(Slot {
+            locked: true,
+            ..numbered("T2")
+        }),
+        test::TestRequest::delete().uri(uri),
+    ] {
+        assert_eq!(status!(app, request, token), StatusCode::CONFLICT);
+    }
+    let fetched: Slot = read!(app, test::TestRequest::get().uri(uri), token);
+    assert_eq!(fetched.tool_number.as_deref(), Some("T1"));
+
+    let unlocked: Slot = read!(
+        app,
+        test::TestRequest::put().uri(uri).set_json(numbered("T1")),
+        token
+    );
+    assert!(!unlocked.locked);
+    let cleared: Slot = read!(app, test::TestRequest::delete().uri(uri), token);
+    assert!(cleared.is_empty());
+}
+
+#[actix_web::test]
+async fn machine_ids_are_only_unique_per_user() {
+    let owner = user("id-owner");
+    let other = user("id-other");
+    let app = service!();
+    let uri = "/api/machines/same-id-mill";
+    let created: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("same-id-mill")),
+        owner
+    );
+
+    // Taking the id tells nothing about the owner's machine
+    let _: Machine = read!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/machines")
+            .set_json(machine("same-id-mill")),
+        other
+    );
+    let mut renamed = machine("same-id-mill");
+    renamed.name = "Other Mill".to_string();
+    let _: Machine = read!(
+        app,
+        test::TestRequest::put().uri(uri).set_json(&renamed),
+        other
+    );
+    let _: Slot = read!(
+        app,
+        test::TestRequest::put()
+            .uri(&format!("{uri}/magazines/0/slots/0"))
+            .set_json(numbered("T7")),
+        other
+    );
+    assert_eq!(
+        status!(app, test::TestRequest::delete().uri(uri), other),
+        StatusCode::NO_CONTENT
+    );
+
+    let fetched: Machine = read!(app, test::TestRequest::get().uri(uri), owner);
+    assert_eq!(fetched, created);
+}
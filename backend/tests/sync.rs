@@ -0,0 +1,225 @@
+use actix_web::{http::StatusCode, test, App};
+use backend::{db, session::issue_token};
+use shared::{
+    machine::Machine,
+    magazine::Magazine,
+    placement::SlotAddress,
+    slot::Slot,
+    sync::{RevisedMachine, SyncOperation, SyncOutcome},
+};
+use std::sync::Once;
+
+static INIT: Once = Once::new();
+
+/// A fresh database shared by the tests in this file, each test with users of its own.
+fn user(username: &str) -> String {
+    INIT.call_once(|| {
+        let path = std::env::temp_dir().join(format!("backend-sync-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        db::init_db(&path).unwrap();
+    });
+    db::add_user(username, "secret", "").unwrap();
+    format!("Bearer {}", issue_token(username).token)
+}
+
+fn machine(uuid: &str) -> Machine {
+    Machine {
+        uuid: uuid.to_string(),
+        name: uuid.to_string(),
+        magazine_count: 1,
+        magazines: vec![Magazine::new(0, 3)],
+        ..Machine::default()
+    }
+}
+
+fn numbered(tool_number: &str) -> Slot {
+    Slot {
+        tool_number: Some(tool_number.to_string()),
+        ..Slot::default()
+    }
+}
+
+macro_rules! service {
+    () => {
+        test::init_service(App::new().configure(backend::configure)).await
+    };
+}
+
+macro_rules! revisions {
+    ($app:expr, $token:expr) => {{
+        let request = test::TestRequest::get()
+            .uri("/api/sync")
+            .insert_header(("Authorization", $token.as_str()))
+            .to_request();
+        let machines: Vec<RevisedMachine> = test::call_and_read_body_json(&$app, request).await;
+        machines
+    }};
+}
+
+macro_rules! sync {
+    ($app:expr, $token:expr, $operations:expr) => {{
+        let request = test::TestRequest::post()
+            .uri("/api/sync")
+            .insert_header(("Authorization", $token.as_str()))
+            .set_json($operations)
+            .to_request();
+        let outcomes: Vec<SyncOutcome> = test::call_and_read_body_json(&$app, request).await;
+        outcomes
+    }};
+}
+
+#[actix_web::test]
+async fn edits_raise_the_revision_of_what_they_change() {
+    let token = user("revisions");
+    let app = service!();
+
+    let outcomes = sync!(
+        app,
+        token,
+        vec![SyncOperation::CreateMachine {
+            machine: Box::new(machine("revisions-mill")),
+        }]
+    );
+    assert_eq!(outcomes, [SyncOutcome::Applied { revision: 0 }]);
+
+    let request = test::TestRequest::put()
+        .uri("/api/machines/revisions-mill/magazines/0/slots/1")
+        .insert_header(("Authorization", token.as_str()))
+        .set_json(numbered("T2"))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status(),
+        StatusCode::OK
+    );
+    let mut renamed = machine("revisions-mill");
+    renamed.name = "Mill".to_string();
+    let request = test::TestRequest::put()
+        .uri("/api/machines/revisions-mill")
+        .insert_header(("Authorization", token.as_str()))
+        .set_json(&renamed)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status(),
+        StatusCode::OK
+    );
+
+    let machines = revisions!(app, token);
+    assert_eq!(machines[0].machine.name, "Mill");
+    assert_eq!(machines[0].revision, 1);
+    assert_eq!(machines[0].slot_revisions, [[0, 1, 0]]);
+}
+
+#[actix_web::test]
+async fn stale_revisions_are_refused_with_the_current_machine() {
+    let token = user("stale");
+    let app = service!();
+    sync!(
+        app,
+        token,
+        vec![SyncOperation::CreateMachine {
+            machine: Box::new(machine("stale-mill")),
+        }]
+    );
+
+    let slot = |tool_number: &str, expected| SyncOperation::UpdateSlot {
+        machine_id: "stale-mill".to_string(),
+        address: SlotAddress::new(0, 0),
+        slot: Box::new(numbered(tool_number)),
+        expected,
+    };
+    let outcomes = sync!(app, token, vec![slot("T1", 0), slot("T9", 0)]);
+    assert_eq!(outcomes[0], SyncOutcome::Applied { revision: 1 });
+    let SyncOutcome::Conflict {
+        current: Some(current),
+    } = &outcomes[1]
+    else {
+        panic!("expected a conflict, got {:?}", outcomes[1]);
+    };
+    assert_eq!(current.slot_revisions, [[1, 0, 0]]);
+    assert_eq!(current.machine.magazines[0].slots[0], numbered("T1"));
+
+    // Once refused, the rest of the machine's changes are refused too
+    let outcomes = sync!(
+        app,
+        token,
+        vec![
+            slot("T9", 0),
+            SyncOperation::UpdateMachine {
+                machine: Box::new(machine("stale-mill")),
+                expected: 0,
+            },
+        ]
+    );
+    assert!(outcomes
+        .iter()
+        .all(|outcome| matches!(outcome, SyncOutcome::Conflict { .. })));
+
+    // Another account cannot reach the machine
+    let other = user("stale-other");
+    let outcomes = sync!(app, other, vec![slot("T7", 1)]);
+    assert_eq!(outcomes, [SyncOutcome::Conflict { current: None }]);
+    let request = test::TestRequest::get().uri("/api/sync").to_request();
+    assert_eq!(
+        test::call_service(&app, request).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_web::test]
+async fn machines_are_reshaped_and_deleted_at_the_expected_revision() {
+    let token = user("reshape");
+    let app = service!();
+    let mut mill = machine("reshape-mill");
+    mill.magazines[0].slots[2] = numbered("T3");
+    sync!(
+        app,
+        token,
+        vec![SyncOperation::CreateMachine {
+            machine: Box::new(mill)
+        }]
+    );
+
+    let mut reshaped = machine("reshape-mill");
+    reshaped.magazines[0].capacity = 2;
+    reshaped.magazines.push(Magazine::new(1, 4));
+    reshaped.magazine_count = 2;
+    for magazine in &mut reshaped.magazines {
+        magazine.slots.clear();
+    }
+    let outcomes = sync!(
+        app,
+        token,
+        vec![SyncOperation::UpdateMachine {
+            machine: Box::new(reshaped),
+            expected: 0,
+        }]
+    );
+    assert_eq!(outcomes, [SyncOutcome::Applied { revision: 1 }]);
+
+    let machines = revisions!(app, token);
+    let magazines = &machines[0].machine.magazines;
+    assert_eq!(magazines.len(), 2);
+    assert_eq!(magazines[0].slots, vec![Slot::default(); 2]);
+    assert_eq!(magazines[1].slots, vec![Slot::default(); 4]);
+    assert_eq!(machines[0].slot_revisions, [vec![0, 0], vec![0, 0, 0, 0]]);
+
+    let delete = |expected, expected_slots: &Vec<Vec<u64>>| SyncOperation::DeleteMachine {
+        machine_id: "reshape-mill".to_string(),
+        expected,
+        expected_slots: expected_slots.clone(),
+    };
+    let mut changed_slot = machines[0].slot_revisions.clone();
+    changed_slot[1][3] = 1;
+    let outcomes = sync!(
+        app,
+        token,
+        vec![
+            delete(1, &changed_slot),
+            delete(0, &machines[0].slot_revisions)
+        ]
+    );
+    assert!(matches!(outcomes[0], SyncOutcome::Conflict { .. }));
+    let outcomes = sync!(app, token, vec![delete(1, &machines[0].slot_revisions)]);
+    assert_eq!(outcomes, [SyncOutcome::Applied { revision: 1 }]);
+    assert!(revisions!(app, token).is_empty());
+}
@@ -1,33 +1,58 @@
 use crate::app_states::{AppState, CentralViewState, OpenWindows, WidgetState};
-#[cfg(target_arch = "wasm32")]
-use crate::database_interactions::*;
 use crate::singletons::Singletons;
+use crate::user_store::{self, UserStore};
+use crate::widgets::add_holder::AddHolderWindow;
 use crate::widgets::add_machine::AddMachineWindow;
+use crate::widgets::add_tool::AddToolWindow;
+use crate::widgets::audit_log::AuditLogWindow;
+use crate::widgets::backup_window::BackupWindow;
 use crate::widgets::delete_machine::DeleteMachineWindow;
 use crate::widgets::edit_machine::EditMachineWindow;
+use crate::widgets::filter_window::FilterWindow;
 use crate::widgets::gripper_fixed_widget::LatheBarGripperFixedWindow;
 use crate::widgets::gripper_widget::LatheBarGripperWindow;
+use crate::widgets::holder_inventory::HolderInventoryWindow;
+use crate::widgets::magazine_widget::MagazineWidget;
 use crate::widgets::settings_window::SettingsWindow;
 use crate::widgets::sign_in::SignInWidget;
 use crate::widgets::sign_up::{show_status, SignUpWidget};
+use crate::widgets::spreadsheet_window::SpreadsheetWindow;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::widgets::sync_window::SyncWindow;
+use crate::widgets::tool_library::ToolLibraryWindow;
 use crate::widgets::unit_conversion::UnitConversionWindow;
 use crate::widgets::welcome::WelcomeWidget;
-#[cfg(target_arch = "wasm32")]
-use shared::settings::*;
 
 use egui::*;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use shared::custom_traits::*;
+use shared::history::History;
+use shared::machine::Machine;
+use shared::saving::SaveTracker;
 use shared::*;
 use std::future::Future;
+use std::sync::Arc;
+
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+/// Edits closer together than this, in seconds, that change the same thing are undone as one
+const MERGE_EDITS_WITHIN: f64 = 1.0;
 
 #[derive(Deserialize, Serialize)]
 #[serde(default)]
 pub struct Application {
+    #[serde(with = "stored_user")]
     pub user: User,
+    pub session_token: Option<String>,
+    /// The machines as the backend last acknowledged them
+    pub synced_machines: Vec<Machine>,
     #[serde(skip)]
     pub client: Client,
+    /// Where accounts are signed in to and user data is saved
+    #[serde(skip, default = "user_store::default_store")]
+    pub store: Arc<dyn UserStore>,
     pub login_status: bool,
     pub registration_status: bool,
     pub app_state: AppState,
@@ -35,20 +60,78 @@ pub struct Application {
     pub widget_state: WidgetState,
     pub open_windows: OpenWindows,
     pub singletons: Singletons,
+    /// Edits of the user data that can be undone, kept until they are saved
+    pub history: History,
+    /// The user data as the history last saw it
+    #[serde(skip)]
+    pub history_baseline: UserData,
+    /// Input time of the last recorded edit
+    #[serde(skip)]
+    pub last_edit_at: Option<f64>,
+    #[serde(skip)]
+    pub history_error: Option<String>,
+    /// Whether the edits reached the store
+    #[serde(skip)]
+    pub saves: SaveTracker,
+    /// Asking whether to sign out with unsaved changes
+    #[serde(skip)]
+    pub confirm_sign_out: bool,
+    #[serde(skip)]
+    pub sign_out_when_saved: bool,
+    /// Why the state of the last run could not be restored
+    #[serde(skip)]
+    pub restore_error: Option<String>,
+}
+
+/// Keeps the user as JSON in the stored app state, so user data stored by an older version
+/// is upgraded on the way in, see [`upgrade`].
+mod stored_user {
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+    use shared::{upgrade::upgrade_user_data, User};
+
+    pub fn serialize<S: Serializer>(user: &User, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = serde_json::to_string(user).map_err(S::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<User, D::Error> {
+        let json = String::deserialize(deserializer)?;
+        let mut value: serde_json::Value = serde_json::from_str(&json).map_err(D::Error::custom)?;
+        let user_data = value
+            .as_object_mut()
+            .and_then(|user| user.remove("user_data"))
+            .unwrap_or_default();
+        let user_data = upgrade_user_data(user_data).map_err(D::Error::custom)?;
+        value["user_data"] =
+            serde_json::to_value(shared::UserData::default()).map_err(D::Error::custom)?;
+        let user = serde_json::from_value(value).map_err(D::Error::custom)?;
+        Ok(User { user_data, ..user })
+    }
 }
 
 impl Default for Application {
     fn default() -> Self {
         Self {
             user: User::default(),
+            session_token: None,
+            synced_machines: vec![],
             login_status: false,
             registration_status: false,
             client: Client::new(),
+            store: user_store::default_store(),
             app_state: AppState::WelcomePage,
             widget_state: WidgetState::Default,
             open_windows: OpenWindows::default(),
             singletons: Singletons::default(),
             central_view_state: CentralViewState::default(),
+            history: History::default(),
+            history_baseline: UserData::default(),
+            last_edit_at: None,
+            history_error: None,
+            saves: SaveTracker::default(),
+            confirm_sign_out: false,
+            sign_out_when_saved: false,
+            restore_error: None,
         }
     }
 }
@@ -70,11 +153,30 @@ where
 }
 
 impl Application {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+    pub fn new(cc: &eframe::CreationContext<'_>, store: Arc<dyn UserStore>) -> Self {
+        let stored = cc
+            .storage
+            .and_then(|storage| storage.get_string(eframe::APP_KEY));
+        let restore_error = match stored.map(|stored| ron::from_str::<Self>(&stored)) {
+            Some(Ok(mut app)) => {
+                app.history_baseline = app.user.user_data.clone();
+                app.store = store;
+                return app;
+            }
+            Some(Err(e)) => {
+                log::error!("Restoring the last session failed: {}", e);
+                Some(format!(
+                    "The last session could not be restored, please sign in again ({})",
+                    e
+                ))
+            }
+            None => None,
+        };
+        Self {
+            store,
+            restore_error,
+            ..Default::default()
         }
-        Default::default()
     }
 }
 
@@ -85,9 +187,20 @@ impl eframe::App for Application {
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if self.singletons.should_save_user_data {
-            self.save_to_database(ctx);
+            self.saves.edited(ctx.input(|i| i.time));
             self.singletons.should_save_user_data = false;
         }
+        self.poll_saves(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll_sync(ctx);
+        // Text fields keep their own undo
+        if self.app_state == AppState::Application && !ctx.wants_keyboard_input() {
+            if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+                self.redo(ctx);
+            } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+                self.undo(ctx);
+            }
+        }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 egui::widgets::global_dark_light_mode_switch(ui);
@@ -95,6 +208,17 @@ impl eframe::App for Application {
                     self.widget_state = WidgetState::Settings;
                     self.open_windows.settings_window_open = true;
                 }
+                if self.app_state == AppState::Application {
+                    self.history_buttons(ui);
+                    ui.separator();
+                    self.save_status_ui(ui);
+                }
+                if let Some(error) = &self.restore_error {
+                    ui.colored_label(Color32::RED, error);
+                    if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                        self.restore_error = None;
+                    }
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     // User sign in/up
                     self.auth_combobox(ui);
@@ -113,6 +237,21 @@ impl eframe::App for Application {
                             self.widget_state = WidgetState::EditMachine;
                             self.open_windows.edit_machine_window_open = true;
                         }
+                        // Add tool and holder to the current machine
+                        if self.user.user_data.selections.selected_machine.is_some() {
+                            if ui.button("Add Holder").clicked() {
+                                self.widget_state = WidgetState::AddHolder;
+                                self.open_windows.add_holder_window = true;
+                            }
+                            if ui.button("Add Tool").clicked() {
+                                self.widget_state = WidgetState::AddTool;
+                                self.open_windows.add_tool_window = true;
+                            }
+                            if ui.button("CSV").clicked() {
+                                self.widget_state = WidgetState::Spreadsheet;
+                                self.open_windows.spreadsheet_window_open = true;
+                            }
+                        }
                         // Select machine
                         self.machines_combobox(ui);
                         // Add machine
@@ -133,6 +272,31 @@ impl eframe::App for Application {
                             self.widget_state = WidgetState::UnitConversion;
                             self.open_windows.unit_conversion_window = true;
                         }
+                        if ui.button("Filter").clicked() {
+                            self.widget_state = WidgetState::Filter;
+                            self.open_windows.filter_window_open = true;
+                        }
+                        if ui.button("Tool Library").clicked() {
+                            self.widget_state = WidgetState::ToolLibrary;
+                            self.open_windows.tool_library_window_open = true;
+                        }
+                        if ui.button("Holder Inventory").clicked() {
+                            self.widget_state = WidgetState::HolderInventory;
+                            self.open_windows.holder_inventory_window_open = true;
+                        }
+                        if ui.button("Audit Log").clicked() {
+                            self.widget_state = WidgetState::AuditLog;
+                            self.open_windows.audit_log_window_open = true;
+                        }
+                        if ui.button("Backup").clicked() {
+                            self.widget_state = WidgetState::Backup;
+                            self.open_windows.backup_window_open = true;
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui.button("Sync").clicked() {
+                            self.widget_state = WidgetState::Sync;
+                            self.open_windows.sync_window_open = true;
+                        }
 
                         /* Add the windows */
                         let mut unit_conversion_window =
@@ -147,6 +311,57 @@ impl eframe::App for Application {
                         );
                         settings_window.show(ctx, &mut self.open_windows.settings_window_open);
 
+                        let mut filter_window = FilterWindow::new(
+                            &mut self.user,
+                            &mut self.singletons,
+                            &mut self.widget_state,
+                        );
+                        filter_window.show(ctx, &mut self.open_windows.filter_window_open);
+
+                        let selected_machine = self.user.user_data.selections.selected_machine;
+                        let mut tool_library_window = ToolLibraryWindow::new(
+                            &mut self.user,
+                            &mut self.singletons,
+                            &mut self.widget_state,
+                            selected_machine,
+                        );
+                        tool_library_window
+                            .show(ctx, &mut self.open_windows.tool_library_window_open);
+
+                        let mut holder_inventory_window = HolderInventoryWindow::new(
+                            &mut self.user,
+                            &mut self.singletons,
+                            &mut self.widget_state,
+                            selected_machine,
+                        );
+                        holder_inventory_window
+                            .show(ctx, &mut self.open_windows.holder_inventory_window_open);
+
+                        let mut audit_log_window = AuditLogWindow::new(
+                            &self.user,
+                            &mut self.singletons,
+                            &mut self.widget_state,
+                            self.session_token.clone(),
+                            self.store.clone(),
+                        );
+                        audit_log_window.show(ctx, &mut self.open_windows.audit_log_window_open);
+
+                        let mut backup_window = BackupWindow::new(
+                            &mut self.user,
+                            &mut self.singletons,
+                            &mut self.widget_state,
+                        );
+                        backup_window.show(ctx, &mut self.open_windows.backup_window_open);
+
+                        #[cfg(not(target_arch = "wasm32"))]
+                        SyncWindow::new(
+                            &mut self.user,
+                            &mut self.singletons,
+                            &mut self.widget_state,
+                            self.client.clone(),
+                        )
+                        .show(ctx, &mut self.open_windows.sync_window_open);
+
                         let mut add_machine_window = AddMachineWindow::new(
                             &mut self.user,
                             &mut self.singletons,
@@ -182,6 +397,34 @@ impl eframe::App for Application {
                                 .show(ctx, &mut self.open_windows.edit_machine_window_open);
                         }
 
+                        if let Some(machine_index) = self.user.user_data.selections.selected_machine
+                        {
+                            let mut add_tool_window = AddToolWindow::new(
+                                &mut self.user,
+                                &mut self.singletons,
+                                &mut self.widget_state,
+                                machine_index,
+                            );
+                            add_tool_window.show(ctx, &mut self.open_windows.add_tool_window);
+
+                            let mut add_holder_window = AddHolderWindow::new(
+                                &mut self.user,
+                                &mut self.singletons,
+                                &mut self.widget_state,
+                                machine_index,
+                            );
+                            add_holder_window.show(ctx, &mut self.open_windows.add_holder_window);
+
+                            let mut spreadsheet_window = SpreadsheetWindow::new(
+                                &mut self.user,
+                                &mut self.singletons,
+                                &mut self.widget_state,
+                                machine_index,
+                            );
+                            spreadsheet_window
+                                .show(ctx, &mut self.open_windows.spreadsheet_window_open);
+                        }
+
                         if self.open_windows.gripper_window_open {
                             let mut gripper_window = LatheBarGripperWindow::new(
                                 &mut self.singletons.gripper_calculations,
@@ -199,6 +442,9 @@ impl eframe::App for Application {
                 });
             });
         });
+        if self.confirm_sign_out {
+            self.sign_out_prompt(ctx);
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             match self.app_state {
@@ -207,18 +453,22 @@ impl eframe::App for Application {
                 }
                 AppState::SignIn => {
                     SignInWidget::new(&mut self.user, &mut self.app_state, &|user| {
-                        let username = user.username.clone();
-                        let password = user.password.clone();
-                        let client = self.client.clone();
+                        let sign_in = self
+                            .store
+                            .verify_user(user.username.clone(), user.password.clone());
                         let ctx = ctx.clone();
 
                         spawn_task(async move {
-                            match Self::verify_user(username, password, client).await {
-                                Ok(Some(user_data)) => {
+                            match sign_in.await {
+                                Ok(Some(login)) => {
                                     ctx.request_repaint();
                                     ctx.memory_mut(|mem| {
                                         mem.data.insert_temp("login_status".into(), true);
-                                        mem.data.insert_temp("user_data".into(), user_data);
+                                        mem.data.insert_temp("user_data".into(), login.user_data);
+                                        mem.data.insert_temp(
+                                            "session_token".into(),
+                                            login.session_token,
+                                        );
                                     });
                                 }
                                 Ok(None) => {
@@ -237,6 +487,11 @@ impl eframe::App for Application {
                         });
                     })
                     .ui(ui);
+                    if ctx.memory(|mem| mem.data.get_temp::<bool>("session_expired".into()))
+                        == Some(true)
+                    {
+                        ui.colored_label(Color32::RED, "Session expired, please sign in again.");
+                    }
                     let login_status = ctx.memory(|mem| mem.data.get_temp("login_status".into()));
                     show_status(ui, login_status, "Login successful!", "Login failed!");
                     if login_status == Some(true) {
@@ -245,24 +500,36 @@ impl eframe::App for Application {
                         if let Some(user_data) =
                             ctx.memory(|mem| mem.data.get_temp::<UserData>("user_data".into()))
                         {
+                            self.synced_machines = user_data.machines.clone();
                             self.user.user_data = user_data;
                         }
+                        self.reset_history();
+                        self.session_token = ctx
+                            .memory(|mem| {
+                                mem.data.get_temp::<Option<String>>("session_token".into())
+                            })
+                            .flatten();
+                        // The session token stands in for the password from here on
+                        self.user.password.clear();
                         // Remove temporary login status, but keep user data
                         ctx.memory_mut(|mem| {
                             mem.data.remove::<bool>("login_status".into());
+                            mem.data.remove::<bool>("session_expired".into());
+                            mem.data.remove::<Option<String>>("session_token".into());
                         });
                     }
                 }
                 AppState::SignUp => {
                     SignUpWidget::new(&mut self.user, &mut self.app_state, &|user| {
-                        let username = user.username.clone();
-                        let password = user.password.clone();
-                        let email = user.email.clone();
-                        let client = self.client.clone();
+                        let sign_up = self.store.register_user(
+                            user.username.clone(),
+                            user.password.clone(),
+                            user.email.clone(),
+                        );
                         let ctx = ctx.clone();
 
                         spawn_task(async move {
-                            match Self::register_user(username, password, email, client).await {
+                            match sign_up.await {
                                 Ok(is_registered) => {
                                     ctx.request_repaint();
                                     ctx.memory_mut(|mem| {
@@ -296,6 +563,7 @@ impl eframe::App for Application {
                     if registration_status.is_some() && registration_status.unwrap() {
                         self.app_state = AppState::SignIn;
                         self.user = User::default();
+                        self.reset_history();
                         ctx.memory_mut(|mem| mem.data.remove::<bool>("registration_status".into()));
                     }
                 }
@@ -307,21 +575,178 @@ impl eframe::App for Application {
                         self.singletons.should_save_user_data = true;
                     }
 
-                    ui.label("Magazine");
-                    // Show magazine here
+                    match self.central_view_state {
+                        CentralViewState::Magazine => {
+                            MagazineWidget::new(&mut self.user, &mut self.singletons).ui(ui);
+                        }
+                    }
                 }
             };
         });
+        // Every change is recorded in the frame it is made, whether or not it asked for a save
+        if self.app_state == AppState::Application {
+            self.record_frame(ctx.input(|i| i.time));
+        }
     }
 }
 
 impl Application {
     pub fn sign_out(&mut self) {
-        self.singletons.should_save_user_data = true;
+        self.singletons.should_save_user_data = false;
         self.app_state = AppState::WelcomePage;
         self.login_status = false;
         self.registration_status = false;
         self.user = User::default();
+        self.session_token = None;
+        self.synced_machines.clear();
+        self.singletons.audit_entries.clear();
+        self.saves = SaveTracker::default();
+        self.confirm_sign_out = false;
+        self.sign_out_when_saved = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.singletons.sync = Default::default();
+        }
+        self.reset_history();
+    }
+
+    /// Asks what to do with edits that have not been saved yet.
+    fn sign_out_prompt(&mut self, ctx: &Context) {
+        let (mut save, mut discard, mut cancel) = (false, false, false);
+        Window::new("Unsaved Changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                if self.sign_out_when_saved {
+                    ui.label("Signing out once the changes are saved.");
+                    self.save_status_ui(ui);
+                } else {
+                    ui.label("Some changes have not been saved yet.");
+                }
+                ui.horizontal(|ui| {
+                    save = ui
+                        .add_enabled(!self.sign_out_when_saved, Button::new("Save and Sign Out"))
+                        .clicked();
+                    discard = ui.button("Sign Out Without Saving").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if save {
+            self.sign_out_when_saved = true;
+            self.saves.save_now();
+        } else if discard {
+            self.sign_out();
+        } else if cancel {
+            self.confirm_sign_out = false;
+            self.sign_out_when_saved = false;
+        }
+    }
+
+    /// Forgets every edit, for when other user data is loaded.
+    fn reset_history(&mut self) {
+        self.history.clear();
+        self.history_baseline = self.user.user_data.clone();
+        self.last_edit_at = None;
+        self.history_error = None;
+        self.singletons.transfer_error = None;
+    }
+
+    /// Forgets the edits once the store holds them.
+    pub fn forget_saved_edits(&mut self) {
+        self.history.clear();
+        self.last_edit_at = None;
+        self.history_error = None;
+    }
+
+    /// Records the changes of this frame, on their own when asked to be undone separately.
+    fn record_frame(&mut self, now: f64) {
+        if !self.singletons.undo_separately {
+            self.record_edit(now);
+            return;
+        }
+        self.last_edit_at = None;
+        self.record_edit(now);
+        self.last_edit_at = None;
+        self.singletons.undo_separately = false;
+    }
+
+    /// Adds whatever changed in the user data since the last call to the history.
+    fn record_edit(&mut self, now: f64) {
+        let Some(edit) = history::Edit::between(&self.history_baseline, &self.user.user_data)
+        else {
+            return;
+        };
+        if self
+            .last_edit_at
+            .is_some_and(|last| now - last < MERGE_EDITS_WITHIN)
+        {
+            self.history.record_merging(edit);
+        } else {
+            self.history.record(edit);
+        }
+        self.history_baseline = self.user.user_data.clone();
+        self.last_edit_at = Some(now);
+        self.history_error = None;
+    }
+
+    fn undo(&mut self, ctx: &Context) {
+        self.record_edit(ctx.input(|i| i.time));
+        let result = self.history.undo(&mut self.user.user_data);
+        self.after_history_step(result);
+    }
+
+    fn redo(&mut self, ctx: &Context) {
+        self.record_edit(ctx.input(|i| i.time));
+        let result = self.history.redo(&mut self.user.user_data);
+        self.after_history_step(result);
+    }
+
+    fn after_history_step(&mut self, result: Result<(), history::HistoryError>) {
+        if let Err(e) = result {
+            self.history_error = Some(e.to_string());
+            return;
+        }
+        let user_data = &mut self.user.user_data;
+        if let Some(selected) = user_data.selections.selected_machine {
+            if selected >= user_data.machines.len() {
+                user_data.selections.selected_machine = None;
+            }
+        }
+        self.history_baseline = user_data.clone();
+        // The next edit starts a new undo step
+        self.last_edit_at = None;
+        self.history_error = None;
+        self.singletons.should_save_user_data = true;
+    }
+
+    fn history_buttons(&mut self, ui: &mut Ui) {
+        let ctx = ui.ctx().clone();
+        let undo = self.history.next_undo().map(|edit| edit.describe());
+        let undo_button = ui
+            .add_enabled(undo.is_some(), Button::new("⟲ Undo"))
+            .on_hover_text(format!(
+                "{} ({})",
+                undo.unwrap_or_default(),
+                ctx.format_shortcut(&UNDO_SHORTCUT)
+            ));
+        if undo_button.clicked() {
+            self.undo(&ctx);
+        }
+        let redo = self.history.next_redo().map(|edit| edit.describe());
+        let redo_button = ui
+            .add_enabled(redo.is_some(), Button::new("⟳ Redo"))
+            .on_hover_text(format!(
+                "{} ({})",
+                redo.unwrap_or_default(),
+                ctx.format_shortcut(&REDO_SHORTCUT)
+            ));
+        if redo_button.clicked() {
+            self.redo(&ctx);
+        }
+        if let Some(error) = &self.history_error {
+            ui.colored_label(Color32::RED, error);
+        }
     }
 
     pub fn auth_combobox(&mut self, ui: &mut Ui) {
@@ -367,8 +792,11 @@ impl Application {
                     .selected_text(&self.user.username)
                     .show_ui(ui, |ui| {
                         if ui.selectable_label(false, "Sign Out").clicked() {
-                            self.singletons.should_save_user_data = true;
-                            self.sign_out();
+                            if self.saves.is_dirty() {
+                                self.confirm_sign_out = true;
+                            } else {
+                                self.sign_out();
+                            }
                         }
                     });
             }
@@ -14,24 +14,6 @@ pub enum CentralViewState {
     Magazine,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
-pub enum SortState {
-    #[default]
-    Index,
-    Diameter,
-    Degree,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
-pub enum FilterState {
-    #[default]
-    ShowAll,
-    RotatingToolCategory,
-    RotatingHolderCategory,
-    TurningToolCategory,
-    TurningHolderCategory,
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum ToolTypeSelection {
     #[default]
@@ -46,8 +28,9 @@ pub enum HolderTypeSelection {
     Turning,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum WidgetState {
+    #[default]
     Default,
     AddMachine,
     DeleteMachine,
@@ -58,12 +41,13 @@ pub enum WidgetState {
     AddTool,
     AddHolder,
     UnitConversion,
-}
-
-impl Default for WidgetState {
-    fn default() -> Self {
-        WidgetState::Default
-    }
+    Filter,
+    ToolLibrary,
+    HolderInventory,
+    AuditLog,
+    Spreadsheet,
+    Backup,
+    Sync,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -77,6 +61,20 @@ pub struct OpenWindows {
     pub add_tool_window: bool,
     pub add_holder_window: bool,
     pub unit_conversion_window: bool,
+    #[serde(default)]
+    pub filter_window_open: bool,
+    #[serde(default)]
+    pub tool_library_window_open: bool,
+    #[serde(default)]
+    pub holder_inventory_window_open: bool,
+    #[serde(default)]
+    pub audit_log_window_open: bool,
+    #[serde(default)]
+    pub spreadsheet_window_open: bool,
+    #[serde(default)]
+    pub backup_window_open: bool,
+    #[serde(default)]
+    pub sync_window_open: bool,
 }
 
 impl OpenWindows {
@@ -90,5 +88,12 @@ impl OpenWindows {
         self.gripper_window_open = false;
         self.settings_window_open = false;
         self.unit_conversion_window = false;
+        self.filter_window_open = false;
+        self.tool_library_window_open = false;
+        self.holder_inventory_window_open = false;
+        self.audit_log_window_open = false;
+        self.spreadsheet_window_open = false;
+        self.backup_window_open = false;
+        self.sync_window_open = false;
     }
 }
@@ -1,19 +1,26 @@
 // database_interactions.rs
 
 use crate::app::Application;
-use egui::Context;
-use reqwest::Client;
-use shared::{User, UserData};
+use crate::app_states::AppState;
+use crate::user_store::StoreError;
+use egui::{Context, Ui};
+use shared::{machine::Machine, saving::SaveStatus};
 use std::future::Future;
+use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
-use rusqlite::{Connection, OptionalExtension, Result as SqliteResult};
+use rusqlite::{Connection, Result as SqliteResult};
+#[cfg(not(target_arch = "wasm32"))]
+use shared::migrations::{run_migrations, Migration, MigrationError};
 #[cfg(not(target_arch = "wasm32"))]
 use std::sync::Once;
 
 #[cfg(not(target_arch = "wasm32"))]
 static INIT: Once = Once::new();
 
+/// The backend the web build talks to, and the server native builds sync with by default.
+pub const API_URL: &str = "https://rasmushogslatt.com";
+
 #[cfg(target_arch = "wasm32")]
 pub fn spawn_task<F: Future<Output = ()> + 'static>(future: F) {
     wasm_bindgen_futures::spawn_local(future);
@@ -31,23 +38,46 @@ where
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-pub fn init_local_db() -> SqliteResult<()> {
+const LOCAL_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create users table",
+        up: create_users_table,
+    },
+    Migration {
+        version: 2,
+        description: "create table of what was last synced with the server",
+        up: crate::sync::create_sync_bases_table,
+    },
+];
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_local_db() -> Result<(), MigrationError> {
+    let mut result = Ok(());
     INIT.call_once(|| {
-        let conn = Connection::open("local_users.db").expect("Failed to open database");
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS users (
-                id INTEGER PRIMARY KEY,
-                username TEXT NOT NULL UNIQUE,
-                password TEXT NOT NULL,
-                user_data TEXT NOT NULL,
-                email TEXT,
-                created_at TEXT,
-                last_login TEXT
-            )",
-            [],
-        )
-        .expect("Failed to create users table");
+        result = Connection::open("local_users.db")
+            .map_err(MigrationError::from)
+            .and_then(|mut conn| run_migrations(&mut conn, LOCAL_MIGRATIONS))
+            .map(|_| ());
     });
+    result
+}
+
+// Existing databases already have this table, hence IF NOT EXISTS.
+#[cfg(not(target_arch = "wasm32"))]
+fn create_users_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY,
+            username TEXT NOT NULL UNIQUE,
+            password TEXT NOT NULL,
+            user_data TEXT NOT NULL,
+            email TEXT,
+            created_at TEXT,
+            last_login TEXT
+        )",
+        [],
+    )?;
     Ok(())
 }
 
@@ -56,159 +86,117 @@ pub fn get_db_connection() -> Connection {
     Connection::open("local_users.db").expect("Failed to open database")
 }
 
+/// How a save started by [`Application::save_to_database`] ended.
+#[derive(Debug, Clone)]
+pub enum SaveOutcome {
+    /// Holds the machines that were saved
+    Saved(Vec<Machine>),
+    Failed(String),
+    /// Holds the machines that were saved before it failed
+    PartlySaved(Vec<Machine>, String),
+    SessionExpired,
+}
+
 impl Application {
-    pub async fn verify_user(
-        username: String,
-        password: String,
-        client: Client,
-    ) -> Result<Option<UserData>, Box<dyn std::error::Error>> {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            // Use local SQLite database for verification
-            let conn = get_db_connection();
-            let mut stmt =
-                conn.prepare("SELECT user_data FROM users WHERE username = ? AND password = ?")?;
-            let user_data: Option<String> = stmt
-                .query_row([&username, &password], |row| row.get(0))
-                .optional()?;
-
-            if let Some(user_data) = user_data {
-                let user_data: UserData = serde_json::from_str(&user_data)?;
-                Ok(Some(user_data))
-            } else {
-                Ok(None)
-            }
-        }
+    /// Saves everything edited so far, [`Application::poll_saves`] handles how it ends.
+    pub fn save_to_database(&mut self, ctx: &Context) {
+        let user = self.user.clone();
+        let machines = user.user_data.machines.clone();
+        let synced_machines = self.synced_machines.clone();
+        let session_token = self.session_token.clone();
+        let save = self
+            .store
+            .update_user_data(user, synced_machines, session_token);
+        let ctx = ctx.clone();
+        self.saves.started();
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            let user = User {
-                username,
-                password,
-                email: "".to_string(),
-                created_at: None,
-                last_login: None,
-                user_data: UserData::default(),
+        spawn_task(async move {
+            let outcome = match save.await {
+                Ok(true) => SaveOutcome::Saved(machines),
+                Ok(false) => SaveOutcome::Failed("The save was refused".to_string()),
+                Err(StoreError::SessionExpired) => SaveOutcome::SessionExpired,
+                Err(StoreError::PartlySaved {
+                    synced_machines,
+                    error,
+                }) => SaveOutcome::PartlySaved(synced_machines, error.to_string()),
+                Err(e) => SaveOutcome::Failed(e.to_string()),
             };
-            let response = client
-                .post("https://rasmushogslatt.com/api/login")
-                .json(&user)
-                .send()
-                .await?;
-
-            if response.status().is_success() {
-                let json: serde_json::Value = response.json().await?;
-                println!("Login response JSON: {:?}", json);
-
-                if json["status"] == "success" {
-                    let user_data: UserData = serde_json::from_value(json["user_data"].clone())?;
-                    println!("Parsed user data: {:?}", user_data);
-                    Ok(Some(user_data))
-                } else {
-                    println!("Login failed with status: {:?}", json["status"]);
-                    Ok(None)
-                }
-            } else {
-                println!("Login failed with status code: {:?}", response.status());
-                Ok(None)
-            }
-        }
+            ctx.request_repaint();
+            ctx.memory_mut(|mem| mem.data.insert_temp("save_outcome".into(), outcome));
+        });
     }
 
-    pub async fn update_user_data(
-        user: User,
-        client: Client,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            // Use local SQLite database for updating user data
-            let conn = get_db_connection();
-            let user_data_json = serde_json::to_string(&user.user_data)?;
-            conn.execute(
-                "UPDATE users SET user_data = ?, last_login = datetime('now') WHERE username = ?",
-                [&user_data_json, &user.username],
-            )?;
-            Ok(true)
-        }
-
-        #[cfg(target_arch = "wasm32")]
-        {
-            let response = client
-                .post("https://rasmushogslatt.com/api/update_user_data")
-                .json(&user)
-                .send()
-                .await?;
-
-            if response.status().is_success() {
-                Ok(true)
-            } else {
-                let error_message = response.text().await?;
-                println!("Failed to update user data: {:?}", error_message);
-                Ok(false)
+    /// Handles the outcome of the last save and starts the next one once it is due.
+    pub fn poll_saves(&mut self, ctx: &Context) {
+        let now = ctx.input(|i| i.time);
+        let outcome = ctx.memory(|mem| mem.data.get_temp::<SaveOutcome>("save_outcome".into()));
+        if let Some(outcome) = outcome {
+            ctx.memory_mut(|mem| mem.data.remove::<SaveOutcome>("save_outcome".into()));
+            // Saves still on their way at sign out end after it
+            if self.saves.is_saving() {
+                match outcome {
+                    SaveOutcome::Saved(machines) => {
+                        self.synced_machines = machines;
+                        self.saves.succeeded();
+                        // Undo reaches back to the last successful save. Edits made while
+                        // saving wait for the next one.
+                        if !self.saves.is_dirty() {
+                            self.forget_saved_edits();
+                        }
+                    }
+                    SaveOutcome::Failed(error) => self.saves.failed(now, error),
+                    SaveOutcome::PartlySaved(machines, error) => {
+                        self.synced_machines = machines;
+                        self.saves.failed(now, error);
+                    }
+                    SaveOutcome::SessionExpired => {
+                        self.sign_out();
+                        self.app_state = AppState::SignIn;
+                        ctx.memory_mut(|mem| mem.data.insert_temp("session_expired".into(), true));
+                        return;
+                    }
+                }
             }
         }
-    }
 
-    pub async fn register_user(
-        username: String,
-        password: String,
-        email: String,
-        client: Client,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            // Use local SQLite database for user registration
-            let conn = get_db_connection();
-            let user_data = UserData::default();
-            let user_data_json = serde_json::to_string(&user_data)?;
-            conn.execute(
-                "INSERT INTO users (username, password, email, user_data, created_at, last_login) 
-                 VALUES (?, ?, ?, ?, datetime('now'), datetime('now'))",
-                [&username, &password, &email, &user_data_json],
-            )?;
-            Ok(true)
+        if self.app_state != AppState::Application {
+            return;
         }
-
-        #[cfg(target_arch = "wasm32")]
-        {
-            let user = User {
-                username,
-                password,
-                email,
-                created_at: None,
-                last_login: None,
-                user_data: UserData::default(),
-            };
-            let response = client
-                .post("https://rasmushogslatt.com/api/register")
-                .json(&user)
-                .send()
-                .await?;
-
-            Ok(response.status().is_success())
+        if self.saves.is_due(now) {
+            self.save_to_database(ctx);
+        } else if let Some(due_at) = self.saves.due_at() {
+            ctx.request_repaint_after(Duration::from_secs_f64(due_at - now));
+        }
+        if self.sign_out_when_saved && !self.saves.is_dirty() {
+            self.sign_out();
         }
     }
 
-    pub fn save_to_database(&self, ctx: &Context) {
-        let user = self.user.clone();
-        let client = self.client.clone();
-        let ctx = ctx.clone();
-
-        spawn_task(async move {
-            match Application::update_user_data(user, client).await {
-                Ok(true) => {
-                    ctx.request_repaint();
-                    ctx.memory_mut(|mem| {
-                        mem.data.insert_temp("update_status".into(), true);
-                    });
-                }
-                _ => {
-                    ctx.request_repaint();
-                    ctx.memory_mut(|mem| {
-                        mem.data.insert_temp("update_status".into(), false);
-                    });
+    /// Whether the last edits reached the store, with a retry button when they did not.
+    pub fn save_status_ui(&mut self, ui: &mut Ui) {
+        let now = ui.input(|i| i.time);
+        match self.saves.status() {
+            SaveStatus::Saved => {
+                ui.label("✔ Saved");
+            }
+            SaveStatus::Pending => {
+                ui.label("Unsaved changes");
+            }
+            SaveStatus::Saving => {
+                ui.spinner();
+                ui.label("Saving…");
+            }
+            SaveStatus::Failed { error, retry_at } => {
+                ui.colored_label(ui.visuals().error_fg_color, "Save failed")
+                    .on_hover_text(format!(
+                        "{}\nTrying again in {:.0} s",
+                        error,
+                        (retry_at - now).max(0.0)
+                    ));
+                if ui.button("Retry").clicked() {
+                    self.saves.save_now();
                 }
             }
-        });
+        }
     }
 }
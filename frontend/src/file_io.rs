@@ -0,0 +1,113 @@
+//! Text files in and out of the application. Native builds read and write paths on disk,
+//! the web build downloads files and lets the user pick one in the browser.
+
+use egui::Context;
+
+/// Where the result of [`open_file`] is left for [`take_opened_file`].
+fn memory_id(id: &str) -> egui::Id {
+    egui::Id::new(("opened_file", id))
+}
+
+/// The text of a file the user dropped onto the application this frame.
+pub fn dropped_file(ctx: &Context) -> Option<Result<String, String>> {
+    let file = ctx.input(|i| i.raw.dropped_files.first().cloned())?;
+    // The web gives the content, native builds the path
+    let bytes = match (file.bytes, &file.path) {
+        (Some(bytes), _) => Ok(bytes.to_vec()),
+        (None, Some(path)) => std::fs::read(path).map_err(|e| e.to_string()),
+        (None, None) => Err("The dropped file could not be read".to_string()),
+    };
+    Some(bytes.and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string())))
+}
+
+/// Writes `contents` to the file at `name`, or downloads it as `name` on the web.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(name: &str, contents: &str) -> Result<(), String> {
+    std::fs::write(name, contents).map_err(|e| format!("Could not write {}: {}", name, e))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(name: &str, contents: &str) -> Result<(), String> {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let blob = web_sys::Blob::new_with_str_sequence(&parts).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+    let link: web_sys::HtmlAnchorElement = document()?
+        .create_element("a")
+        .map_err(js_error)?
+        .unchecked_into();
+    link.set_href(&url);
+    link.set_download(name);
+    link.click();
+    web_sys::Url::revoke_object_url(&url).map_err(js_error)
+}
+
+/// Reads the file at `path`, or asks the user to pick one on the web, where `path` is not
+/// used. The text turns up in [`take_opened_file`] under `id`, on the web a few frames later.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_file(ctx: &Context, id: &str, path: &str) {
+    let result =
+        std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e));
+    ctx.memory_mut(|mem| mem.data.insert_temp(memory_id(id), result));
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn open_file(ctx: &Context, id: &str, _path: &str) {
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    let input: web_sys::HtmlInputElement = match document().and_then(|document| {
+        document
+            .create_element("input")
+            .map_err(js_error)
+            .map(JsCast::unchecked_into)
+    }) {
+        Ok(input) => input,
+        Err(e) => {
+            ctx.memory_mut(|mem| mem.data.insert_temp(memory_id(id), Err::<String, _>(e)));
+            return;
+        }
+    };
+    input.set_type("file");
+
+    let picked = input.clone();
+    let ctx = ctx.clone();
+    let id = id.to_string();
+    let on_change = Closure::once_into_js(move || {
+        let Some(file) = picked.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = wasm_bindgen_futures::JsFuture::from(file.text())
+                .await
+                .map_err(js_error)
+                .and_then(|text| {
+                    text.as_string()
+                        .ok_or_else(|| "The file is not text".to_string())
+                });
+            ctx.request_repaint();
+            ctx.memory_mut(|mem| mem.data.insert_temp(memory_id(&id), result));
+        });
+    });
+    input.set_onchange(Some(on_change.unchecked_ref()));
+    input.click();
+}
+
+/// The text of the file [`open_file`] was asked for under `id`, once it is there.
+pub fn take_opened_file(ctx: &Context, id: &str) -> Option<Result<String, String>> {
+    let result = ctx.memory(|mem| mem.data.get_temp::<Result<String, String>>(memory_id(id)))?;
+    ctx.memory_mut(|mem| mem.data.remove::<Result<String, String>>(memory_id(id)));
+    Some(result)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn document() -> Result<web_sys::Document, String> {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| "No browser document".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(e: wasm_bindgen::JsValue) -> String {
+    e.as_string().unwrap_or_else(|| format!("{:?}", e))
+}
@@ -4,5 +4,9 @@ pub mod app;
 pub use app::Application;
 pub mod app_states;
 pub mod database_interactions;
+pub mod file_io;
 pub mod singletons;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync;
+pub mod user_store;
 pub mod widgets;
@@ -3,8 +3,12 @@
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
+    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+
     // Initialize the local SQLite database
     frontend::database_interactions::init_local_db().expect("Failed to initialize local database");
+    // USER_STORE picks where accounts live, BACKEND_URL which backend the http store uses
+    let store = frontend::user_store::from_env();
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -19,7 +23,7 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "eframe template",
         native_options,
-        Box::new(|cc| Ok(Box::new(frontend::Application::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(frontend::Application::new(cc, store)))),
     )
 }
 
@@ -35,7 +39,12 @@ fn main() {
             .start(
                 "the_canvas_id",
                 web_options,
-                Box::new(|cc| Ok(Box::new(frontend::Application::new(cc)))),
+                Box::new(|cc| {
+                    Ok(Box::new(frontend::Application::new(
+                        cc,
+                        frontend::user_store::from_env(),
+                    )))
+                }),
             )
             .await;
 
@@ -1,17 +1,24 @@
 use serde::{Deserialize, Serialize};
 use shared::{
+    audit::AuditEntry,
     description::Description,
-    holders::holder::{Holder, RotatingHolder, TurningHolder},
+    filter::Filter,
+    holders::holder::{RotatingHolder, TurningHolder},
     machine::Machine,
     magazine::Magazine,
+    placement::SlotAddress,
+    sorting::SlotSort,
     tools::tool::{RotatingTool, Tool, TurningTool},
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::widgets::sync_window::{SyncSettings, SyncState};
 use crate::{
-    app_states::{FilterState, HolderTypeSelection, SortState, ToolTypeSelection},
+    app_states::{HolderTypeSelection, ToolTypeSelection},
     widgets::{
+        audit_log::AuditFilter, backup_window::BackupState, edit_machine::MagazineChange,
         gripper_fixed_widget::GripperFixedCalculationData, gripper_widget::GripperCalculationData,
-        unit_conversion::ConversionData,
+        spreadsheet_window::SpreadsheetState, unit_conversion::ConversionData,
     },
 };
 
@@ -29,9 +36,54 @@ pub struct Singletons {
     pub turning_holder: TurningHolder,
     pub tool_type_selection: ToolTypeSelection,
     pub holder_type_selection: HolderTypeSelection,
-    pub sort_state: SortState,
-    pub filter_state: FilterState,
+    #[serde(default)]
+    pub slot_sort: SlotSort,
+    #[serde(default)]
+    pub active_filter: Filter,
+    /// Name the active filter is saved under
+    #[serde(default)]
+    pub filter_name: String,
     pub conversion_data: ConversionData,
+    /// Slot the tool and holder editors place into
+    #[serde(default)]
+    pub slot_target: SlotAddress,
+    #[serde(default)]
+    pub tool_number: String,
+    #[serde(skip)]
+    pub editor_error: Option<String>,
+    /// Library tool being edited
+    #[serde(skip)]
+    pub library_edit: Option<Tool>,
+    /// Uuid of the library tool waiting for delete confirmation
+    #[serde(skip)]
+    pub library_delete: Option<String>,
+    /// Magazine change waiting for a decision about the tools in it
+    #[serde(skip)]
+    pub magazine_change: Option<MagazineChange>,
+    #[serde(skip)]
+    pub transfer_error: Option<String>,
+    /// This frame's edit gets its own undo step, merging neither into the one before nor
+    /// into the one after
+    #[serde(skip)]
+    pub undo_separately: bool,
+    #[serde(default)]
+    pub audit_filter: AuditFilter,
+    #[serde(skip)]
+    pub audit_entries: Vec<AuditEntry>,
+    #[serde(skip)]
+    pub audit_loading: bool,
+    #[serde(skip)]
+    pub audit_error: Option<String>,
+    #[serde(skip)]
+    pub spreadsheet: SpreadsheetState,
+    #[serde(skip)]
+    pub backup: BackupState,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(default)]
+    pub sync_settings: SyncSettings,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub sync: SyncState,
 }
 
 impl Singletons {
@@ -47,5 +99,6 @@ impl Singletons {
         self.turning_holder = TurningHolder::default();
         self.tool_type_selection = ToolTypeSelection::default();
         self.holder_type_selection = HolderTypeSelection::default();
+        self.transfer_error = None;
     }
 }
@@ -0,0 +1,322 @@
+//! Syncs the machines of a local account with an account on the backend, see
+//! [`shared::sync`]. Native builds only, the web build works on the backend directly.
+//!
+//! What the two sides last agreed on is kept in the local database, so changes made while
+//! the backend cannot be reached are sent with the next sync, even after a restart.
+
+use crate::{
+    app::{spawn_task, Application},
+    app_states::{AppState, WidgetState},
+    database_interactions::get_db_connection,
+    widgets::sync_window::{SyncSettings, SyncState},
+};
+use egui::Context;
+use reqwest::{Client, StatusCode};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use shared::{
+    sync::{self, RevisedMachine, SyncBase, SyncOperation, SyncOutcome, SyncPlan},
+    User,
+};
+
+/// Seconds between syncs while signed in to the server.
+const SYNC_INTERVAL: f64 = 60.0;
+const SIGNED_OUT: &str = "Signed out of the server, sign in again";
+
+pub fn create_sync_bases_table(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_bases (
+            username TEXT PRIMARY KEY,
+            server_username TEXT NOT NULL,
+            base TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// What the local account and the server account last agreed on. Nothing when the local
+/// account was never synced, or was synced with another server account.
+pub fn load_base(username: &str, server_username: &str) -> Result<SyncBase, String> {
+    let conn = get_db_connection();
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT server_username, base FROM sync_bases WHERE username = ?1",
+            [username],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match row {
+        Some((synced_with, base)) if synced_with == server_username => {
+            serde_json::from_str(&base).map_err(|e| e.to_string())
+        }
+        _ => Ok(SyncBase::default()),
+    }
+}
+
+pub fn store_base(username: &str, server_username: &str, base: &SyncBase) -> Result<(), String> {
+    let base = serde_json::to_string(base).map_err(|e| e.to_string())?;
+    get_db_connection()
+        .execute(
+            "INSERT OR REPLACE INTO sync_bases (username, server_username, base)
+             VALUES (?1, ?2, ?3)",
+            params![username, server_username, base],
+        )
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn error_of(status: StatusCode) -> String {
+    match status {
+        StatusCode::UNAUTHORIZED => SIGNED_OUT.to_string(),
+        status => format!("The server answered {}", status),
+    }
+}
+
+async fn sign_in(
+    client: Client,
+    server_url: String,
+    username: String,
+    password: String,
+) -> Result<String, String> {
+    let user = User {
+        username,
+        password,
+        ..User::default()
+    };
+    let response = client
+        .post(format!("{server_url}/api/login"))
+        .json(&user)
+        .send()
+        .await
+        .map_err(|e| format!("The server cannot be reached: {}", e))?;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Err("Wrong server username or password".to_string());
+    } else if !response.status().is_success() {
+        return Err(error_of(response.status()));
+    }
+    let json: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    json["token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "The server did not hand out a session".to_string())
+}
+
+async fn fetch(
+    client: Client,
+    server_url: String,
+    token: String,
+) -> Result<Vec<RevisedMachine>, String> {
+    let response = client
+        .get(format!("{server_url}/api/sync"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("The server cannot be reached: {}", e))?;
+    if !response.status().is_success() {
+        return Err(error_of(response.status()));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+async fn push(
+    client: Client,
+    server_url: String,
+    token: String,
+    operations: Vec<SyncOperation>,
+) -> Result<Vec<SyncOutcome>, String> {
+    if operations.is_empty() {
+        return Ok(vec![]);
+    }
+    let response = client
+        .post(format!("{server_url}/api/sync"))
+        .bearer_auth(token)
+        .json(&operations)
+        .send()
+        .await
+        .map_err(|e| format!("The server cannot be reached: {}", e))?;
+    if !response.status().is_success() {
+        return Err(error_of(response.status()));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+pub fn start_sign_in(ctx: &Context, client: &Client, settings: &SyncSettings, password: String) {
+    let client = client.clone();
+    let server_url = settings.server_url.trim_end_matches('/').to_string();
+    let username = settings.server_username.clone();
+    let ctx = ctx.clone();
+    spawn_task(async move {
+        let result = sign_in(client, server_url, username, password).await;
+        ctx.request_repaint();
+        ctx.memory_mut(|mem| mem.data.insert_temp("sync_token".into(), result));
+    });
+}
+
+/// Fetches the server's machines, [`Application::poll_sync`] merges them when they arrive.
+pub fn start_sync(ctx: &Context, client: &Client, settings: &SyncSettings, state: &mut SyncState) {
+    let Some(token) = state.token.clone() else {
+        return;
+    };
+    state.in_flight = true;
+    state.error = None;
+    state.last_sync_at = Some(ctx.input(|i| i.time));
+    let client = client.clone();
+    let server_url = settings.server_url.trim_end_matches('/').to_string();
+    let ctx = ctx.clone();
+    spawn_task(async move {
+        let result = fetch(client, server_url, token).await;
+        ctx.request_repaint();
+        ctx.memory_mut(|mem| mem.data.insert_temp("sync_remote".into(), result));
+    });
+}
+
+/// Takes the merged machines and sends the plan's operations to the server. Returns whether
+/// the local machines changed and need saving.
+pub fn apply(
+    ctx: &Context,
+    client: &Client,
+    user: &mut User,
+    settings: &SyncSettings,
+    state: &mut SyncState,
+    plan: SyncPlan,
+) -> bool {
+    let Some(token) = state.token.clone() else {
+        return false;
+    };
+    let user_data = &mut user.user_data;
+    let before = std::mem::replace(&mut user_data.machines, plan.merged.clone());
+    user_data.tool_library.adopt_loaded(&user_data.machines);
+    for shortage in user_data.holder_inventory.adopt_loaded(&user_data.machines) {
+        log::warn!("After syncing: {}", shortage);
+    }
+    let changed = before != user_data.machines;
+    if let Some(selected) = user_data.selections.selected_machine {
+        if selected >= user_data.machines.len() {
+            user_data.selections.selected_machine = None;
+        }
+    }
+
+    state.in_flight = true;
+    let operations = plan.operations.clone();
+    state.pushing = Some(plan);
+    let client = client.clone();
+    let server_url = settings.server_url.trim_end_matches('/').to_string();
+    let ctx = ctx.clone();
+    spawn_task(async move {
+        let result = push(client, server_url, token, operations).await;
+        ctx.request_repaint();
+        ctx.memory_mut(|mem| mem.data.insert_temp("sync_outcomes".into(), result));
+    });
+    changed
+}
+
+/// Takes a result out of egui's memory, where the sync tasks leave them.
+fn take<T: Clone + Send + Sync + 'static>(ctx: &Context, id: &'static str) -> Option<T> {
+    let result = ctx.memory(|mem| mem.data.get_temp::<T>(id.into()))?;
+    ctx.memory_mut(|mem| mem.data.remove::<T>(id.into()));
+    Some(result)
+}
+
+impl Application {
+    /// Handles what the sync tasks sent back, and syncs again every [`SYNC_INTERVAL`].
+    pub fn poll_sync(&mut self, ctx: &Context) {
+        if self.app_state != AppState::Application {
+            return;
+        }
+        let settings = &self.singletons.sync_settings;
+        let state = &mut self.singletons.sync;
+
+        if let Some(result) = take::<Result<String, String>>(ctx, "sync_token") {
+            match result {
+                Ok(token) => {
+                    state.token = Some(token);
+                    state.message = Some("Signed in to the server".to_string());
+                    state.error = None;
+                    start_sync(ctx, &self.client, settings, state);
+                }
+                Err(e) => state.error = Some(e),
+            }
+        }
+
+        if let Some(result) = take::<Result<Vec<RevisedMachine>, String>>(ctx, "sync_remote") {
+            state.in_flight = false;
+            let base = load_base(&self.user.username, &settings.server_username);
+            match (result, base) {
+                (Ok(remote), Ok(base)) => {
+                    let plan = sync::plan(&base, &self.user.user_data.machines, &remote);
+                    state.base = Some(base);
+                    if plan.conflicts.is_empty() {
+                        if apply(ctx, &self.client, &mut self.user, settings, state, plan) {
+                            self.singletons.transfer_error = None;
+                            self.singletons.should_save_user_data = true;
+                        }
+                    } else {
+                        // Conflicts wait for the user, in the sync window
+                        state.message = None;
+                        state.plan = Some(plan);
+                        self.widget_state = WidgetState::Sync;
+                        self.open_windows.sync_window_open = true;
+                    }
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    if e == SIGNED_OUT {
+                        state.token = None;
+                    }
+                    state.error = Some(format!("{}. Changes are kept until the next sync.", e));
+                }
+            }
+        }
+
+        if let Some(result) = take::<Result<Vec<SyncOutcome>, String>>(ctx, "sync_outcomes") {
+            state.in_flight = false;
+            let (Some(plan), Some(old)) = (state.pushing.take(), state.base.clone()) else {
+                return;
+            };
+            match result {
+                Ok(outcomes) => {
+                    let base = plan.finish(&old, &outcomes);
+                    let refused = outcomes
+                        .iter()
+                        .filter(|outcome| matches!(outcome, SyncOutcome::Conflict { .. }))
+                        .count();
+                    let stored = store_base(&self.user.username, &settings.server_username, &base);
+                    state.base = Some(base);
+                    match stored {
+                        Ok(()) if refused > 0 => {
+                            state.message = None;
+                            state.error = Some(format!(
+                                "{} changes were refused, the server changed meanwhile. \
+                                 They are sent with the next sync.",
+                                refused
+                            ));
+                        }
+                        Ok(()) => {
+                            state.message =
+                                Some(format!("Synced, {} changes sent", plan.operations.len()));
+                            state.error = None;
+                        }
+                        Err(e) => state.error = Some(e),
+                    }
+                }
+                Err(e) => {
+                    state.error = Some(format!("{}. Changes are kept until the next sync.", e))
+                }
+            }
+        }
+
+        let now = ctx.input(|i| i.time);
+        if state.token.is_some()
+            && !state.in_flight
+            && state.plan.is_none()
+            && state
+                .last_sync_at
+                .map_or(true, |last| now - last >= SYNC_INTERVAL)
+        {
+            start_sync(ctx, &self.client, settings, state);
+        }
+        if state.token.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(SYNC_INTERVAL));
+        }
+    }
+}
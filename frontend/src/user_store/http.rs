@@ -0,0 +1,303 @@
+use super::{LoginSuccess, StoreError, StoreFuture, UserStore};
+use reqwest::{Client, StatusCode};
+use shared::{
+    audit::{AuditEntry, AuditQuery},
+    machine::Machine,
+    upgrade::upgrade_user_data,
+    User, UserData,
+};
+
+/// Accounts on the backend at `base_url`, e.g. `https://rasmushogslatt.com`.
+#[derive(Debug, Clone)]
+pub struct HttpStore {
+    client: Client,
+    base_url: String,
+}
+
+impl HttpStore {
+    pub fn new(client: Client, base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        Self { client, base_url }
+    }
+}
+
+impl UserStore for HttpStore {
+    fn verify_user(&self, username: String, password: String) -> StoreFuture<Option<LoginSuccess>> {
+        let Self { client, base_url } = self.clone();
+        Box::pin(async move {
+            let user = User {
+                username,
+                password,
+                ..User::default()
+            };
+            let response = client
+                .post(format!("{base_url}/api/login"))
+                .json(&user)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                log::warn!("Login failed with status code {}", response.status());
+                return Ok(None);
+            }
+            let json: serde_json::Value = response.json().await?;
+            if json["status"] != "success" {
+                log::warn!("Login failed with status {}", json["status"]);
+                return Ok(None);
+            }
+            Ok(Some(LoginSuccess {
+                user_data: upgrade_user_data(json["user_data"].clone())?,
+                session_token: json["token"].as_str().map(str::to_string),
+            }))
+        })
+    }
+
+    fn register_user(
+        &self,
+        username: String,
+        password: String,
+        email: String,
+    ) -> StoreFuture<bool> {
+        let Self { client, base_url } = self.clone();
+        Box::pin(async move {
+            let user = User {
+                username,
+                password,
+                email,
+                created_at: None,
+                last_login: None,
+                user_data: UserData::default(),
+            };
+            let response = client
+                .post(format!("{base_url}/api/register"))
+                .json(&user)
+                .send()
+                .await?;
+            Ok(response.status().is_success())
+        })
+    }
+
+    fn update_user_data(
+        &self,
+        user: User,
+        synced_machines: Vec<Machine>,
+        session_token: Option<String>,
+    ) -> StoreFuture<bool> {
+        let store = self.clone();
+        Box::pin(async move {
+            let Some(session_token) = session_token else {
+                return Err(StoreError::SessionExpired);
+            };
+            let response = store
+                .client
+                .post(format!("{}/api/update_user_data", store.base_url))
+                .bearer_auth(&session_token)
+                .json(&user.user_data)
+                .send()
+                .await?;
+
+            if response.status() == StatusCode::UNAUTHORIZED {
+                return Err(StoreError::SessionExpired);
+            } else if !response.status().is_success() {
+                let status = response.status();
+                let error_message = response.text().await?;
+                return Err(StoreError::Rejected(format!(
+                    "Saving failed with {}: {}",
+                    status, error_message
+                )));
+            }
+
+            let mut acknowledged = synced_machines;
+            let pushed = store
+                .push_machine_changes(&session_token, &mut acknowledged, &user.user_data.machines)
+                .await;
+            match pushed {
+                Ok(()) => Ok(true),
+                Err(StoreError::SessionExpired) => Err(StoreError::SessionExpired),
+                Err(e) => Err(StoreError::PartlySaved {
+                    synced_machines: acknowledged,
+                    error: Box::new(e),
+                }),
+            }
+        })
+    }
+
+    fn fetch_audit_log(
+        &self,
+        query: AuditQuery,
+        session_token: Option<String>,
+    ) -> StoreFuture<Vec<AuditEntry>> {
+        let Self { client, base_url } = self.clone();
+        Box::pin(async move {
+            let Some(session_token) = session_token else {
+                return Err(StoreError::SessionExpired);
+            };
+            let response = client
+                .get(format!("{base_url}/api/audit"))
+                .bearer_auth(&session_token)
+                .query(&query)
+                .send()
+                .await?;
+            match response.status() {
+                status if status.is_success() => Ok(response.json().await?),
+                StatusCode::UNAUTHORIZED => Err(StoreError::SessionExpired),
+                status => Err(StoreError::Rejected(format!(
+                    "Loading the audit log failed with {}",
+                    status
+                ))),
+            }
+        })
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<(), StoreError> {
+    check(&request.send().await?)
+}
+
+fn check(response: &reqwest::Response) -> Result<(), StoreError> {
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED => Err(StoreError::SessionExpired),
+        status => Err(StoreError::Rejected(format!(
+            "{} returned {}",
+            response.url(),
+            status
+        ))),
+    }
+}
+
+impl HttpStore {
+    /// Mirrors the difference between `acknowledged` and `current` onto the machine
+    /// resources, one machine at a time. `acknowledged` holds the machines as the backend
+    /// has them and is kept up to date as each machine is pushed. A machine whose push
+    /// failed halfway is left out of it, so the next save starts over from the backend's
+    /// copy of that machine instead of repeating steps that already went through.
+    async fn push_machine_changes(
+        &self,
+        session_token: &str,
+        acknowledged: &mut Vec<Machine>,
+        current: &[Machine],
+    ) -> Result<(), StoreError> {
+        let (client, base_url) = (&self.client, &self.base_url);
+        let deleted: Vec<String> = acknowledged
+            .iter()
+            .filter(|machine| !current.iter().any(|m| m.uuid == machine.uuid))
+            .map(|machine| machine.uuid.clone())
+            .collect();
+        for uuid in deleted {
+            let url = format!("{base_url}/api/machines/{uuid}");
+            let response = client.delete(url).bearer_auth(session_token).send().await?;
+            // Already gone, e.g. deleted by a save that failed before it heard back
+            if response.status() != StatusCode::NOT_FOUND {
+                check(&response)?;
+            }
+            acknowledged.retain(|machine| machine.uuid != uuid);
+        }
+
+        for machine in current {
+            let before = acknowledged.iter().position(|m| m.uuid == machine.uuid);
+            let pushed = match before {
+                None => self.push_new_machine(session_token, machine).await,
+                Some(index) if acknowledged[index] != *machine => {
+                    let before = acknowledged[index].clone();
+                    self.push_machine_update(session_token, &before, machine)
+                        .await
+                }
+                Some(_) => continue,
+            };
+            acknowledged.retain(|m| m.uuid != machine.uuid);
+            pushed?;
+            acknowledged.push(machine.clone());
+        }
+        Ok(())
+    }
+
+    async fn push_new_machine(
+        &self,
+        session_token: &str,
+        machine: &Machine,
+    ) -> Result<(), StoreError> {
+        let url = format!("{}/api/machines", self.base_url);
+        let response = self
+            .client
+            .post(url)
+            .bearer_auth(session_token)
+            .json(machine)
+            .send()
+            .await?;
+        if response.status() != StatusCode::CONFLICT {
+            return check(&response);
+        }
+        // An earlier save created it but failed before it finished, so the backend may
+        // hold an older version of it
+        let url = format!("{}/api/machines/{}", self.base_url, machine.uuid);
+        let response = self
+            .client
+            .get(url)
+            .bearer_auth(session_token)
+            .send()
+            .await?;
+        check(&response)?;
+        let on_server: Machine = response.json().await?;
+        self.push_machine_update(session_token, &on_server, machine)
+            .await
+    }
+
+    async fn push_machine_update(
+        &self,
+        session_token: &str,
+        before: &Machine,
+        after: &Machine,
+    ) -> Result<(), StoreError> {
+        let client = &self.client;
+        let machine_url = format!("{}/api/machines/{}", self.base_url, after.uuid);
+        if before.name != after.name
+            || before.manufacturer != after.manufacturer
+            || before.model != after.model
+            || before.description != after.description
+            || before.capabilities != after.capabilities
+        {
+            send(
+                client
+                    .put(&machine_url)
+                    .bearer_auth(session_token)
+                    .json(after),
+            )
+            .await?;
+        }
+
+        // Remove from the back so the remaining indices stay valid
+        for index in (after.magazines.len()..before.magazines.len()).rev() {
+            let url = format!("{machine_url}/magazines/{index}");
+            send(client.delete(url).bearer_auth(session_token)).await?;
+        }
+
+        for (index, magazine) in after.magazines.iter().enumerate() {
+            let magazine_url = format!("{machine_url}/magazines/{index}");
+            let Some(old) = before.magazines.get(index) else {
+                let url = format!("{machine_url}/magazines");
+                send(client.post(url).bearer_auth(session_token).json(magazine)).await?;
+                continue;
+            };
+            if old.name != magazine.name
+                || old.capacity != magazine.capacity
+                || old.kind != magazine.kind
+            {
+                send(
+                    client
+                        .put(&magazine_url)
+                        .bearer_auth(session_token)
+                        .json(magazine),
+                )
+                .await?;
+            }
+            for (index, slot) in magazine.slots.iter().enumerate() {
+                if old.slots.get(index) != Some(slot) {
+                    let url = format!("{magazine_url}/slots/{index}");
+                    send(client.put(url).bearer_auth(session_token).json(slot)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
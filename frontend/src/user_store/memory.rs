@@ -0,0 +1,96 @@
+use super::{adopt, LoginSuccess, StoreError, StoreFuture, UserStore};
+use shared::{
+    audit::{AuditEntry, AuditQuery},
+    machine::Machine,
+    User, UserData,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Debug, Clone)]
+struct Account {
+    password: String,
+    user_data: UserData,
+}
+
+/// Accounts that live as long as the store, for tests and trying the application out.
+/// Passwords are kept as they are, nothing here reaches the disk.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    accounts: Arc<Mutex<HashMap<String, Account>>>,
+}
+
+impl MemoryStore {
+    /// The stored user data of `username`, to check what a save wrote.
+    pub fn user_data(&self, username: &str) -> Option<UserData> {
+        let accounts = self.accounts.lock().unwrap();
+        accounts
+            .get(username)
+            .map(|account| account.user_data.clone())
+    }
+}
+
+impl UserStore for MemoryStore {
+    fn verify_user(&self, username: String, password: String) -> StoreFuture<Option<LoginSuccess>> {
+        let account = self.accounts.lock().unwrap().get(&username).cloned();
+        Box::pin(async move {
+            Ok(account
+                .filter(|account| account.password == password)
+                .map(|account| LoginSuccess {
+                    user_data: adopt(account.user_data),
+                    session_token: None,
+                }))
+        })
+    }
+
+    fn register_user(
+        &self,
+        username: String,
+        password: String,
+        _email: String,
+    ) -> StoreFuture<bool> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let registered = !accounts.contains_key(&username);
+        if registered {
+            accounts.insert(
+                username,
+                Account {
+                    password,
+                    user_data: UserData::default(),
+                },
+            );
+        }
+        Box::pin(async move { Ok(registered) })
+    }
+
+    fn update_user_data(
+        &self,
+        user: User,
+        _synced_machines: Vec<Machine>,
+        _session_token: Option<String>,
+    ) -> StoreFuture<bool> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let updated = match accounts.get_mut(&user.username) {
+            Some(account) => {
+                account.user_data = user.user_data;
+                true
+            }
+            None => false,
+        };
+        Box::pin(async move { Ok(updated) })
+    }
+
+    fn fetch_audit_log(
+        &self,
+        _query: AuditQuery,
+        _session_token: Option<String>,
+    ) -> StoreFuture<Vec<AuditEntry>> {
+        Box::pin(async {
+            Err(StoreError::Unsupported(
+                "The audit log is kept by the server, accounts in memory have none",
+            ))
+        })
+    }
+}
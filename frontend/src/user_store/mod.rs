@@ -0,0 +1,199 @@
+//! Where user accounts and their user data are kept.
+//!
+//! The application talks to a [`UserStore`] chosen at startup, see [`from_env`]: the local
+//! SQLite database, the backend over HTTP, or memory for tests.
+
+use reqwest::Client;
+use shared::{
+    audit::{AuditEntry, AuditQuery},
+    machine::Machine,
+    upgrade::UpgradeError,
+    User, UserData,
+};
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use crate::database_interactions::API_URL;
+
+pub mod http;
+pub mod memory;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sqlite;
+
+pub use http::HttpStore;
+pub use memory::MemoryStore;
+#[cfg(not(target_arch = "wasm32"))]
+pub use sqlite::SqliteStore;
+
+/// What a successful sign in hands back to the application.
+/// The session token is only issued by the backend, local sign ins have none.
+#[derive(Debug, Clone)]
+pub struct LoginSuccess {
+    pub user_data: UserData,
+    pub session_token: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    /// The backend rejected (or we never had) the session token, the user has to sign in again
+    SessionExpired,
+    /// The store does not keep what was asked for
+    Unsupported(&'static str),
+    Rejected(String),
+    /// Some of the machines were saved before `error`, `synced_machines` holds the machines
+    /// as the backend has them now
+    PartlySaved {
+        synced_machines: Vec<Machine>,
+        error: Box<StoreError>,
+    },
+    Http(reqwest::Error),
+    Json(serde_json::Error),
+    Upgrade(UpgradeError),
+    #[cfg(not(target_arch = "wasm32"))]
+    Sqlite(rusqlite::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    Hash(shared::credentials::HashError),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::SessionExpired => write!(f, "Session expired"),
+            StoreError::Unsupported(what) => write!(f, "{}", what),
+            StoreError::Rejected(reason) => write!(f, "{}", reason),
+            StoreError::PartlySaved { error, .. } => write!(f, "{}", error),
+            StoreError::Http(e) => write!(f, "The server cannot be reached: {}", e),
+            StoreError::Json(e) => write!(f, "Invalid JSON: {}", e),
+            StoreError::Upgrade(e) => write!(f, "{}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            StoreError::Sqlite(e) => write!(f, "Local database error: {}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            StoreError::Hash(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StoreError::PartlySaved { error, .. } => Some(error.as_ref()),
+            StoreError::Http(e) => Some(e),
+            StoreError::Json(e) => Some(e),
+            StoreError::Upgrade(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            StoreError::Sqlite(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            StoreError::Hash(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for StoreError {
+    fn from(e: reqwest::Error) -> Self {
+        StoreError::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Json(e)
+    }
+}
+
+impl From<UpgradeError> for StoreError {
+    fn from(e: UpgradeError) -> Self {
+        StoreError::Upgrade(e)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Sqlite(e)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<shared::credentials::HashError> for StoreError {
+    fn from(e: shared::credentials::HashError) -> Self {
+        StoreError::Hash(e)
+    }
+}
+
+/// What the store methods return. Native builds run it on another thread, hence `Send`.
+#[cfg(not(target_arch = "wasm32"))]
+pub type StoreFuture<T> = Pin<Box<dyn Future<Output = Result<T, StoreError>> + Send>>;
+#[cfg(target_arch = "wasm32")]
+pub type StoreFuture<T> = Pin<Box<dyn Future<Output = Result<T, StoreError>>>>;
+
+/// The futures own everything they need, so they can be handed to `spawn_task`.
+pub trait UserStore: Send + Sync {
+    /// The user's data when the password is right, `None` when it is not.
+    fn verify_user(&self, username: String, password: String) -> StoreFuture<Option<LoginSuccess>>;
+
+    /// Whether the account was created.
+    fn register_user(&self, username: String, password: String, email: String)
+        -> StoreFuture<bool>;
+
+    /// `synced_machines` is what the store held after the last successful save, stores
+    /// that keep machines apart only write what differs from it.
+    fn update_user_data(
+        &self,
+        user: User,
+        synced_machines: Vec<Machine>,
+        session_token: Option<String>,
+    ) -> StoreFuture<bool>;
+
+    /// The change log of the user's machines, newest first.
+    fn fetch_audit_log(
+        &self,
+        query: AuditQuery,
+        session_token: Option<String>,
+    ) -> StoreFuture<Vec<AuditEntry>>;
+}
+
+/// The store used when none is chosen: the local database on native builds, the backend
+/// on the web.
+pub fn default_store() -> Arc<dyn UserStore> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Arc::new(SqliteStore)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Arc::new(HttpStore::new(Client::new(), backend_url()))
+    }
+}
+
+/// The backend the HTTP store talks to, `BACKEND_URL` at runtime on native builds and at
+/// compile time on the web.
+pub fn backend_url() -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    let url = std::env::var("BACKEND_URL").ok();
+    #[cfg(target_arch = "wasm32")]
+    let url = option_env!("BACKEND_URL").map(str::to_string);
+    url.unwrap_or_else(|| API_URL.to_string())
+}
+
+/// The store named by `USER_STORE`, `sqlite`, `http` or `memory`. Native builds default to
+/// `sqlite`, the web build always uses `http`.
+pub fn from_env() -> Arc<dyn UserStore> {
+    #[cfg(not(target_arch = "wasm32"))]
+    match std::env::var("USER_STORE").as_deref() {
+        Ok("http") => return Arc::new(HttpStore::new(Client::new(), backend_url())),
+        Ok("memory") => return Arc::new(MemoryStore::default()),
+        Ok("sqlite") | Err(_) => {}
+        Ok(other) => log::warn!("Unknown USER_STORE {:?}, using sqlite", other),
+    }
+    default_store()
+}
+
+/// Links the loaded slots to the tool library and holder inventory. Shortages are shown in
+/// the holder inventory.
+fn adopt(mut user_data: UserData) -> UserData {
+    user_data.tool_library.adopt_loaded(&user_data.machines);
+    for shortage in user_data.holder_inventory.adopt_loaded(&user_data.machines) {
+        log::warn!("Loaded user data: {}", shortage);
+    }
+    user_data
+}
@@ -0,0 +1,101 @@
+use super::{adopt, LoginSuccess, StoreError, StoreFuture, UserStore};
+use crate::database_interactions::get_db_connection;
+use rusqlite::OptionalExtension;
+use shared::{
+    audit::{AuditEntry, AuditQuery},
+    credentials::{hash_password, verify_legacy_password, Verification},
+    machine::Machine,
+    upgrade::user_data_from_json,
+    User, UserData,
+};
+
+/// Accounts in the local database, see [`crate::database_interactions::init_local_db`].
+/// The work happens inside the futures, on the thread `spawn_task` runs them on.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqliteStore;
+
+impl UserStore for SqliteStore {
+    fn verify_user(&self, username: String, password: String) -> StoreFuture<Option<LoginSuccess>> {
+        Box::pin(async move {
+            let conn = get_db_connection();
+            let mut stmt =
+                conn.prepare("SELECT password, user_data FROM users WHERE username = ?")?;
+            let row: Option<(String, String)> = stmt
+                .query_row([&username], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?;
+            let Some((stored_password, user_data)) = row else {
+                return Ok(None);
+            };
+
+            let verification = verify_legacy_password(&password, &stored_password);
+            if verification == Verification::ValidPlaintext {
+                // Databases from older builds hold plain passwords
+                conn.execute(
+                    "UPDATE users SET password = ? WHERE username = ?",
+                    [&hash_password(&password)?, &username],
+                )?;
+            }
+
+            if !verification.is_valid() {
+                return Ok(None);
+            }
+            Ok(Some(LoginSuccess {
+                user_data: adopt(user_data_from_json(&user_data)?),
+                session_token: None,
+            }))
+        })
+    }
+
+    fn register_user(
+        &self,
+        username: String,
+        password: String,
+        email: String,
+    ) -> StoreFuture<bool> {
+        Box::pin(async move {
+            let conn = get_db_connection();
+            let user_data_json = serde_json::to_string(&UserData::default())?;
+            conn.execute(
+                "INSERT INTO users (username, password, email, user_data, created_at, last_login)
+                 VALUES (?, ?, ?, ?, datetime('now'), datetime('now'))",
+                [
+                    &username,
+                    &hash_password(&password)?,
+                    &email,
+                    &user_data_json,
+                ],
+            )?;
+            Ok(true)
+        })
+    }
+
+    fn update_user_data(
+        &self,
+        user: User,
+        _synced_machines: Vec<Machine>,
+        _session_token: Option<String>,
+    ) -> StoreFuture<bool> {
+        Box::pin(async move {
+            let conn = get_db_connection();
+            let user_data_json = serde_json::to_string(&user.user_data)?;
+            let updated = conn.execute(
+                "UPDATE users SET user_data = ?, last_login = datetime('now') WHERE username = ?",
+                [&user_data_json, &user.username],
+            )?;
+            // No such account, e.g. it was removed from the database since the sign in
+            Ok(updated > 0)
+        })
+    }
+
+    fn fetch_audit_log(
+        &self,
+        _query: AuditQuery,
+        _session_token: Option<String>,
+    ) -> StoreFuture<Vec<AuditEntry>> {
+        Box::pin(async {
+            Err(StoreError::Unsupported(
+                "The audit log is kept by the server, local accounts have none",
+            ))
+        })
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    app_states::{HolderTypeSelection, WidgetState},
+    singletons::Singletons,
+    widgets::editor_fields::*,
+};
+use egui::{Context, Grid, Window};
+use shared::{
+    holders::holder::{
+        Holder, RotatingHolder, RotatingHolderCategory, TurningHolder, TurningHolderCategory,
+    },
+    inventory::InventoryError,
+    placement::{editable_slot_mut, place_holder, slot},
+    User,
+};
+use uuid::Uuid;
+
+pub struct AddHolderWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    machine_index: usize,
+}
+
+impl<'a> AddHolderWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        machine_index: usize,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            machine_index,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+
+        Window::new("Add Holder").open(open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut self.singletons.holder_type_selection,
+                    HolderTypeSelection::Rotating,
+                    "Rotating",
+                );
+                ui.radio_value(
+                    &mut self.singletons.holder_type_selection,
+                    HolderTypeSelection::Turning,
+                    "Turning",
+                );
+            });
+
+            Grid::new("add_holder_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| match self.singletons.holder_type_selection {
+                    HolderTypeSelection::Rotating => {
+                        rotating_holder_rows(ui, &mut self.singletons.rotating_holder)
+                    }
+                    HolderTypeSelection::Turning => {
+                        turning_holder_rows(ui, &mut self.singletons.turning_holder)
+                    }
+                });
+
+            let validation = match self.singletons.holder_type_selection {
+                HolderTypeSelection::Rotating => self.singletons.rotating_holder.validate(),
+                HolderTypeSelection::Turning => self.singletons.turning_holder.validate(),
+            };
+            if let Err(errors) = &validation {
+                validation_errors(ui, errors);
+            }
+            let holder = match self.singletons.holder_type_selection {
+                HolderTypeSelection::Rotating => {
+                    Holder::Rotating(self.singletons.rotating_holder.clone())
+                }
+                HolderTypeSelection::Turning => {
+                    Holder::Turning(self.singletons.turning_holder.clone())
+                }
+            };
+
+            ui.separator();
+            let Some(machine) = self.user.user_data.machines.get_mut(self.machine_index) else {
+                return;
+            };
+            slot_picker(ui, "add_holder", machine, &mut self.singletons.slot_target);
+            if let Ok(Some(replaced)) =
+                slot(machine, self.singletons.slot_target).map(|slot| slot.holder.as_ref())
+            {
+                ui.label(format!("Replaces {}", replaced.summary()));
+            }
+            let issues = target_issues(machine, self.singletons.slot_target, None, Some(&holder));
+            compatibility_issues(ui, &issues);
+            let blocked = is_blocked(
+                self.user.user_data.settings.block_incompatible_assemblies,
+                &issues,
+            );
+            if let Some(error) = &self.singletons.editor_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.horizontal(|ui| {
+                let add_holder = ui
+                    .add_enabled(
+                        validation.is_ok() && !blocked,
+                        egui::Button::new("Add Holder"),
+                    )
+                    .on_hover_text("Add to the inventory and put one copy into the slot");
+                let add_to_inventory = ui
+                    .add_enabled(validation.is_ok(), egui::Button::new("Add to Inventory"))
+                    .on_hover_text("Add to the inventory without using a copy");
+                if add_holder.clicked() || add_to_inventory.clicked() {
+                    let target = self.singletons.slot_target;
+                    // The slot is checked first, so a failed add leaves the machine as it was
+                    let checked = if add_holder.clicked() {
+                        editable_slot_mut(machine, target)
+                            .map(|_| ())
+                            .map_err(InventoryError::from)
+                    } else {
+                        Ok(())
+                    };
+                    let added = checked
+                        .and_then(|_| self.user.user_data.holder_inventory.add(holder.clone()))
+                        .and_then(|_| {
+                            if !add_holder.clicked() {
+                                return Ok(());
+                            }
+                            // A new holder owns at least one copy, so placing it cannot run out
+                            place_holder(machine, target, holder, &self.user.username)
+                                .map(|_| ())
+                                .map_err(InventoryError::from)
+                        });
+                    match added {
+                        Ok(()) => {
+                            // Keep the values for the next holder, but not the identity
+                            self.singletons.rotating_holder.uuid = Uuid::new_v4().to_string();
+                            self.singletons.turning_holder.uuid = Uuid::new_v4().to_string();
+                            self.singletons.editor_error = None;
+                            self.singletons.should_save_user_data = true;
+                            *self.widget_state = WidgetState::Default;
+                            should_close = true;
+                        }
+                        Err(e) => self.singletons.editor_error = Some(e.to_string()),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    self.singletons.editor_error = None;
+                    *self.widget_state = WidgetState::Default;
+                    should_close = true;
+                }
+            });
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+}
+
+fn rotating_holder_rows(ui: &mut egui::Ui, holder: &mut RotatingHolder) {
+    holder_category_rows(
+        ui,
+        &mut holder.category,
+        RotatingHolderCategory::same_kind,
+        RotatingHolderCategory::name,
+    );
+    count_row(ui, "Copies", &mut holder.duplicates);
+    float_row(ui, "Diameter", &mut holder.diameter, " mm");
+    float_row(ui, "Length", &mut holder.length, " mm");
+    integer_row(ui, "Maximum RPM", &mut holder.max_rpm, " RPM");
+    checkbox_row(ui, "Coolant Through", &mut holder.coolant_through);
+    range_row(
+        ui,
+        "Tool Clamping Range",
+        &mut holder.tool_clamping_range,
+        " mm",
+    );
+    text_row(ui, "Taper Type", &mut holder.taper_type);
+    float_row(ui, "Runout", &mut holder.runout, " mm");
+    text_row(ui, "Balance Grade", &mut holder.balance_grade);
+    float_row(ui, "Weight", &mut holder.weight, " g");
+    if holder.uses_collet_type() {
+        text_row(ui, "Collet Type", &mut holder.collet_type);
+    }
+    if holder.uses_weldon_flat_size() {
+        float_row(ui, "Weldon Flat Size", &mut holder.weldon_flat_size, " mm");
+    }
+    if holder.uses_adjustable_range() {
+        range_row(ui, "Adjustable Range", &mut holder.adjustable_range, " mm");
+    }
+    if holder.uses_tension_compression_range() {
+        range_row(
+            ui,
+            "Tension Compression Range",
+            &mut holder.tension_compression_range,
+            " mm",
+        );
+    }
+}
+
+fn turning_holder_rows(ui: &mut egui::Ui, holder: &mut TurningHolder) {
+    holder_category_rows(
+        ui,
+        &mut holder.category,
+        TurningHolderCategory::same_kind,
+        TurningHolderCategory::name,
+    );
+    count_row(ui, "Copies", &mut holder.duplicates);
+    float_row(ui, "Degree", &mut holder.degree, "°");
+    float_row(ui, "Shank Height", &mut holder.shank_height, " mm");
+    float_row(ui, "Shank Width", &mut holder.shank_width, " mm");
+    float_row(ui, "Overall Length", &mut holder.overall_length, " mm");
+    float_row(ui, "Weight", &mut holder.weight, " g");
+    text_row(ui, "Insert Size", &mut holder.insert_size);
+    choice_row(ui, "Handedness", &mut holder.handedness);
+    text_row(ui, "Coolant Type", &mut holder.coolant_type);
+    float_row(
+        ui,
+        "Max Cutting Diameter",
+        &mut holder.max_cutting_diameter,
+        " mm",
+    );
+    checkbox_row(
+        ui,
+        "Quick Change Compatible",
+        &mut holder.quick_change_compatible,
+    );
+    if holder.uses_bore_limits() {
+        float_row(ui, "Max Bore Depth", &mut holder.max_bore_depth, " mm");
+        float_row(
+            ui,
+            "Min Bore Diameter",
+            &mut holder.min_bore_diameter,
+            " mm",
+        );
+    }
+    if holder.uses_cartridge_type() {
+        text_row(ui, "Cartridge Type", &mut holder.cartridge_type);
+    }
+    if holder.uses_thread_pitch_range() {
+        range_row(
+            ui,
+            "Thread Pitch Range",
+            &mut holder.thread_pitch_range,
+            " mm",
+        );
+    }
+    if holder.uses_form_profile() {
+        text_row(ui, "Form Profile", &mut holder.form_profile);
+    }
+    if holder.uses_tool_post_size() {
+        text_row(ui, "Tool Post Size", &mut holder.tool_post_size);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    app_states::{ToolTypeSelection, WidgetState},
+    singletons::Singletons,
+    widgets::editor_fields::*,
+};
+use egui::{Context, Grid, Window};
+use shared::{
+    library::LibraryError,
+    placement::{editable_slot_mut, place_tool, slot},
+    tools::tool::Tool,
+    User,
+};
+use uuid::Uuid;
+
+pub struct AddToolWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    machine_index: usize,
+}
+
+impl<'a> AddToolWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        machine_index: usize,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            machine_index,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+
+        Window::new("Add Tool").open(open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut self.singletons.tool_type_selection,
+                    ToolTypeSelection::Rotating,
+                    "Rotating",
+                );
+                ui.radio_value(
+                    &mut self.singletons.tool_type_selection,
+                    ToolTypeSelection::Turning,
+                    "Turning",
+                );
+            });
+
+            Grid::new("add_tool_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| match self.singletons.tool_type_selection {
+                    ToolTypeSelection::Rotating => {
+                        rotating_tool_rows(ui, &mut self.singletons.rotating_tool)
+                    }
+                    ToolTypeSelection::Turning => {
+                        turning_tool_rows(ui, &mut self.singletons.turning_tool)
+                    }
+                });
+
+            let validation = match self.singletons.tool_type_selection {
+                ToolTypeSelection::Rotating => self.singletons.rotating_tool.validate(),
+                ToolTypeSelection::Turning => self.singletons.turning_tool.validate(),
+            };
+            if let Err(errors) = &validation {
+                validation_errors(ui, errors);
+            }
+            let tool = match self.singletons.tool_type_selection {
+                ToolTypeSelection::Rotating => {
+                    Tool::Rotating(self.singletons.rotating_tool.clone())
+                }
+                ToolTypeSelection::Turning => Tool::Turning(self.singletons.turning_tool.clone()),
+            };
+
+            ui.separator();
+            let Some(machine) = self.user.user_data.machines.get_mut(self.machine_index) else {
+                return;
+            };
+            ui.horizontal(|ui| {
+                ui.label("Tool Number:");
+                ui.text_edit_singleline(&mut self.singletons.tool_number);
+            });
+            slot_picker(ui, "add_tool", machine, &mut self.singletons.slot_target);
+            if let Ok(Some(replaced)) =
+                slot(machine, self.singletons.slot_target).map(|slot| slot.tool.as_ref())
+            {
+                ui.label(format!("Replaces {}", replaced.summary()));
+            }
+            let issues = target_issues(machine, self.singletons.slot_target, Some(&tool), None);
+            compatibility_issues(ui, &issues);
+            let blocked = is_blocked(
+                self.user.user_data.settings.block_incompatible_assemblies,
+                &issues,
+            );
+            if let Some(error) = &self.singletons.editor_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.horizontal(|ui| {
+                let add_tool = ui
+                    .add_enabled(
+                        validation.is_ok() && !blocked,
+                        egui::Button::new("Add Tool"),
+                    )
+                    .on_hover_text("Add to the library and load into the slot");
+                let add_to_library = ui
+                    .add_enabled(validation.is_ok(), egui::Button::new("Add to Library"))
+                    .on_hover_text("Add to the library without loading it");
+                if add_tool.clicked() || add_to_library.clicked() {
+                    let target = self.singletons.slot_target;
+                    // The slot is checked first, so a failed add leaves the machine as it was
+                    let checked = if add_tool.clicked() {
+                        editable_slot_mut(machine, target)
+                            .map(|_| ())
+                            .map_err(LibraryError::from)
+                    } else {
+                        Ok(())
+                    };
+                    let added = checked
+                        .and_then(|_| self.user.user_data.tool_library.add(tool.clone()))
+                        .and_then(|_| {
+                            if !add_tool.clicked() {
+                                return Ok(());
+                            }
+                            let tool_number = self.singletons.tool_number.trim();
+                            let tool_number =
+                                (!tool_number.is_empty()).then(|| tool_number.to_string());
+                            place_tool(machine, target, tool, tool_number, &self.user.username)
+                                .map(|_| ())
+                                .map_err(LibraryError::from)
+                        });
+                    match added {
+                        Ok(()) => {
+                            // Keep the values for the next tool, but not the identity
+                            self.singletons.rotating_tool.uuid = Uuid::new_v4().to_string();
+                            self.singletons.turning_tool.uuid = Uuid::new_v4().to_string();
+                            self.singletons.tool_number.clear();
+                            self.singletons.editor_error = None;
+                            self.singletons.should_save_user_data = true;
+                            *self.widget_state = WidgetState::Default;
+                            should_close = true;
+                        }
+                        Err(e) => self.singletons.editor_error = Some(e.to_string()),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    self.singletons.editor_error = None;
+                    *self.widget_state = WidgetState::Default;
+                    should_close = true;
+                }
+            });
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    app::spawn_task, app_states::WidgetState, singletons::Singletons, user_store::UserStore,
+};
+use chrono::{Days, NaiveDate};
+use egui::{ComboBox, Context, DragValue, Grid, ScrollArea, TextEdit, Ui, Window};
+use serde::{Deserialize, Serialize};
+use shared::{
+    audit::{AuditEntry, AuditQuery},
+    User,
+};
+use std::sync::Arc;
+
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+/// The filters as they are typed in, turned into an [`AuditQuery`] on refresh.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AuditFilter {
+    pub machine: Option<String>,
+    pub magazine: Option<usize>,
+    pub slot: Option<usize>,
+    /// First day to show, `YYYY-MM-DD`
+    pub from: String,
+    /// Last day to show, `YYYY-MM-DD`
+    pub to: String,
+}
+
+impl AuditFilter {
+    fn query(&self) -> Result<AuditQuery, String> {
+        let day = |text: &str| -> Result<Option<NaiveDate>, String> {
+            let text = text.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            NaiveDate::parse_from_str(text, DAY_FORMAT)
+                .map(Some)
+                .map_err(|_| format!("'{}' is not a date like 2024-05-31", text))
+        };
+        let start_of = |day: NaiveDate| day.and_hms_opt(0, 0, 0).map(|time| time.and_utc());
+        Ok(AuditQuery {
+            machine: self.machine.clone(),
+            magazine: self.magazine,
+            slot: self.magazine.and(self.slot),
+            from: day(&self.from)?.and_then(start_of),
+            // The last day counts in full
+            to: day(&self.to)?
+                .and_then(|day| day.checked_add_days(Days::new(1)))
+                .and_then(start_of),
+        })
+    }
+}
+
+pub struct AuditLogWindow<'a> {
+    user: &'a User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    session_token: Option<String>,
+    store: Arc<dyn UserStore>,
+}
+
+impl<'a> AuditLogWindow<'a> {
+    pub fn new(
+        user: &'a User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        session_token: Option<String>,
+        store: Arc<dyn UserStore>,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            session_token,
+            store,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+        if let Some(result) = ctx.memory(|mem| {
+            mem.data
+                .get_temp::<Result<Vec<AuditEntry>, String>>("audit_entries".into())
+        }) {
+            ctx.memory_mut(|mem| {
+                mem.data
+                    .remove::<Result<Vec<AuditEntry>, String>>("audit_entries".into())
+            });
+            self.singletons.audit_loading = false;
+            match result {
+                Ok(entries) => self.singletons.audit_entries = entries,
+                Err(e) => self.singletons.audit_error = Some(e),
+            }
+        }
+
+        Window::new("Audit Log").open(open).show(ctx, |ui| {
+            if self.filter_ui(ui) {
+                self.refresh(ctx);
+            }
+            if let Some(error) = &self.singletons.audit_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            ui.separator();
+            if self.singletons.audit_loading {
+                ui.spinner();
+            } else if self.singletons.audit_entries.is_empty() {
+                ui.weak("No changes found");
+            } else {
+                self.entries_ui(ui);
+            }
+
+            ui.separator();
+            if ui.button("Close").clicked() {
+                *self.widget_state = WidgetState::Default;
+                should_close = true;
+            }
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+
+    /// Returns whether the log should be fetched again.
+    fn filter_ui(&mut self, ui: &mut Ui) -> bool {
+        let filter = &mut self.singletons.audit_filter;
+        let machines = &self.user.user_data.machines;
+        Grid::new("audit_filter").num_columns(2).show(ui, |ui| {
+            ui.label("Machine:");
+            let selected = match &filter.machine {
+                None => "All machines".to_string(),
+                Some(uuid) => machines
+                    .iter()
+                    .find(|machine| &machine.uuid == uuid)
+                    .map_or_else(|| "Deleted machine".to_string(), |m| m.name.clone()),
+            };
+            ComboBox::from_id_source("audit_machine")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.machine, None, "All machines");
+                    for machine in machines {
+                        ui.selectable_value(
+                            &mut filter.machine,
+                            Some(machine.uuid.clone()),
+                            &machine.name,
+                        );
+                    }
+                });
+            ui.end_row();
+
+            optional_index(ui, "Magazine", &mut filter.magazine);
+            if filter.magazine.is_some() {
+                optional_index(ui, "Slot", &mut filter.slot);
+            }
+
+            ui.label("From:");
+            ui.add(TextEdit::singleline(&mut filter.from).hint_text("YYYY-MM-DD"));
+            ui.end_row();
+            ui.label("To:");
+            ui.add(TextEdit::singleline(&mut filter.to).hint_text("YYYY-MM-DD"));
+            ui.end_row();
+        });
+        ui.button("Refresh").clicked()
+    }
+
+    fn entries_ui(&self, ui: &mut Ui) {
+        ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            Grid::new("audit_entries")
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("When");
+                    ui.strong("Who");
+                    ui.strong("What");
+                    ui.strong("Where");
+                    ui.strong("Change");
+                    ui.end_row();
+                    for entry in &self.singletons.audit_entries {
+                        ui.label(entry.changed_at.format("%Y-%m-%d %H:%M:%S").to_string())
+                            .on_hover_text("UTC");
+                        ui.label(&entry.changed_by);
+                        ui.label(entry.action.to_string());
+                        ui.label(entry.location());
+                        ui.label(entry.summary());
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn refresh(&mut self, ctx: &Context) {
+        let query = match self.singletons.audit_filter.query() {
+            Ok(query) => query,
+            Err(e) => {
+                self.singletons.audit_error = Some(e);
+                return;
+            }
+        };
+        self.singletons.audit_error = None;
+        self.singletons.audit_loading = true;
+        let fetch = self
+            .store
+            .fetch_audit_log(query, self.session_token.clone());
+        let ctx = ctx.clone();
+        spawn_task(async move {
+            let result = fetch.await.map_err(|e| e.to_string());
+            ctx.request_repaint();
+            ctx.memory_mut(|mem| mem.data.insert_temp("audit_entries".into(), result));
+        });
+    }
+}
+
+/// A checkbox that turns a 1-based index field on and off.
+fn optional_index(ui: &mut Ui, label: &str, index: &mut Option<usize>) {
+    let mut on = index.is_some();
+    if ui.checkbox(&mut on, label).changed() {
+        *index = on.then_some(0);
+    }
+    match index {
+        Some(index) => {
+            let mut number = *index + 1;
+            if ui.add(DragValue::new(&mut number).range(1..=200)).changed() {
+                *index = number - 1;
+            }
+        }
+        None => {
+            ui.weak("Any");
+        }
+    }
+    ui.end_row();
+}
@@ -0,0 +1,217 @@
+use crate::{
+    app_states::WidgetState, file_io, singletons::Singletons,
+    widgets::holder_inventory::shortage_warning,
+};
+use egui::{Context, Grid, Ui, Window};
+use shared::{
+    backup::{self, Backup, RestoreMode},
+    User,
+};
+
+/// Where [`file_io::open_file`] leaves the backup to restore.
+const OPENED_FILE: &str = "backup";
+
+#[derive(Debug, Clone, Default)]
+pub struct BackupState {
+    /// File to save to and open from, native builds only
+    pub path: String,
+    pub mode: RestoreMode,
+    /// Backup that was read and checked, waiting for the user to restore it
+    pub pending: Option<Backup>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+pub struct BackupWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+}
+
+impl<'a> BackupWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+        if *open {
+            if let Some(result) =
+                file_io::take_opened_file(ctx, OPENED_FILE).or_else(|| file_io::dropped_file(ctx))
+            {
+                self.read(result);
+            }
+        }
+
+        Window::new("Backup").open(open).show(ctx, |ui| {
+            self.backup_ui(ui);
+            ui.separator();
+            self.restore_ui(ctx, ui);
+
+            let state = &self.singletons.backup;
+            if let Some(message) = &state.message {
+                ui.label(message);
+            }
+            if let Some(error) = &state.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.separator();
+            if ui.button("Close").clicked() {
+                self.singletons.backup = BackupState::default();
+                *self.widget_state = WidgetState::Default;
+                should_close = true;
+            }
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+
+    /// The file the backup is saved to, named after the user when no path is given.
+    fn path(&self) -> String {
+        let path = self.singletons.backup.path.trim();
+        if path.is_empty() {
+            format!("{}-backup.json", self.user.username)
+        } else {
+            path.to_string()
+        }
+    }
+
+    fn backup_ui(&mut self, ui: &mut Ui) {
+        ui.heading("Back Up");
+        ui.label("Machines, tools, holders, filters, selections and settings, in one file.");
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            let hint = format!("{}-backup.json", self.user.username);
+            ui.label("File:");
+            ui.add(egui::TextEdit::singleline(&mut self.singletons.backup.path).hint_text(hint));
+        });
+
+        let label = if cfg!(target_arch = "wasm32") {
+            "Download Backup"
+        } else {
+            "Save Backup"
+        };
+        if ui.button(label).clicked() {
+            let path = self.path();
+            let result = backup::write(&self.user.user_data, &self.user.username)
+                .map_err(|e| e.to_string())
+                .and_then(|json| file_io::save_file(&path, &json));
+            let state = &mut self.singletons.backup;
+            match result {
+                Ok(()) => {
+                    state.message = Some(format!("Saved {}", path));
+                    state.error = None;
+                }
+                Err(e) => {
+                    state.message = None;
+                    state.error = Some(e);
+                }
+            }
+        }
+    }
+
+    fn restore_ui(&mut self, ctx: &Context, ui: &mut Ui) {
+        ui.heading("Restore");
+        ui.horizontal(|ui| {
+            let label = if cfg!(target_arch = "wasm32") {
+                "Choose File…"
+            } else {
+                "Open"
+            };
+            if ui.button(label).clicked() {
+                file_io::open_file(ctx, OPENED_FILE, &self.path());
+            }
+            ui.weak("or drop a backup onto the window");
+        });
+
+        let state = &mut self.singletons.backup;
+        let Some(pending) = &state.pending else {
+            return;
+        };
+        let user_data = &pending.user_data;
+        Grid::new("backup_summary").num_columns(2).show(ui, |ui| {
+            ui.label("Account:");
+            ui.label(&pending.username);
+            ui.end_row();
+            ui.label("Made:");
+            ui.label(pending.created_at.format("%Y-%m-%d %H:%M").to_string())
+                .on_hover_text("UTC");
+            ui.end_row();
+            ui.label("Machines:");
+            ui.label(user_data.machines.len().to_string());
+            ui.end_row();
+            ui.label("Tools:");
+            ui.label(user_data.tool_library.tools().len().to_string());
+            ui.end_row();
+            ui.label("Holders:");
+            ui.label(user_data.holder_inventory.holders().len().to_string());
+            ui.end_row();
+        });
+
+        ui.radio_value(&mut state.mode, RestoreMode::Merge, "Merge")
+            .on_hover_text("Add to what the account has, keeping its selections and settings");
+        ui.radio_value(&mut state.mode, RestoreMode::Replace, "Replace");
+        if state.mode == RestoreMode::Replace {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Everything in this account is replaced by the backup",
+            );
+        }
+
+        let mut restore = false;
+        ui.horizontal(|ui| {
+            restore = ui.button("Restore").clicked();
+            if ui.button("Cancel").clicked() {
+                state.pending = None;
+            }
+        });
+        if restore {
+            self.restore();
+        }
+    }
+
+    fn read(&mut self, file: Result<String, String>) {
+        let state = &mut self.singletons.backup;
+        state.message = None;
+        // Nothing is touched until the whole file is read and checked
+        match file.and_then(|json| backup::read(&json).map_err(|e| e.to_string())) {
+            Ok(backup) => {
+                state.pending = Some(backup);
+                state.error = None;
+            }
+            Err(e) => {
+                state.pending = None;
+                state.error = Some(e);
+            }
+        }
+    }
+
+    fn restore(&mut self) {
+        let state = &mut self.singletons.backup;
+        let Some(pending) = state.pending.take() else {
+            return;
+        };
+        let report = backup::restore(&mut self.user.user_data, pending.user_data, state.mode);
+        let mut message = format!(
+            "Restored {} machines, {} tools and {} holders",
+            report.machines, report.tools, report.holders
+        );
+        if report.remapped > 0 {
+            message.push_str(&format!(", {} with a new id", report.remapped));
+        }
+        state.message = Some(message);
+        state.error = shortage_warning(&report.shortages);
+        self.singletons.transfer_error = None;
+        self.singletons.should_save_user_data = true;
+    }
+}
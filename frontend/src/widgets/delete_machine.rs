@@ -4,6 +4,7 @@ use crate::{
 };
 use egui::{Context, Window};
 use shared::User;
+use std::cmp::Ordering;
 
 pub struct DeleteMachineWindow<'a> {
     user: &'a mut User,
@@ -47,12 +48,16 @@ impl<'a> DeleteMachineWindow<'a> {
                         self.user.user_data.machines.remove(self.machine_index);
                         // If the deleted machine was selected, clear the selection
                         if let Some(selected) = self.user.user_data.selections.selected_machine {
-                            if selected == self.machine_index {
-                                self.user.user_data.selections.selected_machine = None;
-                            } else if selected > self.machine_index {
-                                // Adjust the selection index if it was after the deleted machine
-                                self.user.user_data.selections.selected_machine =
-                                    Some(selected - 1);
+                            match selected.cmp(&self.machine_index) {
+                                Ordering::Equal => {
+                                    self.user.user_data.selections.selected_machine = None;
+                                }
+                                Ordering::Greater => {
+                                    // Adjust the selection index if it was after the deleted machine
+                                    self.user.user_data.selections.selected_machine =
+                                        Some(selected - 1);
+                                }
+                                Ordering::Less => {}
                             }
                         }
                         *self.app_state = AppState::Application;
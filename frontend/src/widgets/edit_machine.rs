@@ -1,11 +1,19 @@
-use egui::{Context, Window};
-use shared::User;
+use egui::{emath::Numeric, ComboBox, Context, DragValue, Grid, Ui, Window};
+use shared::{
+    layout::{add_magazine, remove_magazine, resize_magazine, LayoutError, ShrinkPolicy},
+    machine::{Machine, MachineCapabilities},
+    User,
+};
 
 use crate::{
     app_states::{AppState, WidgetState},
     singletons::Singletons,
 };
 
+/// Slots of a new magazine when there is no other magazine to copy the size of
+const DEFAULT_CAPACITY: usize = 20;
+const MAX_CAPACITY: usize = 200;
+
 pub struct EditMachineWindow<'a> {
     user: &'a mut User,
     singletons: &'a mut Singletons,
@@ -37,38 +45,51 @@ impl<'a> EditMachineWindow<'a> {
         Window::new("Edit Machine").open(open).show(ctx, |ui| {
             ui.heading("Edit Machine");
             let mut changed = false;
-            let machine_index = self.user.user_data.selections.selected_machine.unwrap();
-            if let Some(machine) = self.user.user_data.machines.get_mut(machine_index) {
+            let mut request = None;
+            if let Some(machine) = self.user.user_data.machines.get_mut(self.machine_index) {
                 ui.horizontal(|ui| {
                     ui.label("Name:");
-                    changed = ui.text_edit_singleline(&mut machine.name).changed();
+                    changed |= ui.text_edit_singleline(&mut machine.name).changed();
                 });
                 ui.horizontal(|ui| {
                     ui.label("Manufacturer:");
-                    changed = ui.text_edit_singleline(&mut machine.manufacturer).changed();
+                    changed |= ui.text_edit_singleline(&mut machine.manufacturer).changed();
                 });
                 ui.horizontal(|ui| {
                     ui.label("Model:");
-                    changed = ui.text_edit_singleline(&mut machine.model).changed();
+                    changed |= ui.text_edit_singleline(&mut machine.model).changed();
                 });
                 ui.horizontal(|ui| {
                     ui.label("Description:");
-                    changed = ui
+                    changed |= ui
                         .text_edit_multiline(&mut machine.description.text)
                         .changed();
                 });
-                ui.label(format!("Number of magazines: {}", machine.magazines.len()));
-                if machine.magazines.len() > 0 {
-                    ui.label(format!(
-                        "Magazine capacity: {}",
-                        machine.magazines[0].capacity
-                    ));
-                }
+                ui.separator();
+                ui.strong("Capabilities");
+                changed |= capabilities_ui(ui, &mut machine.capabilities);
+                ui.separator();
+                ui.strong("Magazines");
+                let enabled = self.singletons.magazine_change.is_none();
+                request = magazines_ui(ui, machine, enabled, &mut changed);
                 if changed {
                     self.singletons.should_save_user_data = true;
                 }
             }
+            if let Some(change) = request {
+                self.apply(change, ShrinkPolicy::Block);
+            }
+            if let Some(change) = self.singletons.magazine_change {
+                self.confirm_ui(ui, change);
+            }
+            if let Some(error) = &self.singletons.editor_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.separator();
             if ui.button("Apply").clicked() {
+                self.singletons.magazine_change = None;
+                self.singletons.editor_error = None;
                 *self.app_state = AppState::Application;
                 *self.widget_state = WidgetState::Default;
                 should_close = true;
@@ -78,4 +99,218 @@ impl<'a> EditMachineWindow<'a> {
             *open = false;
         }
     }
+
+    /// Resizes or removes a magazine. Asks what to do with the tools in the slots that go
+    /// away when `policy` is to block.
+    fn apply(&mut self, change: MagazineChange, policy: ShrinkPolicy) {
+        let Some(machine) = self.user.user_data.machines.get_mut(self.machine_index) else {
+            return;
+        };
+        let changed_by = &self.user.username;
+        let result = match change {
+            MagazineChange::Resize { magazine, capacity } => {
+                resize_magazine(machine, magazine, capacity, policy, changed_by)
+            }
+            MagazineChange::Remove(magazine) => {
+                remove_magazine(machine, magazine, policy, changed_by)
+            }
+        };
+        match result {
+            Ok(_) => {
+                self.singletons.magazine_change = None;
+                self.singletons.editor_error = None;
+                self.singletons.should_save_user_data = true;
+            }
+            Err(LayoutError::Occupied(_)) => self.singletons.magazine_change = Some(change),
+            Err(e) => self.singletons.editor_error = Some(e.to_string()),
+        }
+    }
+
+    fn confirm_ui(&mut self, ui: &mut Ui, change: MagazineChange) {
+        let question = match change {
+            MagazineChange::Resize { magazine, capacity } => format!(
+                "Slots after slot {} of magazine {} are in use. Where should their tools go?",
+                capacity,
+                magazine + 1
+            ),
+            MagazineChange::Remove(magazine) => format!(
+                "Magazine {} is in use. Where should its tools go?",
+                magazine + 1
+            ),
+        };
+        ui.colored_label(ui.visuals().warn_fg_color, question);
+        let mut policy = None;
+        ui.horizontal(|ui| {
+            if ui
+                .button("Move to Empty Slots")
+                .on_hover_text("Fills empty slots of this magazine first, then the others")
+                .clicked()
+            {
+                policy = Some(ShrinkPolicy::Relocate);
+            }
+            if ui
+                .button("Unload")
+                .on_hover_text("Tools stay in the library, holders go back to the inventory")
+                .clicked()
+            {
+                policy = Some(ShrinkPolicy::Unload);
+            }
+            if ui.button("Cancel").clicked() {
+                self.singletons.magazine_change = None;
+                self.singletons.editor_error = None;
+            }
+        });
+        if let Some(policy) = policy {
+            self.apply(change, policy);
+        }
+    }
+}
+
+/// A magazine resize or removal, kept while the user decides about the tools in it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MagazineChange {
+    Resize { magazine: usize, capacity: usize },
+    Remove(usize),
+}
+
+/// Name, type and size of every magazine, plus a button to add one. Returns the resize or
+/// removal the user asked for.
+fn magazines_ui(
+    ui: &mut Ui,
+    machine: &mut Machine,
+    enabled: bool,
+    changed: &mut bool,
+) -> Option<MagazineChange> {
+    let mut request = None;
+    ui.add_enabled_ui(enabled, |ui| {
+        Grid::new("edit_machine_magazines")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("Type");
+                ui.strong("Slots");
+                ui.end_row();
+                for (index, magazine) in machine.magazines.iter_mut().enumerate() {
+                    *changed |= ui.text_edit_singleline(&mut magazine.name).changed();
+                    *changed |= optional_choice(ui, ("magazine_kind", index), &mut magazine.kind);
+                    let mut capacity = magazine.slots.len();
+                    if ui
+                        .add(DragValue::new(&mut capacity).range(1..=MAX_CAPACITY))
+                        .changed()
+                    {
+                        request = Some(MagazineChange::Resize {
+                            magazine: index,
+                            capacity,
+                        });
+                    }
+                    if ui.button("Remove").clicked() {
+                        request = Some(MagazineChange::Remove(index));
+                    }
+                    ui.end_row();
+                }
+            });
+        if ui.button("Add Magazine").clicked() {
+            let capacity = machine
+                .magazines
+                .last()
+                .map_or(DEFAULT_CAPACITY, |magazine| magazine.slots.len());
+            let name = format!("Magazine {}", machine.magazines.len() + 1);
+            add_magazine(machine, name, capacity, None);
+            *changed = true;
+        }
+    });
+    request
+}
+
+/// Edits the machine's limits. Returns whether anything changed.
+fn capabilities_ui(ui: &mut Ui, capabilities: &mut MachineCapabilities) -> bool {
+    let mut changed = false;
+    Grid::new("edit_machine_capabilities")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Machine type:");
+            changed |= optional_choice(ui, "machine_kind", &mut capabilities.kind);
+            ui.end_row();
+            ui.label("Spindle taper:");
+            changed |= ui
+                .text_edit_singleline(&mut capabilities.spindle_taper)
+                .changed();
+            ui.end_row();
+            changed |= optional_limit(
+                ui,
+                "Spindle limit:",
+                &mut capabilities.spindle_max_rpm,
+                10000,
+                " RPM",
+            );
+            changed |= optional_limit(
+                ui,
+                "Max tool length:",
+                &mut capabilities.max_tool_length,
+                250.0,
+                " mm",
+            );
+            changed |= optional_limit(
+                ui,
+                "Max tool diameter:",
+                &mut capabilities.max_tool_diameter,
+                80.0,
+                " mm",
+            );
+            changed |= optional_limit(
+                ui,
+                "Max tool weight:",
+                &mut capabilities.max_tool_weight,
+                8000.0,
+                " g",
+            );
+        });
+    changed
+}
+
+/// A grid row with a checkbox that sets the limit to `initial` or clears it.
+fn optional_limit<T: Numeric>(
+    ui: &mut Ui,
+    label: &str,
+    limit: &mut Option<T>,
+    initial: T,
+    suffix: &str,
+) -> bool {
+    let mut changed = false;
+    let mut known = limit.is_some();
+    if ui.checkbox(&mut known, label).changed() {
+        *limit = known.then_some(initial);
+        changed = true;
+    }
+    match limit {
+        Some(value) => {
+            changed |= ui
+                .add(DragValue::new(value).speed(1.0).suffix(suffix))
+                .changed();
+        }
+        None => {
+            ui.weak("Unknown");
+        }
+    }
+    ui.end_row();
+    changed
+}
+
+/// A combo box over every variant of `T` plus "Unknown" for `None`.
+fn optional_choice<T>(ui: &mut Ui, id: impl std::hash::Hash, value: &mut Option<T>) -> bool
+where
+    T: enum_iterator::Sequence + PartialEq + Copy + std::fmt::Display,
+{
+    let before = *value;
+    let text = value.map_or("Unknown".to_string(), |value| value.to_string());
+    ComboBox::from_id_source(id)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, None, "Unknown");
+            for option in enum_iterator::all::<T>() {
+                ui.selectable_value(value, Some(option), option.to_string());
+            }
+        });
+    *value != before
 }
@@ -0,0 +1,281 @@
+//! Grid rows shared by the tool and holder editors.
+
+use egui::{ComboBox, DragValue, Ui};
+use shared::{
+    compatibility::{check_in_machine, has_errors, Issue, Severity},
+    holders::holder::Holder,
+    machine::Machine,
+    placement::{slot, SlotAddress},
+    tools::tool::{RotatingTool, Tool, TurningTool},
+    validation::FieldError,
+};
+
+pub fn float_row(ui: &mut Ui, label: &str, value: &mut f32, suffix: &str) {
+    ui.label(label);
+    ui.add(DragValue::new(value).speed(0.1).suffix(suffix));
+    ui.end_row();
+}
+
+pub fn integer_row(ui: &mut Ui, label: &str, value: &mut u32, suffix: &str) {
+    ui.label(label);
+    ui.add(DragValue::new(value).speed(10.0).suffix(suffix));
+    ui.end_row();
+}
+
+pub fn count_row(ui: &mut Ui, label: &str, value: &mut usize) {
+    ui.label(label);
+    ui.add(DragValue::new(value).range(1..=1000));
+    ui.end_row();
+}
+
+pub fn range_row(ui: &mut Ui, label: &str, range: &mut (f32, f32), suffix: &str) {
+    ui.label(label);
+    ui.horizontal(|ui| {
+        ui.add(DragValue::new(&mut range.0).speed(0.1).suffix(suffix));
+        ui.label("to");
+        ui.add(DragValue::new(&mut range.1).speed(0.1).suffix(suffix));
+    });
+    ui.end_row();
+}
+
+pub fn text_row(ui: &mut Ui, label: &str, value: &mut String) {
+    ui.label(label);
+    ui.text_edit_singleline(value);
+    ui.end_row();
+}
+
+pub fn checkbox_row(ui: &mut Ui, label: &str, value: &mut bool) {
+    ui.label(label);
+    ui.checkbox(value, "");
+    ui.end_row();
+}
+
+/// A combo box row over every value of a `Sequence` enum.
+pub fn choice_row<T>(ui: &mut Ui, label: &str, value: &mut T)
+where
+    T: enum_iterator::Sequence + PartialEq + std::fmt::Display,
+{
+    ui.label(label);
+    ComboBox::from_id_source(label)
+        .selected_text(value.to_string())
+        .show_ui(ui, |ui| {
+            for option in enum_iterator::all::<T>() {
+                let text = option.to_string();
+                ui.selectable_value(value, option, text);
+            }
+        });
+    ui.end_row();
+}
+
+pub fn rotating_tool_rows(ui: &mut Ui, tool: &mut RotatingTool) {
+    choice_row(ui, "Category", &mut tool.category);
+    float_row(ui, "Cutting Diameter", &mut tool.cutting_diameter, " mm");
+    float_row(
+        ui,
+        "Connection Diameter",
+        &mut tool.connection_diameter,
+        " mm",
+    );
+    float_row(ui, "Usable Length", &mut tool.usable_length, " mm");
+    float_row(ui, "Functional Length", &mut tool.functional_length, " mm");
+    float_row(
+        ui,
+        "Achievable Hole Tolerance",
+        &mut tool.achievable_hole_tolerance,
+        " mm",
+    );
+    float_row(ui, "Weight of Tool", &mut tool.weight_of_tool, " g");
+    integer_row(ui, "Max RPM", &mut tool.max_rpm, " RPM");
+    integer_row(ui, "Coolant Pressure", &mut tool.coolant_pressure, " BAR");
+}
+
+pub fn turning_tool_rows(ui: &mut Ui, tool: &mut TurningTool) {
+    choice_row(ui, "Category", &mut tool.category);
+    choice_row(ui, "Handedness", &mut tool.handedness);
+    text_row(ui, "Insert Type", &mut tool.insert_type);
+    float_row(ui, "Lead Angle", &mut tool.lead_angle, "°");
+    float_row(ui, "Cutting Edge Angle", &mut tool.cutting_edge_angle, "°");
+    float_row(
+        ui,
+        "Maximum Ramping Angle",
+        &mut tool.maximum_ramping_angle,
+        "°",
+    );
+    float_row(
+        ui,
+        "Workpiece Side Body Angle",
+        &mut tool.workpiece_side_body_angle,
+        "°",
+    );
+    float_row(
+        ui,
+        "Machine Side Body Angle",
+        &mut tool.machine_side_body_angle,
+        "°",
+    );
+    if tool.uses_bore_diameter() {
+        float_row(
+            ui,
+            "Minimum Bore Diameter",
+            &mut tool.minimum_bore_diameter,
+            " mm",
+        );
+    }
+    float_row(
+        ui,
+        "Cutting Depth Maximum",
+        &mut tool.cutting_depth_maximum,
+        " mm",
+    );
+    float_row(ui, "Minimum Overhang", &mut tool.minimum_overhang, " mm");
+    float_row(ui, "Maximum Overhang", &mut tool.maximum_overhang, " mm");
+    float_row(ui, "Usable Length", &mut tool.usable_length, " mm");
+    float_row(ui, "Body Length", &mut tool.body_length, " mm");
+    float_row(ui, "Body Diameter", &mut tool.body_diameter, " mm");
+    float_row(
+        ui,
+        "Functional Diameter",
+        &mut tool.functional_diameter,
+        " mm",
+    );
+    float_row(
+        ui,
+        "Peripheral Effective Cutting",
+        &mut tool.peripheral_effective_cutting,
+        " mm",
+    );
+    float_row(
+        ui,
+        "Connection Diameter",
+        &mut tool.connection_diameter,
+        " mm",
+    );
+    integer_row(ui, "Maximum RPM", &mut tool.maximum_rpm, " RPM");
+    float_row(ui, "Tool Weight", &mut tool.tool_weight, " g");
+}
+
+/// One entry per holder category, ignoring subcategories.
+pub fn holder_kinds<T: enum_iterator::Sequence>(same_kind: fn(&T, &T) -> bool) -> Vec<T> {
+    let mut kinds: Vec<T> = enum_iterator::all::<T>().collect();
+    kinds.dedup_by(|a, b| same_kind(a, b));
+    kinds
+}
+
+/// Category and, when the category has more than one, subcategory rows for a holder.
+pub fn holder_category_rows<T>(
+    ui: &mut Ui,
+    value: &mut T,
+    same_kind: fn(&T, &T) -> bool,
+    name: fn(&T) -> &'static str,
+) where
+    T: enum_iterator::Sequence + PartialEq + Clone + std::fmt::Display,
+{
+    ui.label("Category");
+    ComboBox::from_id_source("holder_category")
+        .selected_text(name(value))
+        .show_ui(ui, |ui| {
+            for kind in holder_kinds(same_kind) {
+                if ui
+                    .selectable_label(same_kind(value, &kind), name(&kind))
+                    .clicked()
+                {
+                    *value = kind;
+                }
+            }
+        });
+    ui.end_row();
+
+    let subcategories: Vec<T> = enum_iterator::all::<T>()
+        .filter(|option| same_kind(option, value))
+        .collect();
+    if subcategories.len() > 1 {
+        ui.label("Type");
+        ComboBox::from_id_source("holder_subcategory")
+            .selected_text(value.to_string())
+            .show_ui(ui, |ui| {
+                for option in subcategories {
+                    let text = option.to_string();
+                    ui.selectable_value(value, option, text);
+                }
+            });
+        ui.end_row();
+    }
+}
+
+pub fn validation_errors(ui: &mut Ui, errors: &[FieldError]) {
+    for error in errors {
+        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+    }
+}
+
+/// Lists compatibility issues, errors first.
+pub fn compatibility_issues(ui: &mut Ui, issues: &[Issue]) {
+    for issue in issues {
+        let color = match issue.severity() {
+            Severity::Error => ui.visuals().error_fg_color,
+            Severity::Warning => ui.visuals().warn_fg_color,
+        };
+        ui.colored_label(color, issue.to_string());
+    }
+}
+
+/// Compatibility issues of the slot at `target` once `tool` or `holder` is put into it,
+/// including the machine's limits.
+pub fn target_issues(
+    machine: &Machine,
+    target: SlotAddress,
+    tool: Option<&Tool>,
+    holder: Option<&Holder>,
+) -> Vec<Issue> {
+    slot(machine, target)
+        .map(|slot| check_in_machine(machine, slot, tool, holder))
+        .unwrap_or_default()
+}
+
+/// Whether the settings forbid an assembly with these issues.
+pub fn is_blocked(block_incompatible: bool, issues: &[Issue]) -> bool {
+    block_incompatible && has_errors(issues)
+}
+
+/// Lets the user pick a magazine and one of its slots. Keeps `target` inside the machine.
+pub fn slot_picker(ui: &mut Ui, id: &str, machine: &Machine, target: &mut SlotAddress) {
+    if machine.magazines.is_empty() {
+        ui.weak("This machine has no magazines");
+        return;
+    }
+    target.magazine = target.magazine.min(machine.magazines.len() - 1);
+    let magazine = &machine.magazines[target.magazine];
+    target.slot = target.slot.min(magazine.slots.len().saturating_sub(1));
+
+    ui.horizontal(|ui| {
+        ui.label("Magazine:");
+        ComboBox::from_id_source(format!("{}_magazine", id))
+            .selected_text(&magazine.name)
+            .show_ui(ui, |ui| {
+                for (index, magazine) in machine.magazines.iter().enumerate() {
+                    ui.selectable_value(&mut target.magazine, index, &magazine.name);
+                }
+            });
+
+        ui.label("Slot:");
+        ComboBox::from_id_source(format!("{}_slot", id))
+            .selected_text(format!("{}", target.slot + 1))
+            .show_ui(ui, |ui| {
+                for (index, slot) in magazine.slots.iter().enumerate() {
+                    let mut text = format!("{}", index + 1);
+                    if let Some(tool_number) = &slot.tool_number {
+                        text.push_str(&format!(" {}", tool_number));
+                    }
+                    if let Some(tool) = &slot.tool {
+                        text.push_str(&format!(" - {}", tool.summary()));
+                    }
+                    if slot.locked {
+                        text.push_str(" (reserved)");
+                    }
+                    ui.add_enabled_ui(!slot.locked, |ui| {
+                        ui.selectable_value(&mut target.slot, index, text);
+                    });
+                }
+            });
+    });
+}
@@ -0,0 +1,299 @@
+use crate::{
+    app_states::WidgetState, singletons::Singletons, widgets::editor_fields::holder_kinds,
+};
+use egui::{ComboBox, Context, DragValue, Id, Ui, Window};
+use shared::{
+    filter::{Filter, SavedFilter},
+    holders::holder::{RotatingHolderCategory, TurningHolderCategory},
+    sorting::SortField,
+    tools::tool::{RotatingToolCategory, TurningToolCategory},
+    User,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterKind {
+    All,
+    And,
+    Or,
+    Not,
+    RotatingToolCategory,
+    TurningToolCategory,
+    RotatingHolderCategory,
+    TurningHolderCategory,
+    Range,
+}
+
+const FILTER_KINDS: [FilterKind; 9] = [
+    FilterKind::All,
+    FilterKind::And,
+    FilterKind::Or,
+    FilterKind::Not,
+    FilterKind::RotatingToolCategory,
+    FilterKind::TurningToolCategory,
+    FilterKind::RotatingHolderCategory,
+    FilterKind::TurningHolderCategory,
+    FilterKind::Range,
+];
+
+impl FilterKind {
+    fn of(filter: &Filter) -> Self {
+        match filter {
+            Filter::All => FilterKind::All,
+            Filter::And(_) => FilterKind::And,
+            Filter::Or(_) => FilterKind::Or,
+            Filter::Not(_) => FilterKind::Not,
+            Filter::RotatingToolCategory(_) => FilterKind::RotatingToolCategory,
+            Filter::TurningToolCategory(_) => FilterKind::TurningToolCategory,
+            Filter::RotatingHolderCategory(_) => FilterKind::RotatingHolderCategory,
+            Filter::TurningHolderCategory(_) => FilterKind::TurningHolderCategory,
+            Filter::Range { .. } => FilterKind::Range,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            FilterKind::All => "Everything",
+            FilterKind::And => "All of",
+            FilterKind::Or => "Any of",
+            FilterKind::Not => "Not",
+            FilterKind::RotatingToolCategory => "Rotating tool",
+            FilterKind::TurningToolCategory => "Turning tool",
+            FilterKind::RotatingHolderCategory => "Rotating holder",
+            FilterKind::TurningHolderCategory => "Turning holder",
+            FilterKind::Range => "Value range",
+        }
+    }
+
+    /// Turns `current` into a filter of this kind. Groups wrap what was there before.
+    fn convert(&self, current: Filter) -> Filter {
+        match self {
+            FilterKind::All => Filter::All,
+            FilterKind::And => Filter::And(vec![current]),
+            FilterKind::Or => Filter::Or(vec![current]),
+            FilterKind::Not => Filter::Not(Box::new(current)),
+            FilterKind::RotatingToolCategory => {
+                Filter::RotatingToolCategory(RotatingToolCategory::EndMill)
+            }
+            FilterKind::TurningToolCategory => {
+                Filter::TurningToolCategory(TurningToolCategory::ExternalTurningTool)
+            }
+            FilterKind::RotatingHolderCategory => Filter::RotatingHolderCategory(
+                holder_kinds(RotatingHolderCategory::same_kind)[1].clone(),
+            ),
+            FilterKind::TurningHolderCategory => Filter::TurningHolderCategory(
+                holder_kinds(TurningHolderCategory::same_kind)[1].clone(),
+            ),
+            FilterKind::Range => Filter::Range {
+                field: SortField::ToolDiameter,
+                min: None,
+                max: None,
+            },
+        }
+    }
+}
+
+pub struct FilterWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+}
+
+impl<'a> FilterWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+
+        Window::new("Filter").open(open).show(ctx, |ui| {
+            filter_editor(
+                ui,
+                Id::new("filter_editor"),
+                &mut self.singletons.active_filter,
+            );
+            ui.separator();
+            ui.label(format!("Showing: {}", self.singletons.active_filter));
+
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.singletons.filter_name);
+                let name = self.singletons.filter_name.trim().to_string();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                    .clicked()
+                {
+                    let saved = SavedFilter {
+                        name: name.clone(),
+                        filter: self.singletons.active_filter.clone(),
+                    };
+                    let saved_filters = &mut self.user.user_data.saved_filters;
+                    match saved_filters.iter_mut().find(|f| f.name == name) {
+                        Some(existing) => *existing = saved,
+                        None => saved_filters.push(saved),
+                    }
+                    self.singletons.should_save_user_data = true;
+                }
+                if ui.button("Clear").clicked() {
+                    self.singletons.active_filter = Filter::All;
+                    self.singletons.filter_name.clear();
+                }
+            });
+
+            ui.separator();
+            ui.heading("Saved Filters");
+            if self.user.user_data.saved_filters.is_empty() {
+                ui.weak("No saved filters");
+            }
+            let mut delete = None;
+            for (index, saved) in self.user.user_data.saved_filters.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(&saved.name)
+                        .on_hover_text(saved.filter.to_string());
+                    if ui.button("Apply").clicked() {
+                        self.singletons.active_filter = saved.filter.clone();
+                        self.singletons.filter_name = saved.name.clone();
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = delete {
+                self.user.user_data.saved_filters.remove(index);
+                self.singletons.should_save_user_data = true;
+            }
+
+            ui.separator();
+            if ui.button("Close").clicked() {
+                *self.widget_state = WidgetState::Default;
+                should_close = true;
+            }
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+}
+
+fn filter_editor(ui: &mut Ui, id: Id, filter: &mut Filter) {
+    let kind = FilterKind::of(filter);
+    let mut new_kind = kind;
+    ui.horizontal(|ui| {
+        ComboBox::from_id_source(id.with("kind"))
+            .selected_text(kind.label())
+            .show_ui(ui, |ui| {
+                for option in FILTER_KINDS {
+                    ui.selectable_value(&mut new_kind, option, option.label());
+                }
+            });
+
+        match filter {
+            Filter::RotatingToolCategory(category) => {
+                ComboBox::from_id_source(id.with("category"))
+                    .selected_text(category.to_string())
+                    .show_ui(ui, |ui| {
+                        for option in enum_iterator::all::<RotatingToolCategory>() {
+                            let text = option.to_string();
+                            ui.selectable_value(category, option, text);
+                        }
+                    });
+            }
+            Filter::TurningToolCategory(category) => {
+                ComboBox::from_id_source(id.with("category"))
+                    .selected_text(category.to_string())
+                    .show_ui(ui, |ui| {
+                        for option in enum_iterator::all::<TurningToolCategory>() {
+                            let text = option.to_string();
+                            ui.selectable_value(category, option, text);
+                        }
+                    });
+            }
+            Filter::RotatingHolderCategory(category) => {
+                ComboBox::from_id_source(id.with("category"))
+                    .selected_text(category.name())
+                    .show_ui(ui, |ui| {
+                        for option in holder_kinds(RotatingHolderCategory::same_kind) {
+                            let text = option.name();
+                            ui.selectable_value(category, option, text);
+                        }
+                    });
+            }
+            Filter::TurningHolderCategory(category) => {
+                ComboBox::from_id_source(id.with("category"))
+                    .selected_text(category.name())
+                    .show_ui(ui, |ui| {
+                        for option in holder_kinds(TurningHolderCategory::same_kind) {
+                            let text = option.name();
+                            ui.selectable_value(category, option, text);
+                        }
+                    });
+            }
+            Filter::Range { field, min, max } => {
+                ComboBox::from_id_source(id.with("field"))
+                    .selected_text(field.to_string())
+                    .show_ui(ui, |ui| {
+                        for option in enum_iterator::all::<SortField>()
+                            .filter(|option| *option != SortField::Index)
+                        {
+                            ui.selectable_value(field, option, option.to_string());
+                        }
+                    });
+                bound_editor(ui, "min", min);
+                bound_editor(ui, "max", max);
+            }
+            _ => {}
+        }
+    });
+
+    if new_kind != kind {
+        *filter = new_kind.convert(std::mem::take(filter));
+        return;
+    }
+
+    match filter {
+        Filter::And(filters) | Filter::Or(filters) => {
+            ui.indent(id.with("children"), |ui| {
+                let mut remove = None;
+                for (index, child) in filters.iter_mut().enumerate() {
+                    ui.horizontal_top(|ui| {
+                        if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                            remove = Some(index);
+                        }
+                        ui.vertical(|ui| filter_editor(ui, id.with(index), child));
+                    });
+                }
+                if let Some(index) = remove {
+                    filters.remove(index);
+                }
+                if ui.button("Add Condition").clicked() {
+                    filters.push(Filter::All);
+                }
+            });
+        }
+        Filter::Not(child) => {
+            ui.indent(id.with("children"), |ui| {
+                filter_editor(ui, id.with(0), child)
+            });
+        }
+        _ => {}
+    }
+}
+
+fn bound_editor(ui: &mut Ui, label: &str, bound: &mut Option<f32>) {
+    let mut enabled = bound.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *bound = enabled.then_some(0.0);
+    }
+    if let Some(value) = bound {
+        ui.add(DragValue::new(value).speed(0.1));
+    }
+}
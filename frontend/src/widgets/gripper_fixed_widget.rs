@@ -202,7 +202,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
                             });
                     });
                 ui.end_row();
-                ui.label(format!("Maximum possible nr. of pieces:"));
+                ui.label("Maximum possible nr. of pieces:");
                 ui.label(format!("{:.0}", data.total_possible_pieces.floor()));
                 ui.end_row();
                 ui.label("Unused material:");
@@ -368,7 +368,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let safety_margin_start = chuck_end;
         let safety_margin_end = safety_margin_start + data.desired_safety_margin_end * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             safety_margin_start,
             safety_margin_end,
             SEGMENT_HEIGHT,
@@ -385,7 +385,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let gripper_extension_end =
             gripper_extension_start + data.gripper_overextension * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             gripper_extension_start,
             gripper_extension_end,
             SEGMENT_HEIGHT,
@@ -406,7 +406,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
 
         let gripping_point_text = format!("Gripping Point: {:.2} mm", data.gripping_point);
         self.draw_text(
-            &painter,
+            painter,
             &gripping_point_text,
             Pos2::new(gripper_extension_end, above_bar_y - arrow_length - 20.0),
             data.gripper_color,
@@ -416,7 +416,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let offset_from_cut_start = gripper_extension_end;
         let offset_from_cut_end = offset_from_cut_start + data.margin_from_cut * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             offset_from_cut_start,
             offset_from_cut_end,
             SEGMENT_HEIGHT,
@@ -432,7 +432,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let cutter_start = offset_from_cut_end;
         let cutter_end = cutter_start + data.cutter_width * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             cutter_start,
             cutter_end,
             SEGMENT_HEIGHT,
@@ -448,7 +448,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let left_facing_stock_start = cutter_end;
         let left_facing_stock_end = left_facing_stock_start + data.left_facing_stock * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             left_facing_stock_start,
             left_facing_stock_end,
             SEGMENT_HEIGHT,
@@ -464,7 +464,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let workpiece_start = left_facing_stock_end;
         let workpiece_end = workpiece_start + data.workpiece_length * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             workpiece_start,
             workpiece_end,
             SEGMENT_HEIGHT,
@@ -481,7 +481,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         let right_facing_stock_end =
             right_facing_stock_start + data.right_facing_stock * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             right_facing_stock_start,
             right_facing_stock_end,
             SEGMENT_HEIGHT,
@@ -504,7 +504,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
             Stroke::new(1.0, data.z_zero_color),
         );
         self.draw_text(
-            &painter,
+            painter,
             "Z Zero",
             Pos2::new(z_zero_start, above_bar_y - z_zero_height_offset - 20.0),
             data.z_zero_color,
@@ -521,6 +521,7 @@ impl<'a> LatheBarGripperFixedWindow<'a> {
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_section(
         &self,
         painter: &Painter,
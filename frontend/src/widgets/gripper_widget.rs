@@ -209,7 +209,7 @@ impl<'a> LatheBarGripperWindow<'a> {
                             });
                     });
                 ui.end_row();
-                ui.label(format!("Maximum possible nr. of pieces:"));
+                ui.label("Maximum possible nr. of pieces:");
                 ui.label(format!("{:.0}", data.total_possible_pieces.floor()));
                 ui.end_row();
                 ui.label("Unused material:");
@@ -375,7 +375,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         let safety_margin_start = chuck_end;
         let safety_margin_end = safety_margin_start + data.desired_safety_margin_end * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             safety_margin_start,
             safety_margin_end,
             SEGMENT_HEIGHT,
@@ -419,7 +419,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         //     data.gripper_color,
         // );
         self.draw_claw(
-            &painter,
+            painter,
             gripper_extension_end,
             1.0,
             self.gripper_calculation_data.bar_diameter,
@@ -437,7 +437,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         let offset_from_cut_start = gripper_extension_end;
         let offset_from_cut_end = offset_from_cut_start + data.margin_from_cut * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             offset_from_cut_start,
             offset_from_cut_end,
             SEGMENT_HEIGHT,
@@ -453,7 +453,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         let cutter_start = offset_from_cut_end;
         let cutter_end = cutter_start + data.cutter_width * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             cutter_start,
             cutter_end,
             SEGMENT_HEIGHT,
@@ -469,7 +469,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         let left_facing_stock_start = cutter_end;
         let left_facing_stock_end = left_facing_stock_start + data.left_facing_stock * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             left_facing_stock_start,
             left_facing_stock_end,
             SEGMENT_HEIGHT,
@@ -485,7 +485,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         let workpiece_start = left_facing_stock_end;
         let workpiece_end = workpiece_start + data.workpiece_length * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             workpiece_start,
             workpiece_end,
             SEGMENT_HEIGHT,
@@ -502,7 +502,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         let right_facing_stock_end =
             right_facing_stock_start + data.right_facing_stock * scale_factor;
         self.draw_section(
-            &painter,
+            painter,
             right_facing_stock_start,
             right_facing_stock_end,
             SEGMENT_HEIGHT,
@@ -525,7 +525,7 @@ impl<'a> LatheBarGripperWindow<'a> {
             Stroke::new(1.0, data.z_zero_color),
         );
         self.draw_text(
-            &painter,
+            painter,
             "Z Zero",
             Pos2::new(z_zero_start, above_bar_y - z_zero_height_offset - 20.0),
             data.z_zero_color,
@@ -542,6 +542,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_claw(
         &self,
         painter: &Painter,
@@ -562,7 +563,6 @@ impl<'a> LatheBarGripperWindow<'a> {
         let target_y = center_y - diameter / 2.0;
 
         // Calculate the angle needed for alignment
-        let dx = leg_length;
         let dy = target_y - center_y;
         let required_angle = (dy / arm_length).atan();
 
@@ -604,6 +604,7 @@ impl<'a> LatheBarGripperWindow<'a> {
         required_angle
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_section(
         &self,
         painter: &Painter,
@@ -0,0 +1,208 @@
+use crate::{
+    app_states::WidgetState,
+    singletons::Singletons,
+    widgets::editor_fields::{is_blocked, slot_picker, target_issues},
+};
+use egui::{Context, Grid, Ui, Window};
+use shared::{
+    custom_traits::GetUuid,
+    inventory::{InventoryError, Shortage},
+    User,
+};
+
+/// What a button in the report asked for, applied after the report is drawn.
+enum Action {
+    AddCopy(String),
+    RemoveCopy(String),
+    Place(String),
+}
+
+pub struct HolderInventoryWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    /// Machine that holders are put into
+    machine_index: Option<usize>,
+}
+
+impl<'a> HolderInventoryWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        machine_index: Option<usize>,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            machine_index,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+
+        Window::new("Holder Inventory").open(open).show(ctx, |ui| {
+            if self.user.user_data.holder_inventory.holders().is_empty() {
+                ui.label("The inventory is empty. Holders added with Add Holder show up here.");
+            } else {
+                self.place_target_ui(ui);
+                ui.separator();
+                if let Some(action) = self.report_ui(ui) {
+                    self.apply(action);
+                }
+            }
+            if let Some(error) = &self.singletons.editor_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.separator();
+            if ui.button("Close").clicked() {
+                self.singletons.editor_error = None;
+                *self.widget_state = WidgetState::Default;
+                should_close = true;
+            }
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+
+    fn place_target_ui(&mut self, ui: &mut Ui) {
+        let Some(machine) = self
+            .machine_index
+            .and_then(|index| self.user.user_data.machines.get(index))
+        else {
+            ui.weak("Select a machine to put holders into it");
+            return;
+        };
+        ui.label(format!("Put into {}", machine.name));
+        slot_picker(
+            ui,
+            "holder_inventory",
+            machine,
+            &mut self.singletons.slot_target,
+        );
+    }
+
+    fn report_ui(&self, ui: &mut Ui) -> Option<Action> {
+        let can_place = self
+            .machine_index
+            .and_then(|index| self.user.user_data.machines.get(index))
+            .is_some_and(|machine| !machine.magazines.is_empty());
+        let mut action = None;
+
+        Grid::new("holder_inventory_grid")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Holder");
+                ui.strong("Owned");
+                ui.strong("In Use");
+                ui.strong("Free");
+                ui.end_row();
+                let report = self
+                    .user
+                    .user_data
+                    .holder_inventory
+                    .report(&self.user.user_data.machines);
+                for stock in report {
+                    let uuid = stock.holder.get_uuid();
+                    ui.label(stock.holder.summary())
+                        .on_hover_ui(|ui| stock.holder.hover_ui(ui));
+                    ui.label(stock.owned.to_string());
+                    if stock.missing() > 0 {
+                        ui.colored_label(ui.visuals().warn_fg_color, stock.in_use.to_string())
+                            .on_hover_text(format!(
+                                "{} more in slots than owned, add copies with +",
+                                stock.missing()
+                            ));
+                    } else {
+                        ui.label(stock.in_use.to_string());
+                    }
+                    ui.label(stock.free().to_string());
+                    ui.horizontal(|ui| {
+                        if ui.button("+").on_hover_text("Add a copy").clicked() {
+                            action = Some(Action::AddCopy(uuid.clone()));
+                        }
+                        if ui
+                            .add_enabled(stock.free() > 0, egui::Button::new("-"))
+                            .on_hover_text("Remove a free copy")
+                            .clicked()
+                        {
+                            action = Some(Action::RemoveCopy(uuid.clone()));
+                        }
+                        if ui
+                            .add_enabled(can_place && stock.free() > 0, egui::Button::new("Put"))
+                            .on_hover_text("Put a free copy into the selected slot")
+                            .clicked()
+                        {
+                            action = Some(Action::Place(uuid.clone()));
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        action
+    }
+
+    fn apply(&mut self, action: Action) {
+        if let (Action::Place(uuid), Some(machine_index)) = (&action, self.machine_index) {
+            let issues = target_issues(
+                &self.user.user_data.machines[machine_index],
+                self.singletons.slot_target,
+                None,
+                self.user.user_data.holder_inventory.get(uuid),
+            );
+            if is_blocked(
+                self.user.user_data.settings.block_incompatible_assemblies,
+                &issues,
+            ) {
+                self.singletons.editor_error = Some(format!("Not put in: {}", issues[0]));
+                return;
+            }
+        }
+        let user_data = &mut self.user.user_data;
+        let inventory = &mut user_data.holder_inventory;
+        let result: Result<(), InventoryError> = match action {
+            Action::AddCopy(uuid) => inventory
+                .add_copy(&mut user_data.machines, &uuid)
+                .map(|_| ()),
+            Action::RemoveCopy(uuid) => inventory
+                .remove_copy(&mut user_data.machines, &uuid)
+                .map(|_| ()),
+            Action::Place(uuid) => match self.machine_index {
+                Some(machine_index) => inventory
+                    .place(
+                        &mut user_data.machines,
+                        machine_index,
+                        self.singletons.slot_target,
+                        &uuid,
+                        &self.user.username,
+                    )
+                    .map(|_| ()),
+                None => Ok(()),
+            },
+        };
+        match result {
+            Ok(()) => {
+                self.singletons.editor_error = None;
+                self.singletons.should_save_user_data = true;
+            }
+            Err(e) => self.singletons.editor_error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Tells the user which holders are in more slots than owned, `None` when all are owned.
+pub fn shortage_warning(shortages: &[Shortage]) -> Option<String> {
+    if shortages.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = shortages.iter().map(Shortage::to_string).collect();
+    Some(format!(
+        "Add the missing copies in the Holder Inventory:\n{}",
+        lines.join("\n")
+    ))
+}
@@ -1,6 +1,20 @@
-use crate::singletons::Singletons;
-use egui::{Response, Ui, Widget};
-use shared::User;
+use crate::{singletons::Singletons, widgets::editor_fields::compatibility_issues};
+use egui::{ComboBox, Frame, Grid, Id, Response, ScrollArea, Ui, Widget};
+use shared::{
+    assembly::slot_assembly,
+    compatibility::{check_in_machine, has_errors},
+    custom_traits::GetName,
+    filter::Filter,
+    machine::Machine,
+    placement::{MachineSlot, SlotAddress},
+    slot::Slot,
+    sorting::{SlotSort, SortDirection, SortField},
+    transfer::{apply, free_slot, Transfer, TransferError},
+    User,
+};
+
+/// Descriptions longer than this are cut off in the table, hovering shows the full text.
+const DESCRIPTION_PREVIEW_CHARS: usize = 40;
 
 pub struct MagazineWidget<'a> {
     user: &'a mut User,
@@ -14,8 +28,340 @@ impl<'a> MagazineWidget<'a> {
 }
 
 impl<'a> Widget for MagazineWidget<'a> {
-    fn ui(mut self, ui: &mut Ui) -> Response {
-        ui.centered_and_justified(|ui| ui.label("Magazine Widget"))
-            .response
+    fn ui(self, ui: &mut Ui) -> Response {
+        let machines = &self.user.user_data.machines;
+        let machine_index = self.user.user_data.selections.selected_machine;
+        let selected_machine = machine_index.and_then(|index| machines.get(index));
+        let (Some(machine_index), Some(machine)) = (machine_index, selected_machine) else {
+            return ui
+                .centered_and_justified(|ui| ui.label("Select or add a machine"))
+                .response;
+        };
+        if machine.magazines.is_empty() {
+            return ui
+                .centered_and_justified(|ui| ui.label("This machine has no magazines"))
+                .response;
+        }
+
+        let singletons = self.singletons;
+        let mut dropped = None;
+        let response = ui
+            .vertical(|ui| {
+                ui.horizontal(|ui| {
+                    sort_controls(ui, &mut singletons.slot_sort);
+                    filter_controls(ui, &mut singletons.active_filter);
+                    ui.separator();
+                    ui.weak(
+                        "Drag a slot number onto another slot to move or swap, hold Ctrl to copy",
+                    );
+                });
+                if let Some(transfer) = machine_targets(ui, machines, machine_index) {
+                    dropped = Some(transfer);
+                }
+                if let Some(error) = &singletons.transfer_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.separator();
+                ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for (index, magazine) in machine.magazines.iter().enumerate() {
+                            ui.heading(&magazine.name);
+                            let table = MagazineTable {
+                                machine_index,
+                                machine,
+                                magazine_index: index,
+                                slot_sort: &singletons.slot_sort,
+                                filter: &singletons.active_filter,
+                            };
+                            if let Some(transfer) = table.ui(ui) {
+                                dropped = Some(Ok(transfer));
+                            }
+                            ui.add_space(12.0);
+                        }
+                    });
+            })
+            .response;
+
+        let user = self.user;
+        let result = dropped.map(|transfer| {
+            transfer.and_then(|transfer| {
+                apply(
+                    &mut user.user_data.machines,
+                    &user.user_data.holder_inventory,
+                    transfer,
+                    &user.username,
+                    user.user_data.settings.block_incompatible_assemblies,
+                )
+            })
+        });
+        match result {
+            Some(Ok(())) => {
+                singletons.transfer_error = None;
+                // Undone from the top panel like any other edit, one transfer at a time
+                singletons.undo_separately = true;
+                singletons.should_save_user_data = true;
+            }
+            Some(Err(e)) => singletons.transfer_error = Some(e.to_string()),
+            None => {}
+        }
+        response
+    }
+}
+
+/// The other machines, as targets for dropping a slot onto. The slot goes into the first
+/// free slot of the machine it is dropped on.
+fn machine_targets(
+    ui: &mut Ui,
+    machines: &[Machine],
+    machine_index: usize,
+) -> Option<Result<Transfer, TransferError>> {
+    if machines.len() < 2 {
+        return None;
+    }
+    let mut dropped = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.weak("Drop onto another machine:");
+        for (index, machine) in machines.iter().enumerate() {
+            if index == machine_index {
+                continue;
+            }
+            let (_, payload) = ui.dnd_drop_zone::<MachineSlot, _>(
+                Frame::group(ui.style()).inner_margin(4.0),
+                |ui| ui.label(machine.get_name()),
+            );
+            if let Some(from) = payload {
+                let from = *from;
+                let copy = ui.input(|input| input.modifiers.command);
+                dropped = Some(free_slot(machines, index).map(|to| {
+                    if copy {
+                        Transfer::Copy { from, to }
+                    } else {
+                        Transfer::Move { from, to }
+                    }
+                }));
+            }
+        }
+    });
+    dropped
+}
+
+fn sort_controls(ui: &mut Ui, slot_sort: &mut SlotSort) {
+    ComboBox::from_label("Sort by")
+        .selected_text(slot_sort.field.to_string())
+        .show_ui(ui, |ui| {
+            for field in enum_iterator::all::<SortField>() {
+                ui.selectable_value(&mut slot_sort.field, field, field.to_string());
+            }
+        });
+    let (arrow, next) = match slot_sort.direction {
+        SortDirection::Ascending => ("⬆", SortDirection::Descending),
+        SortDirection::Descending => ("⬇", SortDirection::Ascending),
+    };
+    if ui
+        .button(format!("{} {}", arrow, slot_sort.direction))
+        .clicked()
+    {
+        slot_sort.direction = next;
+    }
+}
+
+fn filter_controls(ui: &mut Ui, filter: &mut Filter) {
+    if *filter == Filter::All {
+        return;
+    }
+    ui.separator();
+    ui.label(format!("Filter: {}", filter));
+    if ui.button("Clear Filter").clicked() {
+        *filter = Filter::All;
+    }
+}
+
+/// One magazine of the selected machine. Slots can be dragged onto each other by their
+/// number.
+struct MagazineTable<'a> {
+    machine_index: usize,
+    machine: &'a Machine,
+    magazine_index: usize,
+    slot_sort: &'a SlotSort,
+    filter: &'a Filter,
+}
+
+impl MagazineTable<'_> {
+    /// Returns the transfer asked for by dropping one slot on another.
+    fn ui(&self, ui: &mut Ui) -> Option<Transfer> {
+        let magazine = &self.machine.magazines[self.magazine_index];
+        let mut dropped = None;
+        Grid::new(format!(
+            "magazine_table_{}_{}",
+            self.machine.uuid, self.magazine_index
+        ))
+        .num_columns(8)
+        .striped(true)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.strong("Slot");
+            ui.strong("Tool Number");
+            ui.strong("Tool");
+            ui.strong("Holder");
+            ui.strong("Overhang");
+            ui.strong("Description");
+            ui.strong("Assembly");
+            ui.strong("Compatibility");
+            ui.end_row();
+
+            for index in self
+                .slot_sort
+                .sorted_indices(magazine)
+                .into_iter()
+                .filter(|&index| self.filter.matches(index, &magazine.slots[index]))
+            {
+                let slot = &magazine.slots[index];
+                if let Some(transfer) = self.slot_handle(ui, index, slot) {
+                    dropped = Some(transfer);
+                }
+                slot_row(ui, slot, self.machine);
+                ui.end_row();
+            }
+        });
+        dropped
+    }
+
+    /// The slot number, which is dragged to move, swap or copy the slot.
+    fn slot_handle(&self, ui: &mut Ui, index: usize, slot: &Slot) -> Option<Transfer> {
+        let to = MachineSlot {
+            machine: self.machine_index,
+            address: SlotAddress::new(self.magazine_index, index),
+        };
+        let id = Id::new(("slot_handle", &self.machine.uuid, to.address));
+        let mut response = ui
+            .dnd_drag_source(id, to, |ui| ui.label(format!("☰ {}", index + 1)))
+            .response;
+        if slot.locked {
+            response = response.on_hover_text("Reserved");
+        }
+        if response.dnd_hover_payload::<MachineSlot>().is_some() {
+            ui.painter()
+                .rect_stroke(response.rect, 2.0, ui.visuals().selection.stroke);
+        }
+
+        let from = *response.dnd_release_payload::<MachineSlot>()?;
+        Some(if ui.input(|input| input.modifiers.command) {
+            Transfer::Copy { from, to }
+        } else if slot.is_empty() {
+            Transfer::Move { from, to }
+        } else {
+            Transfer::Swap { a: from, b: to }
+        })
+    }
+}
+
+/// Every cell after the slot number.
+fn slot_row(ui: &mut Ui, slot: &Slot, machine: &Machine) {
+    match &slot.tool_number {
+        Some(tool_number) => ui.label(tool_number),
+        None => ui.weak("-"),
+    };
+
+    match &slot.tool {
+        Some(tool) => {
+            ui.label(tool.summary()).on_hover_ui(|ui| tool.hover_ui(ui));
+        }
+        None => {
+            ui.weak("-");
+        }
+    }
+
+    match &slot.holder {
+        Some(holder) => {
+            ui.label(holder.summary())
+                .on_hover_ui(|ui| holder.hover_ui(ui));
+        }
+        None => {
+            ui.weak("-");
+        }
+    }
+
+    match &slot.overhang {
+        Some(overhang) => ui.label(overhang.to_string()),
+        None => ui.weak("-"),
+    };
+
+    match &slot.description {
+        Some(description) if description.text.chars().count() > DESCRIPTION_PREVIEW_CHARS => {
+            let preview: String = description
+                .text
+                .chars()
+                .take(DESCRIPTION_PREVIEW_CHARS)
+                .collect();
+            ui.label(format!("{}…", preview.trim_end()))
+                .on_hover_text(&description.text);
+        }
+        Some(description) => {
+            ui.label(&description.text);
+        }
+        None => {
+            ui.weak("-");
+        }
+    }
+
+    assembly_cell(ui, slot, machine.capabilities.spindle_max_rpm);
+    compatibility_cell(ui, machine, slot);
+}
+
+fn assembly_cell(ui: &mut Ui, slot: &Slot, spindle_max_rpm: Option<u32>) {
+    let Some(assembly) = slot_assembly(slot, spindle_max_rpm) else {
+        ui.weak("-");
+        return;
+    };
+    let text = match (assembly.max_rpm, assembly.gauge_length) {
+        (Some((rpm, _)), Some(length)) => format!("{} RPM, {:.1} mm", rpm, length),
+        (Some((rpm, _)), None) => format!("{} RPM", rpm),
+        (None, Some(length)) => format!("{:.1} mm", length),
+        (None, None) => assembly.kind.to_string(),
+    };
+    ui.label(text).on_hover_ui(|ui| {
+        Grid::new("assembly_info")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Type:");
+                ui.label(assembly.kind.to_string());
+                ui.end_row();
+                ui.label("Max RPM:");
+                match assembly.max_rpm {
+                    Some((rpm, limit)) => ui.label(format!("{} (set by the {})", rpm, limit)),
+                    None => ui.label("Unknown"),
+                };
+                ui.end_row();
+                ui.label("Gauge Length:");
+                match assembly.gauge_length {
+                    Some(length) => ui.label(format!("{:.2} mm", length)),
+                    None => ui.label("-"),
+                };
+                ui.end_row();
+                ui.label("Total Weight:");
+                ui.label(format!("{:.0} g", assembly.total_weight));
+                ui.end_row();
+            });
+    });
+}
+
+fn compatibility_cell(ui: &mut Ui, machine: &Machine, slot: &Slot) {
+    if slot.tool.is_none() && slot.holder.is_none() {
+        ui.weak("-");
+        return;
+    }
+    let issues = check_in_machine(machine, slot, None, None);
+    if issues.is_empty() {
+        ui.label("OK");
+        return;
     }
+    let (color, text) = if has_errors(&issues) {
+        (ui.visuals().error_fg_color, "Incompatible")
+    } else {
+        (ui.visuals().warn_fg_color, "Warning")
+    };
+    ui.colored_label(color, format!("⚠ {}", text))
+        .on_hover_ui(|ui| compatibility_issues(ui, &issues));
 }
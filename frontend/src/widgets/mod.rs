@@ -1,11 +1,22 @@
+pub mod add_holder;
 pub mod add_machine;
+pub mod add_tool;
+pub mod audit_log;
+pub mod backup_window;
 pub mod delete_machine;
 pub mod edit_machine;
+pub mod editor_fields;
+pub mod filter_window;
 pub mod gripper_fixed_widget;
 pub mod gripper_widget;
+pub mod holder_inventory;
 pub mod magazine_widget;
 pub mod settings_window;
 pub mod sign_in;
 pub mod sign_up;
+pub mod spreadsheet_window;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync_window;
+pub mod tool_library;
 pub mod unit_conversion;
 pub mod welcome;
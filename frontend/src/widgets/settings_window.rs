@@ -31,15 +31,34 @@ impl<'a> SettingsWindow<'a> {
                 egui::Grid::new("settings_grid")
                     .num_columns(2)
                     .show(ui, |ui| {
+                        let mut changed = false;
                         ui.label("Color 1:");
-                        ui.color_edit_button_srgba(&mut self.settings.color1);
+                        changed |= ui
+                            .color_edit_button_srgba(&mut self.settings.color1)
+                            .changed();
                         ui.end_row();
                         ui.label("Color 2:");
-                        ui.color_edit_button_srgba(&mut self.settings.color2);
+                        changed |= ui
+                            .color_edit_button_srgba(&mut self.settings.color2)
+                            .changed();
                         ui.end_row();
                         ui.label("Color 3:");
-                        ui.color_edit_button_srgba(&mut self.settings.color3);
+                        changed |= ui
+                            .color_edit_button_srgba(&mut self.settings.color3)
+                            .changed();
                         ui.end_row();
+                        ui.label("Block incompatible tools and holders:");
+                        changed |= ui
+                            .checkbox(&mut self.settings.block_incompatible_assemblies, "")
+                            .on_hover_text(
+                                "Refuse to put a tool and a holder together \
+                                 when the compatibility check finds an error",
+                            )
+                            .changed();
+                        ui.end_row();
+                        if changed {
+                            self.singletons.should_save_user_data = true;
+                        }
                     });
 
                 if ui.button("Cancel").clicked() {
@@ -0,0 +1,341 @@
+use crate::{
+    app_states::WidgetState, file_io, singletons::Singletons,
+    widgets::holder_inventory::shortage_warning,
+};
+use egui::{ComboBox, Context, DragValue, Grid, ScrollArea, TextEdit, Ui, Window};
+use shared::{
+    slot::Slot,
+    spreadsheet::{self, Column, ImportPlan, Sheet},
+    User,
+};
+
+/// The sheet being imported and what it would do, kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct SpreadsheetState {
+    /// `None` exports the whole machine
+    pub export_magazine: Option<usize>,
+    /// CSV text to import, pasted or read from a dropped file
+    pub text: String,
+    pub sheet: Option<Sheet>,
+    /// What each column of `sheet` is imported as
+    pub mapping: Vec<Column>,
+    /// Magazine the rows go into when no column is mapped to the magazine
+    pub default_magazine: usize,
+    /// Dry run of the import, applied as it is
+    pub plan: Option<ImportPlan>,
+    pub error: Option<String>,
+}
+
+pub struct SpreadsheetWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    machine_index: usize,
+}
+
+impl<'a> SpreadsheetWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        machine_index: usize,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            machine_index,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+        if *open {
+            self.read_dropped_file(ctx);
+        }
+
+        Window::new("Import / Export CSV")
+            .open(open)
+            .show(ctx, |ui| {
+                self.export_ui(ui);
+                ui.separator();
+                self.import_ui(ui);
+                if let Some(error) = &self.singletons.spreadsheet.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                ui.separator();
+                if ui.button("Close").clicked() {
+                    self.singletons.spreadsheet = SpreadsheetState::default();
+                    *self.widget_state = WidgetState::Default;
+                    should_close = true;
+                }
+            });
+        if should_close {
+            *open = false;
+        }
+    }
+
+    /// Takes the text of a file dropped onto the application as the sheet to import.
+    fn read_dropped_file(&mut self, ctx: &Context) {
+        let state = &mut self.singletons.spreadsheet;
+        match file_io::dropped_file(ctx) {
+            Some(Ok(text)) => {
+                state.text = text;
+                state.error = None;
+                self.read_sheet();
+            }
+            Some(Err(e)) => state.error = Some(e),
+            None => {}
+        }
+    }
+
+    fn export_ui(&mut self, ui: &mut Ui) {
+        let machine = &self.user.user_data.machines[self.machine_index];
+        let state = &mut self.singletons.spreadsheet;
+        ui.heading("Export");
+        ui.horizontal(|ui| {
+            let selected = match state.export_magazine {
+                None => "Whole machine".to_string(),
+                Some(index) => machine.magazines[index].name.clone(),
+            };
+            ComboBox::from_id_source("spreadsheet_export")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut state.export_magazine, None, "Whole machine");
+                    for (index, magazine) in machine.magazines.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut state.export_magazine,
+                            Some(index),
+                            &magazine.name,
+                        );
+                    }
+                });
+            if ui.button("Copy CSV").clicked() {
+                let csv = match state.export_magazine {
+                    None => spreadsheet::export_machine(machine),
+                    Some(index) => spreadsheet::export_magazine(machine, index),
+                };
+                match csv {
+                    Ok(csv) => {
+                        ui.output_mut(|o| o.copied_text = csv);
+                        state.error = None;
+                    }
+                    Err(e) => state.error = Some(e.to_string()),
+                }
+            }
+        });
+    }
+
+    fn import_ui(&mut self, ui: &mut Ui) {
+        ui.heading("Import");
+        ui.label("Paste the sheet below or drop a CSV file onto the window.");
+        let text = TextEdit::multiline(&mut self.singletons.spreadsheet.text)
+            .code_editor()
+            .desired_rows(6)
+            .desired_width(f32::INFINITY);
+        if ScrollArea::vertical()
+            .id_source("spreadsheet_text")
+            .max_height(150.0)
+            .show(ui, |ui| ui.add(text))
+            .inner
+            .changed()
+        {
+            let state = &mut self.singletons.spreadsheet;
+            state.sheet = None;
+            state.plan = None;
+        }
+        if ui.button("Read Columns").clicked() {
+            self.read_sheet();
+        }
+
+        if self.singletons.spreadsheet.sheet.is_some() {
+            ui.separator();
+            self.mapping_ui(ui);
+        }
+        if self.singletons.spreadsheet.plan.is_some() {
+            ui.separator();
+            self.plan_ui(ui);
+        }
+    }
+
+    fn read_sheet(&mut self) {
+        let state = &mut self.singletons.spreadsheet;
+        state.plan = None;
+        match spreadsheet::read(&state.text) {
+            Ok(sheet) => {
+                state.mapping = sheet.guess_mapping();
+                state.sheet = Some(sheet);
+                state.error = None;
+            }
+            Err(e) => {
+                state.sheet = None;
+                state.error = Some(e.to_string());
+            }
+        }
+    }
+
+    fn mapping_ui(&mut self, ui: &mut Ui) {
+        let machine = &self.user.user_data.machines[self.machine_index];
+        let state = &mut self.singletons.spreadsheet;
+        let Some(sheet) = &state.sheet else {
+            return;
+        };
+        ui.label(format!(
+            "{} rows. Import the columns as:",
+            sheet.row_count()
+        ));
+        let mut changed = false;
+        ScrollArea::vertical()
+            .id_source("spreadsheet_mapping")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                Grid::new("spreadsheet_mapping")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (index, header) in sheet.headers.iter().enumerate() {
+                            ui.label(header);
+                            let column = &mut state.mapping[index];
+                            ComboBox::from_id_source(("spreadsheet_column", index))
+                                .selected_text(column.to_string())
+                                .show_ui(ui, |ui| {
+                                    for choice in
+                                        std::iter::once(Column::Ignore).chain(Column::all())
+                                    {
+                                        let label = choice.to_string();
+                                        changed |=
+                                            ui.selectable_value(column, choice, label).changed();
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                    });
+            });
+        if !state.mapping.contains(&Column::Magazine) {
+            ui.horizontal(|ui| {
+                ui.label("Into magazine:");
+                let mut number = state.default_magazine + 1;
+                if ui
+                    .add(DragValue::new(&mut number).range(1..=machine.magazines.len()))
+                    .changed()
+                {
+                    state.default_magazine = number - 1;
+                    changed = true;
+                }
+            });
+        }
+        if changed {
+            state.plan = None;
+        }
+
+        if ui.button("Check").clicked() {
+            let user_data = &self.user.user_data;
+            match spreadsheet::plan_import(
+                sheet,
+                &state.mapping,
+                machine,
+                &user_data.tool_library,
+                &user_data.holder_inventory,
+                state.default_magazine,
+            ) {
+                Ok(plan) => {
+                    state.plan = Some(plan);
+                    state.error = None;
+                }
+                Err(e) => state.error = Some(e.to_string()),
+            }
+        }
+    }
+
+    fn plan_ui(&mut self, ui: &mut Ui) {
+        let Some(plan) = &self.singletons.spreadsheet.plan else {
+            return;
+        };
+        ui.label(format!(
+            "{} slots change, {} stay as they are, {} rows have errors.",
+            plan.changes.len(),
+            plan.unchanged,
+            plan.errors.len()
+        ));
+        ScrollArea::vertical()
+            .id_source("spreadsheet_plan")
+            .max_height(250.0)
+            .show(ui, |ui| {
+                if !plan.changes.is_empty() {
+                    Grid::new("spreadsheet_changes")
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Line");
+                            ui.strong("Slot");
+                            ui.strong("Before");
+                            ui.strong("After");
+                            ui.end_row();
+                            for change in &plan.changes {
+                                ui.label(change.line.to_string());
+                                ui.label(format!(
+                                    "Magazine {}, slot {}",
+                                    change.address.magazine + 1,
+                                    change.address.slot + 1
+                                ));
+                                ui.label(slot_label(&change.before));
+                                ui.label(slot_label(&change.after));
+                                ui.end_row();
+                            }
+                        });
+                }
+                for error in &plan.errors {
+                    ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                }
+            });
+
+        let can_apply = !plan.changes.is_empty();
+        if ui
+            .add_enabled(can_apply, egui::Button::new("Apply"))
+            .on_disabled_hover_text("Nothing to change")
+            .clicked()
+        {
+            self.apply();
+        }
+    }
+
+    fn apply(&mut self) {
+        let state = &mut self.singletons.spreadsheet;
+        let Some(plan) = state.plan.take() else {
+            return;
+        };
+        let user_data = &mut self.user.user_data;
+        match spreadsheet::apply_import(
+            &plan,
+            &mut user_data.machines[self.machine_index],
+            &mut user_data.tool_library,
+            &mut user_data.holder_inventory,
+            &self.user.username,
+        ) {
+            Ok(shortages) => {
+                state.error = shortage_warning(&shortages);
+                self.singletons.should_save_user_data = true;
+            }
+            Err(e) => state.error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Tool number, tool and holder of a slot in a few words.
+fn slot_label(slot: &Slot) -> String {
+    if slot.is_empty() {
+        return "empty".to_string();
+    }
+    let parts: Vec<String> = [
+        slot.tool_number.clone(),
+        slot.tool.as_ref().map(|tool| tool.summary()),
+        slot.holder.as_ref().map(|holder| holder.summary()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if parts.is_empty() {
+        return "no tool".to_string();
+    }
+    parts.join(", ")
+}
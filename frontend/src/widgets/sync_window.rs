@@ -0,0 +1,264 @@
+use crate::{
+    app_states::WidgetState, database_interactions::API_URL, singletons::Singletons, sync,
+};
+use egui::{Context, Grid, ScrollArea, TextEdit, Ui, Window};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use shared::{
+    slot::Slot,
+    sync::{Conflict, Resolution, SyncBase, SyncPlan},
+    User,
+};
+
+/// Which server account the local account syncs with.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncSettings {
+    pub server_url: String,
+    pub server_username: String,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            server_url: API_URL.to_string(),
+            server_username: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncState {
+    pub password: String,
+    /// Session on the server, syncs run while there is one
+    pub token: Option<String>,
+    /// What the last sync agreed on, loaded when a sync starts
+    pub base: Option<SyncBase>,
+    /// Merge waiting for the user to resolve its conflicts
+    pub plan: Option<SyncPlan>,
+    /// Merge whose changes are on their way to the server
+    pub pushing: Option<SyncPlan>,
+    pub in_flight: bool,
+    /// Input time the last sync started at
+    pub last_sync_at: Option<f64>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+pub struct SyncWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    client: Client,
+}
+
+impl<'a> SyncWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        client: Client,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            client,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+        if *open && self.singletons.sync.base.is_none() {
+            let settings = &self.singletons.sync_settings;
+            match sync::load_base(&self.user.username, &settings.server_username) {
+                Ok(base) => self.singletons.sync.base = Some(base),
+                Err(e) => self.singletons.sync.error = Some(e),
+            }
+        }
+
+        Window::new("Sync").open(open).show(ctx, |ui| {
+            self.server_ui(ctx, ui);
+            ui.separator();
+            self.status_ui(ctx, ui);
+            if self.singletons.sync.plan.is_some() {
+                ui.separator();
+                self.conflicts_ui(ctx, ui);
+            }
+
+            ui.separator();
+            if ui.button("Close").clicked() {
+                *self.widget_state = WidgetState::Default;
+                should_close = true;
+            }
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+
+    fn server_ui(&mut self, ctx: &Context, ui: &mut Ui) {
+        let settings = &mut self.singletons.sync_settings;
+        let state = &mut self.singletons.sync;
+        let signed_in = state.token.is_some();
+        Grid::new("sync_server").num_columns(2).show(ui, |ui| {
+            ui.label("Server:");
+            ui.add_enabled(
+                !signed_in,
+                TextEdit::singleline(&mut settings.server_url).hint_text(API_URL),
+            );
+            ui.end_row();
+            ui.label("Username:");
+            let username = ui.add_enabled(
+                !signed_in,
+                TextEdit::singleline(&mut settings.server_username),
+            );
+            // Another account has another base
+            if username.changed() {
+                state.base = None;
+            }
+            ui.end_row();
+            if !signed_in {
+                ui.label("Password:");
+                ui.add(
+                    TextEdit::singleline(&mut state.password)
+                        .password(!self.user.user_data.settings.show_password),
+                );
+                ui.end_row();
+            }
+        });
+        if signed_in {
+            if ui.button("Sign Out of Server").clicked() {
+                state.token = None;
+                state.message = None;
+            }
+        } else if ui.button("Sign In to Server").clicked() {
+            let password = std::mem::take(&mut state.password);
+            sync::start_sign_in(ctx, &self.client, settings, password);
+        }
+    }
+
+    fn status_ui(&mut self, ctx: &Context, ui: &mut Ui) {
+        let state = &mut self.singletons.sync;
+        if let Some(base) = &state.base {
+            let pending = shared::sync::pending_changes(base, &self.user.user_data.machines);
+            ui.label(match pending {
+                0 => "No local changes waiting".to_string(),
+                1 => "1 local change waiting".to_string(),
+                pending => format!("{} local changes waiting", pending),
+            });
+        }
+        ui.horizontal(|ui| {
+            let can_sync = state.token.is_some() && !state.in_flight && state.plan.is_none();
+            if ui
+                .add_enabled(can_sync, egui::Button::new("Sync Now"))
+                .clicked()
+            {
+                sync::start_sync(ctx, &self.client, &self.singletons.sync_settings, state);
+            }
+            if state.in_flight {
+                ui.spinner();
+            }
+        });
+        if let Some(message) = &state.message {
+            ui.label(message);
+        }
+        if let Some(error) = &state.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn conflicts_ui(&mut self, ctx: &Context, ui: &mut Ui) {
+        let Some(plan) = &mut self.singletons.sync.plan else {
+            return;
+        };
+        ui.heading("Conflicts");
+        ui.label("Changed here and on the server since the last sync:");
+        let mut resolved = None;
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            Grid::new("sync_conflicts")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (index, conflict) in plan.conflicts.iter().enumerate() {
+                        ui.label(conflict.to_string())
+                            .on_hover_text(describe(conflict));
+                        if ui.button("Keep Local").clicked() {
+                            resolved = Some((index, Resolution::KeepLocal));
+                        }
+                        if ui.button("Keep Server").clicked() {
+                            resolved = Some((index, Resolution::KeepRemote));
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some((index, resolution)) = resolved {
+            plan.resolve(index, resolution);
+        }
+
+        let can_apply = plan.conflicts.is_empty();
+        let (mut apply, mut cancel) = (false, false);
+        ui.horizontal(|ui| {
+            apply = ui
+                .add_enabled(can_apply, egui::Button::new("Apply"))
+                .clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+        if cancel {
+            self.singletons.sync.plan = None;
+        } else if apply {
+            let (Some(plan), Some(base)) =
+                (self.singletons.sync.plan.take(), &self.singletons.sync.base)
+            else {
+                return;
+            };
+            // The machines may have been edited while the conflicts waited
+            let plan = plan.replan(base, &self.user.user_data.machines);
+            if !plan.conflicts.is_empty() {
+                self.singletons.sync.message =
+                    Some("Changed here meanwhile, resolve the new conflicts".to_string());
+                self.singletons.sync.plan = Some(plan);
+                return;
+            }
+            let settings = &self.singletons.sync_settings;
+            if sync::apply(
+                ctx,
+                &self.client,
+                self.user,
+                settings,
+                &mut self.singletons.sync,
+                plan,
+            ) {
+                self.singletons.transfer_error = None;
+                self.singletons.should_save_user_data = true;
+            }
+        }
+    }
+}
+
+/// Both versions of what conflicts, for the tooltip.
+fn describe(conflict: &Conflict) -> String {
+    let slot = |slot: &Slot| {
+        if slot.is_empty() {
+            "empty".to_string()
+        } else {
+            slot.tool_number
+                .clone()
+                .unwrap_or_else(|| "no tool number".to_string())
+        }
+    };
+    match conflict {
+        Conflict::Machine { local, remote } => format!(
+            "Here: {}, {} magazines\nServer: {}, {} magazines",
+            local.name,
+            local.magazines.len(),
+            remote.machine.name,
+            remote.machine.magazines.len()
+        ),
+        Conflict::Slot { local, remote, .. } => {
+            format!("Here: {}\nServer: {}", slot(local), slot(remote))
+        }
+        Conflict::DeletedLocally { .. } => "Keep Local deletes it on the server".to_string(),
+        Conflict::DeletedRemotely { .. } => "Keep Local creates it again".to_string(),
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{app_states::WidgetState, singletons::Singletons, widgets::editor_fields::*};
+use egui::{Context, Grid, Ui, Window};
+use shared::{
+    custom_traits::GetUuid,
+    library::{uses, RemovePolicy},
+    tools::tool::Tool,
+    User,
+};
+
+pub struct ToolLibraryWindow<'a> {
+    user: &'a mut User,
+    singletons: &'a mut Singletons,
+    widget_state: &'a mut WidgetState,
+    /// Machine that tools are loaded into
+    machine_index: Option<usize>,
+}
+
+impl<'a> ToolLibraryWindow<'a> {
+    pub fn new(
+        user: &'a mut User,
+        singletons: &'a mut Singletons,
+        widget_state: &'a mut WidgetState,
+        machine_index: Option<usize>,
+    ) -> Self {
+        Self {
+            user,
+            singletons,
+            widget_state,
+            machine_index,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+        let mut should_close = false;
+
+        Window::new("Tool Library").open(open).show(ctx, |ui| {
+            if self.user.user_data.tool_library.tools().is_empty() {
+                ui.label("The library is empty. Tools added with Add Tool show up here.");
+            } else {
+                self.load_target_ui(ui);
+                ui.separator();
+                self.tools_ui(ui);
+            }
+
+            if self.singletons.library_edit.is_some() {
+                ui.separator();
+                self.edit_ui(ui);
+            }
+            if self.singletons.library_delete.is_some() {
+                ui.separator();
+                self.delete_ui(ui);
+            }
+            if let Some(error) = &self.singletons.editor_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            ui.separator();
+            if ui.button("Close").clicked() {
+                self.singletons.library_edit = None;
+                self.singletons.library_delete = None;
+                self.singletons.editor_error = None;
+                *self.widget_state = WidgetState::Default;
+                should_close = true;
+            }
+        });
+        if should_close {
+            *open = false;
+        }
+    }
+
+    fn load_target_ui(&mut self, ui: &mut Ui) {
+        let Some(machine) = self
+            .machine_index
+            .and_then(|index| self.user.user_data.machines.get(index))
+        else {
+            ui.weak("Select a machine to load tools into it");
+            return;
+        };
+        ui.label(format!("Load into {}", machine.name));
+        ui.horizontal(|ui| {
+            ui.label("Tool Number:");
+            ui.text_edit_singleline(&mut self.singletons.tool_number);
+        });
+        slot_picker(
+            ui,
+            "tool_library",
+            machine,
+            &mut self.singletons.slot_target,
+        );
+    }
+
+    fn tools_ui(&mut self, ui: &mut Ui) {
+        let can_load = self
+            .machine_index
+            .and_then(|index| self.user.user_data.machines.get(index))
+            .is_some_and(|machine| !machine.magazines.is_empty());
+        let mut load = None;
+
+        Grid::new("tool_library_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Tool");
+                ui.strong("Loaded");
+                ui.end_row();
+                for tool in self.user.user_data.tool_library.tools() {
+                    let uuid = tool.get_uuid();
+                    ui.label(tool.summary()).on_hover_ui(|ui| tool.hover_ui(ui));
+                    let loaded = uses(&self.user.user_data.machines, &uuid).len();
+                    ui.label(match loaded {
+                        0 => "No".to_string(),
+                        1 => "1 slot".to_string(),
+                        n => format!("{} slots", n),
+                    });
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(can_load, egui::Button::new("Load"))
+                            .clicked()
+                        {
+                            load = Some(uuid.clone());
+                        }
+                        if ui.button("Edit").clicked() {
+                            self.singletons.library_edit = Some(tool.clone());
+                            self.singletons.editor_error = None;
+                        }
+                        if ui.button("Delete").clicked() {
+                            self.singletons.library_delete = Some(uuid.clone());
+                            self.singletons.editor_error = None;
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        let (Some(uuid), Some(machine_index)) = (load, self.machine_index) else {
+            return;
+        };
+        let issues = target_issues(
+            &self.user.user_data.machines[machine_index],
+            self.singletons.slot_target,
+            self.user.user_data.tool_library.get(&uuid),
+            None,
+        );
+        if is_blocked(
+            self.user.user_data.settings.block_incompatible_assemblies,
+            &issues,
+        ) {
+            self.singletons.editor_error = Some(format!("Not loaded: {}", issues[0]));
+            return;
+        }
+        let tool_number = self.singletons.tool_number.trim();
+        let tool_number = (!tool_number.is_empty()).then(|| tool_number.to_string());
+        let user_data = &mut self.user.user_data;
+        let result = user_data.tool_library.load(
+            &mut user_data.machines[machine_index],
+            self.singletons.slot_target,
+            &uuid,
+            tool_number,
+            &self.user.username,
+        );
+        match result {
+            Ok(_) => {
+                self.singletons.tool_number.clear();
+                self.singletons.editor_error = None;
+                self.singletons.should_save_user_data = true;
+            }
+            Err(e) => self.singletons.editor_error = Some(e.to_string()),
+        }
+    }
+
+    fn edit_ui(&mut self, ui: &mut Ui) {
+        let Some(tool) = &mut self.singletons.library_edit else {
+            return;
+        };
+        ui.heading("Edit Tool");
+        Grid::new("tool_library_edit_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| match tool {
+                Tool::Rotating(tool) => rotating_tool_rows(ui, tool),
+                Tool::Turning(tool) => turning_tool_rows(ui, tool),
+            });
+        let validation = match tool {
+            Tool::Rotating(tool) => tool.validate(),
+            Tool::Turning(tool) => tool.validate(),
+        };
+        if let Err(errors) = &validation {
+            validation_errors(ui, errors);
+        }
+
+        let loaded = uses(&self.user.user_data.machines, &tool.get_uuid()).len();
+        if loaded > 0 {
+            ui.label(format!("Saving also updates the {} loaded copies", loaded));
+        }
+        let mut save = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            save = ui
+                .add_enabled(validation.is_ok(), egui::Button::new("Save"))
+                .clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+
+        if save {
+            let tool = tool.clone();
+            let user_data = &mut self.user.user_data;
+            match user_data
+                .tool_library
+                .update(&mut user_data.machines, tool, &self.user.username)
+            {
+                Ok(_) => {
+                    self.singletons.library_edit = None;
+                    self.singletons.editor_error = None;
+                    self.singletons.should_save_user_data = true;
+                }
+                Err(e) => self.singletons.editor_error = Some(e.to_string()),
+            }
+        } else if cancel {
+            self.singletons.library_edit = None;
+        }
+    }
+
+    fn delete_ui(&mut self, ui: &mut Ui) {
+        let Some(uuid) = self.singletons.library_delete.clone() else {
+            return;
+        };
+        let Some(tool) = self.user.user_data.tool_library.get(&uuid) else {
+            self.singletons.library_delete = None;
+            return;
+        };
+        let loaded = uses(&self.user.user_data.machines, &uuid);
+        if loaded.is_empty() {
+            ui.label(format!("Delete {} from the library?", tool.summary()));
+        } else {
+            ui.label(format!(
+                "{} is loaded in these slots. Deleting it unloads it from all of them:",
+                tool.summary()
+            ));
+            for at in &loaded {
+                let machine = &self.user.user_data.machines[at.machine];
+                ui.label(format!("{}, {}", machine.name, at.address));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let button = if loaded.is_empty() {
+                "Delete"
+            } else {
+                "Unload and Delete"
+            };
+            if ui.button(button).clicked() {
+                let user_data = &mut self.user.user_data;
+                let result = user_data.tool_library.remove(
+                    &mut user_data.machines,
+                    &uuid,
+                    RemovePolicy::Unload,
+                    &self.user.username,
+                );
+                match result {
+                    Ok(_) => {
+                        self.singletons.library_delete = None;
+                        self.singletons.editor_error = None;
+                        self.singletons.should_save_user_data = true;
+                    }
+                    Err(e) => self.singletons.editor_error = Some(e.to_string()),
+                }
+            }
+            if ui.button("Cancel").clicked() {
+                self.singletons.library_delete = None;
+            }
+        });
+    }
+}
@@ -1,7 +1,7 @@
 use std::f32::consts::PI;
 
 use crate::{app_states::WidgetState, singletons::Singletons};
-use egui::{Context, Separator, Window};
+use egui::{Context, Window};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -945,7 +945,7 @@ pub fn m2_to_micrometer2(m2: f32) -> f32 {
     m2 * 1_000_000_000_000.0
 }
 pub fn m2_to_inch2(m2: f32) -> f32 {
-    m2 * 1550.0031
+    m2 * 1_550.003
 }
 pub fn m2_to_ft2(m2: f32) -> f32 {
     m2 * 10.76391
@@ -971,7 +971,7 @@ pub fn cm2_to_ft2(cm2: f32) -> f32 {
     cm2 / 929.0304
 }
 pub fn cm2_to_yd2(cm2: f32) -> f32 {
-    cm2 / 8361.2736
+    cm2 / 8_361.273
 }
 
 // Conversions from square millimeters (mm2)
@@ -991,7 +991,7 @@ pub fn mm2_to_ft2(mm2: f32) -> f32 {
     mm2 / 92903.04
 }
 pub fn mm2_to_yd2(mm2: f32) -> f32 {
-    mm2 / 836127.36
+    mm2 / 836_127.4
 }
 
 // Conversions from square micrometers (micrometer2)
@@ -1059,10 +1059,10 @@ pub fn yd2_to_m2(yd2: f32) -> f32 {
     yd2 * 0.836127
 }
 pub fn yd2_to_cm2(yd2: f32) -> f32 {
-    yd2 * 8361.2736
+    yd2 * 8_361.273
 }
 pub fn yd2_to_mm2(yd2: f32) -> f32 {
-    yd2 * 836127.36
+    yd2 * 836_127.4
 }
 pub fn yd2_to_micrometer2(yd2: f32) -> f32 {
     yd2 * 836127360000.0
@@ -1110,10 +1110,10 @@ pub fn cm3_to_inch3(cm3: f32) -> f32 {
     cm3 / 16.387064
 }
 pub fn cm3_to_ft3(cm3: f32) -> f32 {
-    cm3 / 28316.846592
+    cm3 / 28_316.846
 }
 pub fn cm3_to_yd3(cm3: f32) -> f32 {
-    cm3 / 764554.857984
+    cm3 / 764_554.9
 }
 
 // Conversions from cubic millimeters (mm3)
@@ -1178,10 +1178,10 @@ pub fn inch3_to_yd3(inch3: f32) -> f32 {
 
 // Conversions from cubic feet (ft3)
 pub fn ft3_to_m3(ft3: f32) -> f32 {
-    ft3 * 0.028316846592
+    ft3 * 0.028_316_846
 }
 pub fn ft3_to_cm3(ft3: f32) -> f32 {
-    ft3 * 28316.846592
+    ft3 * 28_316.846
 }
 pub fn ft3_to_mm3(ft3: f32) -> f32 {
     ft3 * 28316846.592
@@ -1198,10 +1198,10 @@ pub fn ft3_to_yd3(ft3: f32) -> f32 {
 
 // Conversions from cubic yards (yd3)
 pub fn yd3_to_m3(yd3: f32) -> f32 {
-    yd3 * 0.764554857984
+    yd3 * 0.764_554_86
 }
 pub fn yd3_to_cm3(yd3: f32) -> f32 {
-    yd3 * 764554.857984
+    yd3 * 764_554.9
 }
 pub fn yd3_to_mm3(yd3: f32) -> f32 {
     yd3 * 764554857.984
@@ -1229,10 +1229,10 @@ pub fn mg_to_kg(mg: f32) -> f32 {
     mg / 1000000.0
 }
 pub fn mg_to_dr(mg: f32) -> f32 {
-    mg / 1771.845195
+    mg / 1_771.845_2
 }
 pub fn mg_to_oz(mg: f32) -> f32 {
-    mg / 28349.523125
+    mg / 28_349.523
 }
 pub fn mg_to_lb(mg: f32) -> f32 {
     mg / 453592.37
@@ -1249,10 +1249,10 @@ pub fn g_to_kg(g: f32) -> f32 {
     g / 1000.0
 }
 pub fn g_to_dr(g: f32) -> f32 {
-    g / 1.7718451953125
+    g / 1.771_845_2
 }
 pub fn g_to_oz(g: f32) -> f32 {
-    g / 28.349523125
+    g / 28.349_524
 }
 pub fn g_to_lb(g: f32) -> f32 {
     g / 453.59237
@@ -1269,7 +1269,7 @@ pub fn hg_to_kg(hg: f32) -> f32 {
     hg / 10.0
 }
 pub fn hg_to_dr(hg: f32) -> f32 {
-    hg * 56.43833912
+    hg * 56.438_34
 }
 pub fn hg_to_oz(hg: f32) -> f32 {
     hg * 3.5273962
@@ -1289,27 +1289,27 @@ pub fn kg_to_hg(kg: f32) -> f32 {
     kg * 10.0
 }
 pub fn kg_to_dr(kg: f32) -> f32 {
-    kg * 564.383391
+    kg * 564.383_36
 }
 pub fn kg_to_oz(kg: f32) -> f32 {
-    kg * 35.27396195
+    kg * 35.273_96
 }
 pub fn kg_to_lb(kg: f32) -> f32 {
-    kg * 2.20462262
+    kg * 2.204_622_5
 }
 
 // Conversions from drams (dr)
 pub fn dr_to_mg(dr: f32) -> f32 {
-    dr * 1771.845195
+    dr * 1_771.845_2
 }
 pub fn dr_to_g(dr: f32) -> f32 {
-    dr * 1.7718451953125
+    dr * 1.771_845_2
 }
 pub fn dr_to_hg(dr: f32) -> f32 {
-    dr / 56.43833912
+    dr / 56.438_34
 }
 pub fn dr_to_kg(dr: f32) -> f32 {
-    dr / 564.383391
+    dr / 564.383_36
 }
 pub fn dr_to_oz(dr: f32) -> f32 {
     dr / 16.0
@@ -1320,16 +1320,16 @@ pub fn dr_to_lb(dr: f32) -> f32 {
 
 // Conversions from ounces (oz)
 pub fn oz_to_mg(oz: f32) -> f32 {
-    oz * 28349.523125
+    oz * 28_349.523
 }
 pub fn oz_to_g(oz: f32) -> f32 {
-    oz * 28.349523125
+    oz * 28.349_524
 }
 pub fn oz_to_hg(oz: f32) -> f32 {
     oz / 3.5273962
 }
 pub fn oz_to_kg(oz: f32) -> f32 {
-    oz / 35.27396195
+    oz / 35.273_96
 }
 pub fn oz_to_dr(oz: f32) -> f32 {
     oz * 16.0
@@ -1349,7 +1349,7 @@ pub fn lb_to_hg(lb: f32) -> f32 {
     lb * 4.5359237
 }
 pub fn lb_to_kg(lb: f32) -> f32 {
-    lb / 2.20462262
+    lb / 2.204_622_5
 }
 pub fn lb_to_dr(lb: f32) -> f32 {
     lb * 256.0
@@ -0,0 +1,45 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use frontend::Application;
+use shared::{upgrade::CURRENT_USER_DATA_VERSION, User};
+
+const V0_WITH_MACHINE: &str =
+    include_str!("../../shared/tests/fixtures/user_data_v0_with_machine.json");
+
+/// The app state as eframe stores it, with the user given as JSON.
+fn stored_with(user: &str) -> String {
+    let stored = ron::to_string(&Application::default()).unwrap();
+    let default_user = ron::to_string(&serde_json::to_string(&User::default()).unwrap()).unwrap();
+    assert!(stored.contains(&default_user));
+    stored.replacen(&default_user, &ron::to_string(user).unwrap(), 1)
+}
+
+fn user_json(user_data: &str) -> String {
+    format!(
+        r#"{{"username":"ada","password":"","email":"","created_at":null,"last_login":null,"user_data":{}}}"#,
+        user_data
+    )
+}
+
+#[test]
+fn user_data_of_an_older_version_is_upgraded_on_restore() {
+    let app: Application = ron::from_str(&stored_with(&user_json(V0_WITH_MACHINE))).unwrap();
+
+    assert_eq!(app.user.username, "ada");
+    assert_eq!(app.user.user_data.version, CURRENT_USER_DATA_VERSION);
+    assert_eq!(app.user.user_data.machines.len(), 1);
+}
+
+#[test]
+fn user_data_from_a_newer_version_is_not_restored() {
+    let stored = stored_with(&user_json(r#"{"version": 999}"#));
+
+    let error = ron::from_str::<Application>(&stored).err().unwrap();
+    assert!(
+        error
+            .to_string()
+            .contains("newer than the supported version"),
+        "{}",
+        error
+    );
+}
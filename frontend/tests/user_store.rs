@@ -0,0 +1,73 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use frontend::user_store::{MemoryStore, StoreError, UserStore};
+use shared::{audit::AuditQuery, User};
+
+async fn registered(username: &str, password: &str) -> MemoryStore {
+    let store = MemoryStore::default();
+    let registered = store
+        .register_user(username.to_string(), password.to_string(), String::new())
+        .await
+        .unwrap();
+    assert!(registered);
+    store
+}
+
+#[tokio::test]
+async fn signs_in_with_the_password_it_was_registered_with() {
+    let store = registered("ada", "secret").await;
+
+    let login = store
+        .verify_user("ada".to_string(), "secret".to_string())
+        .await
+        .unwrap()
+        .expect("the right password signs in");
+    assert_eq!(login.user_data, Default::default());
+    assert_eq!(login.session_token, None);
+
+    let wrong = store.verify_user("ada".to_string(), "guess".to_string());
+    assert!(wrong.await.unwrap().is_none());
+    let unknown = store.verify_user("bob".to_string(), "secret".to_string());
+    assert!(unknown.await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn a_username_is_registered_once() {
+    let store = registered("ada", "secret").await;
+
+    let again = store.register_user("ada".to_string(), "other".to_string(), String::new());
+    assert!(!again.await.unwrap());
+    let login = store.verify_user("ada".to_string(), "other".to_string());
+    assert!(login.await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn saved_user_data_is_there_at_the_next_sign_in() {
+    let store = registered("ada", "secret").await;
+    let mut user = User {
+        username: "ada".to_string(),
+        ..User::default()
+    };
+    user.user_data.settings.show_password = true;
+
+    let saved = store.update_user_data(user.clone(), vec![], None);
+    assert!(saved.await.unwrap());
+    let login = store
+        .verify_user("ada".to_string(), "secret".to_string())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(login.user_data, user.user_data);
+    assert_eq!(store.user_data("ada"), Some(user.user_data.clone()));
+
+    // Nothing is saved for accounts that do not exist
+    user.username = "bob".to_string();
+    assert!(!store.update_user_data(user, vec![], None).await.unwrap());
+    assert!(matches!(
+        store
+            .fetch_audit_log(AuditQuery::default(), None)
+            .await
+            .unwrap_err(),
+        StoreError::Unsupported(_)
+    ));
+}
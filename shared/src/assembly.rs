@@ -0,0 +1,133 @@
+//! Limits of a tool and a holder used together, e.g. the contents of a loaded slot.
+//!
+//! Limits left at 0 on a tool or holder are treated as unknown and ignored.
+
+use std::fmt;
+
+use crate::{
+    custom_traits::{GetHolderType, GetToolType},
+    holders::holder::Holder,
+    slot::{Overhang, Slot},
+    tools::tool::Tool,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssemblyKind {
+    Rotating,
+    Turning,
+    /// A rotating part combined with a turning one.
+    Mixed,
+}
+
+impl fmt::Display for AssemblyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblyKind::Rotating => write!(f, "Rotating"),
+            AssemblyKind::Turning => write!(f, "Turning"),
+            AssemblyKind::Mixed => write!(f, "Mixed"),
+        }
+    }
+}
+
+/// The part that sets the effective RPM limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpmLimit {
+    Tool,
+    Holder,
+    Spindle,
+}
+
+impl fmt::Display for RpmLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpmLimit::Tool => write!(f, "tool"),
+            RpmLimit::Holder => write!(f, "holder"),
+            RpmLimit::Spindle => write!(f, "spindle"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assembly {
+    pub kind: AssemblyKind,
+    /// Lowest known limit of tool, holder and spindle, with the part that sets it.
+    pub max_rpm: Option<(u32, RpmLimit)>,
+    /// Gauge line to tool tip in mm. Only known for a rotating tool in a rotating holder.
+    pub gauge_length: Option<f32>,
+    /// Tool and holder together in grams.
+    pub total_weight: f32,
+}
+
+/// Combines a tool and a holder.
+///
+/// The gauge length is the holder length plus how far the tool sticks out of it: the
+/// overhang, or the whole functional length when no overhang is set. An overhang longer
+/// than the functional length is cut to it.
+pub fn assembly(
+    tool: &Tool,
+    holder: &Holder,
+    overhang: Option<Overhang>,
+    spindle_max_rpm: Option<u32>,
+) -> Assembly {
+    let kind = if tool.is_rotating() && holder.is_rotating() {
+        AssemblyKind::Rotating
+    } else if tool.is_turning() && holder.is_turning() {
+        AssemblyKind::Turning
+    } else {
+        AssemblyKind::Mixed
+    };
+
+    let tool_rpm = match tool {
+        Tool::Rotating(tool) => tool.max_rpm,
+        Tool::Turning(tool) => tool.maximum_rpm,
+    };
+    let holder_rpm = match holder {
+        Holder::Rotating(holder) => holder.max_rpm,
+        Holder::Turning(_) => 0,
+    };
+    let max_rpm = [
+        (tool_rpm, RpmLimit::Tool),
+        (holder_rpm, RpmLimit::Holder),
+        (spindle_max_rpm.unwrap_or(0), RpmLimit::Spindle),
+    ]
+    .into_iter()
+    .filter(|(rpm, _)| *rpm > 0)
+    .min_by_key(|(rpm, _)| *rpm);
+
+    let gauge_length = match (tool, holder) {
+        (Tool::Rotating(tool), Holder::Rotating(holder)) => {
+            let stick_out = overhang
+                .map(|overhang| overhang.to_millimeters())
+                .unwrap_or(tool.functional_length)
+                .min(tool.functional_length);
+            Some(holder.length + stick_out)
+        }
+        _ => None,
+    };
+
+    let tool_weight = match tool {
+        Tool::Rotating(tool) => tool.weight_of_tool,
+        Tool::Turning(tool) => tool.tool_weight,
+    };
+    let holder_weight = match holder {
+        Holder::Rotating(holder) => holder.weight,
+        Holder::Turning(holder) => holder.weight,
+    };
+
+    Assembly {
+        kind,
+        max_rpm,
+        gauge_length,
+        total_weight: tool_weight + holder_weight,
+    }
+}
+
+/// The assembly in a slot, `None` unless it holds both a tool and a holder.
+pub fn slot_assembly(slot: &Slot, spindle_max_rpm: Option<u32>) -> Option<Assembly> {
+    Some(assembly(
+        slot.tool.as_ref()?,
+        slot.holder.as_ref()?,
+        slot.overhang,
+        spindle_max_rpm,
+    ))
+}
@@ -0,0 +1,117 @@
+//! The change log the backend keeps of every mutation of machines, magazines and slots.
+//!
+//! Entries are only ever appended. Each one names what changed and holds it as JSON from
+//! before and after the change, so a deleted machine can still be looked at.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{machine::Machine, magazine::Magazine, slot::Slot};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditAction::Created => write!(f, "Created"),
+            AuditAction::Updated => write!(f, "Updated"),
+            AuditAction::Deleted => write!(f, "Deleted"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub changed_at: DateTime<Utc>,
+    pub changed_by: String,
+    pub action: AuditAction,
+    pub machine_id: String,
+    /// Name of the machine when the change was made
+    pub machine_name: String,
+    /// Index of the magazine when the change was made, `None` for machine changes
+    pub magazine: Option<usize>,
+    /// `None` for machine and magazine changes
+    pub slot: Option<usize>,
+    /// The machine, magazine or slot before the change, `None` when it was created
+    pub before: Option<Value>,
+    /// The machine, magazine or slot after the change, `None` when it was deleted
+    pub after: Option<Value>,
+}
+
+/// Which entries to list. Every field that is set has to match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AuditQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub magazine: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<usize>,
+    /// Changes at or after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    /// Changes before this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl AuditEntry {
+    /// Where the change was made, e.g. "DMU 50, magazine 1, slot 12".
+    pub fn location(&self) -> String {
+        let mut location = self.machine_name.clone();
+        if let Some(magazine) = self.magazine {
+            location.push_str(&format!(", magazine {}", magazine + 1));
+        }
+        if let Some(slot) = self.slot {
+            location.push_str(&format!(", slot {}", slot + 1));
+        }
+        location
+    }
+
+    /// What changed in a few words, e.g. "T1 → empty" for a slot that was cleared.
+    pub fn summary(&self) -> String {
+        let label = |value: &Option<Value>| -> Option<String> {
+            let value = value.as_ref()?;
+            Some(match (self.magazine, self.slot) {
+                (_, Some(_)) => slot_label(&parse::<Slot>(value)?),
+                (Some(_), None) => {
+                    let magazine = parse::<Magazine>(value)?;
+                    format!("{} ({} slots)", magazine.name, magazine.capacity)
+                }
+                (None, None) => parse::<Machine>(value)?.name,
+            })
+        };
+        match (label(&self.before), label(&self.after)) {
+            (Some(before), Some(after)) if before == after => before,
+            (Some(before), Some(after)) => format!("{} → {}", before, after),
+            (Some(only), None) | (None, Some(only)) => only,
+            (None, None) => String::new(),
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(value: &Value) -> Option<T> {
+    serde_json::from_value(value.clone()).ok()
+}
+
+fn slot_label(slot: &Slot) -> String {
+    let mut label = match (&slot.tool_number, slot.is_empty()) {
+        (_, true) => "empty".to_string(),
+        (Some(tool_number), false) => tool_number.clone(),
+        (None, false) if slot.tool.is_none() && slot.holder.is_none() => "no tool".to_string(),
+        (None, false) => "unnumbered tool".to_string(),
+    };
+    if slot.locked {
+        label.push_str(" (reserved)");
+    }
+    label
+}
@@ -0,0 +1,351 @@
+//! A whole [`UserData`] as one JSON file, to keep as a backup or to move into another account.
+//!
+//! The file wraps the user data in a small envelope naming the format, so that some other
+//! JSON file is not taken for a backup. The user data inside keeps its own `version` and is
+//! upgraded when read, see [`upgrade`](crate::upgrade), so backups from older builds restore.
+//!
+//! [`read`] checks the whole file before anything is touched, [`restore`] then cannot fail.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    custom_traits::GetUuid,
+    holders::holder::Holder,
+    inventory::Shortage,
+    tools::tool::Tool,
+    upgrade::{upgrade_user_data, UpgradeError, CURRENT_USER_DATA_VERSION},
+    UserData,
+};
+
+/// Written into every backup and checked when one is read.
+pub const BACKUP_FORMAT: &str = "tool-magazine-backup";
+
+/// Shape of the envelope around the user data.
+pub const BACKUP_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    backup_version: u32,
+    created_at: DateTime<Utc>,
+    username: String,
+    /// Upgraded on its own, so it is read as plain JSON first
+    user_data: Value,
+}
+
+/// A backup that was read and checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub created_at: DateTime<Utc>,
+    /// The account the backup was made from
+    pub username: String,
+    pub user_data: UserData,
+}
+
+#[derive(Debug)]
+pub enum BackupError {
+    Json(serde_json::Error),
+    NotABackup,
+    TooNew {
+        found: u32,
+    },
+    UserData(UpgradeError),
+    /// Everything that is wrong with the user data in the file
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::Json(e) => write!(f, "The file is not valid JSON: {}", e),
+            BackupError::NotABackup => write!(f, "The file is not a backup"),
+            BackupError::TooNew { found } => write!(
+                f,
+                "Backup version {} is newer than the supported version {}",
+                found, BACKUP_VERSION
+            ),
+            BackupError::UserData(e) => write!(f, "{}", e),
+            BackupError::Invalid(problems) => {
+                write!(f, "The backup is damaged: {}", problems.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for BackupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BackupError::Json(e) => Some(e),
+            BackupError::UserData(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for BackupError {
+    fn from(e: serde_json::Error) -> Self {
+        BackupError::Json(e)
+    }
+}
+
+impl From<UpgradeError> for BackupError {
+    fn from(e: UpgradeError) -> Self {
+        BackupError::UserData(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RestoreMode {
+    /// Adds the machines, tools, holders and filters of the backup to the current ones
+    #[default]
+    Merge,
+    /// Makes the account exactly what the backup holds
+    Replace,
+}
+
+/// What a restore added.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RestoreReport {
+    pub machines: usize,
+    pub tools: usize,
+    pub holders: usize,
+    /// Machines, tools and holders that were given a new uuid
+    pub remapped: usize,
+    /// Holders the restored slots use more copies of than the inventory owns
+    pub shortages: Vec<Shortage>,
+}
+
+pub fn write(user_data: &UserData, username: &str) -> Result<String, BackupError> {
+    let envelope = Envelope {
+        format: BACKUP_FORMAT.to_string(),
+        backup_version: BACKUP_VERSION,
+        created_at: Utc::now(),
+        username: username.to_string(),
+        user_data: serde_json::to_value(user_data)?,
+    };
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+pub fn read(json: &str) -> Result<Backup, BackupError> {
+    let value: Value = serde_json::from_str(json)?;
+    if value.get("format").and_then(Value::as_str) != Some(BACKUP_FORMAT) {
+        return Err(BackupError::NotABackup);
+    }
+    let envelope: Envelope = serde_json::from_value(value)?;
+    if envelope.backup_version > BACKUP_VERSION {
+        return Err(BackupError::TooNew {
+            found: envelope.backup_version,
+        });
+    }
+
+    let user_data = upgrade_user_data(envelope.user_data)?;
+    let problems = problems(&user_data);
+    if !problems.is_empty() {
+        return Err(BackupError::Invalid(problems));
+    }
+    Ok(Backup {
+        created_at: envelope.created_at,
+        username: envelope.username,
+        user_data,
+    })
+}
+
+/// What would break the application if the user data was used as it is.
+fn problems(user_data: &UserData) -> Vec<String> {
+    let mut problems = vec![];
+    let mut seen = |kind: &str, uuids: Vec<String>| {
+        let mut unique = HashSet::new();
+        for uuid in uuids {
+            if !unique.insert(uuid.clone()) {
+                problems.push(format!("{} {} is in it twice", kind, uuid));
+            }
+        }
+    };
+    seen(
+        "machine",
+        user_data.machines.iter().map(GetUuid::get_uuid).collect(),
+    );
+    seen(
+        "tool",
+        user_data
+            .tool_library
+            .tools()
+            .iter()
+            .map(GetUuid::get_uuid)
+            .collect(),
+    );
+    seen(
+        "holder",
+        user_data
+            .holder_inventory
+            .holders()
+            .iter()
+            .map(GetUuid::get_uuid)
+            .collect(),
+    );
+
+    for machine in &user_data.machines {
+        for magazine in &machine.magazines {
+            if magazine.slots.len() != magazine.capacity {
+                problems.push(format!(
+                    "{} of {} has {} slots but room for {}",
+                    magazine.name,
+                    machine.name,
+                    magazine.slots.len(),
+                    magazine.capacity
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// Restores `backup` into `current`.
+///
+/// Machine uuids are unique within an account, and a backup may be restored next to the
+/// machines it was taken from, so a machine from the backup keeps its uuid only when it
+/// replaces the machine with that uuid. Tools and holders are
+/// only given a new uuid when the current library or inventory has a different entry with
+/// the same one, identical entries are the same tool or holder.
+pub fn restore(current: &mut UserData, mut backup: UserData, mode: RestoreMode) -> RestoreReport {
+    let mut report = RestoreReport::default();
+    // Every loaded tool and holder gets an entry, so remapping entries reaches all of them
+    backup.tool_library.adopt_loaded(&backup.machines);
+    backup.holder_inventory.adopt_loaded(&backup.machines);
+
+    let current_machines: HashSet<String> = current
+        .machines
+        .iter()
+        .map(|machine| machine.uuid.clone())
+        .collect();
+    for machine in &mut backup.machines {
+        if mode == RestoreMode::Merge || !current_machines.contains(&machine.uuid) {
+            machine.uuid = Uuid::new_v4().to_string();
+            report.remapped += 1;
+        }
+    }
+    report.machines = backup.machines.len();
+
+    match mode {
+        RestoreMode::Replace => {
+            report.tools = backup.tool_library.tools().len();
+            report.holders = backup.holder_inventory.holders().len();
+            *current = backup;
+            if current
+                .selections
+                .selected_machine
+                .is_some_and(|index| index >= current.machines.len())
+            {
+                current.selections.selected_machine = None;
+            }
+        }
+        RestoreMode::Merge => merge(current, backup, &mut report),
+    }
+    current.version = CURRENT_USER_DATA_VERSION;
+    current.tool_library.adopt_loaded(&current.machines);
+    report.shortages = current.holder_inventory.adopt_loaded(&current.machines);
+    report
+}
+
+/// Adds what the backup has to `current`, keeping the current selections and settings.
+fn merge(current: &mut UserData, mut backup: UserData, report: &mut RestoreReport) {
+    let mut tool_uuids = HashMap::new();
+    for tool in backup.tool_library.tools() {
+        let mut tool = tool.clone();
+        match current.tool_library.get(&tool.get_uuid()) {
+            Some(entry) if *entry == tool => continue,
+            Some(_) => {
+                let uuid = Uuid::new_v4().to_string();
+                tool_uuids.insert(tool.get_uuid(), uuid.clone());
+                set_tool_uuid(&mut tool, uuid);
+                report.remapped += 1;
+            }
+            None => {}
+        }
+        // The uuid is free, checked or made above
+        let _ = current.tool_library.add(tool);
+        report.tools += 1;
+    }
+
+    let mut holder_uuids = HashMap::new();
+    for holder in backup.holder_inventory.holders() {
+        let mut holder = holder.clone();
+        match current.holder_inventory.get(&holder.get_uuid()) {
+            // The number of copies owned is kept as it is
+            Some(entry) if same_holder(entry, &holder) => continue,
+            Some(_) => {
+                let uuid = Uuid::new_v4().to_string();
+                holder_uuids.insert(holder.get_uuid(), uuid.clone());
+                set_holder_uuid(&mut holder, uuid);
+                report.remapped += 1;
+            }
+            None => {}
+        }
+        let _ = current.holder_inventory.add(holder);
+        report.holders += 1;
+    }
+
+    for slot in backup
+        .machines
+        .iter_mut()
+        .flat_map(|machine| &mut machine.magazines)
+        .flat_map(|magazine| &mut magazine.slots)
+    {
+        if let Some(tool) = &mut slot.tool {
+            if let Some(uuid) = tool_uuids.get(&tool.get_uuid()) {
+                set_tool_uuid(tool, uuid.clone());
+            }
+        }
+        if let Some(holder) = &mut slot.holder {
+            if let Some(uuid) = holder_uuids.get(&holder.get_uuid()) {
+                set_holder_uuid(holder, uuid.clone());
+            }
+        }
+    }
+    current.machines.append(&mut backup.machines);
+
+    for mut saved in backup.saved_filters {
+        if current.saved_filters.contains(&saved) {
+            continue;
+        }
+        if current.saved_filters.iter().any(|s| s.name == saved.name) {
+            saved.name.push_str(" (restored)");
+        }
+        current.saved_filters.push(saved);
+    }
+}
+
+fn set_tool_uuid(tool: &mut Tool, uuid: String) {
+    match tool {
+        Tool::Rotating(tool) => tool.uuid = uuid,
+        Tool::Turning(tool) => tool.uuid = uuid,
+    }
+}
+
+fn set_holder_uuid(holder: &mut Holder, uuid: String) {
+    match holder {
+        Holder::Rotating(holder) => holder.uuid = uuid,
+        Holder::Turning(holder) => holder.uuid = uuid,
+    }
+}
+
+/// Equal apart from the number of copies owned.
+fn same_holder(a: &Holder, b: &Holder) -> bool {
+    let one_copy = |holder: &Holder| {
+        let mut holder = holder.clone();
+        match &mut holder {
+            Holder::Rotating(holder) => holder.duplicates = 1,
+            Holder::Turning(holder) => holder.duplicates = 1,
+        }
+        holder
+    };
+    one_copy(a) == one_copy(b)
+}
@@ -0,0 +1,318 @@
+//! Checks whether a tool and a holder can be used together, and in a machine.
+//!
+//! Values left at 0, and machine capabilities left unset, are treated as unknown and not
+//! checked.
+
+use std::fmt;
+
+use crate::{
+    assembly::assembly,
+    custom_traits::{GetHolderType, GetToolType},
+    holders::holder::{Holder, RotatingHolder, TurningHolder},
+    machine::{Machine, MachineCapabilities, MachineKind},
+    slot::{Overhang, Slot},
+    tools::tool::{Handedness, RotatingTool, Tool, TurningTool},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The assembly works, but not as well as the parts on their own.
+    Warning,
+    /// The assembly cannot be used.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// A rotating tool in a turning holder or the other way around.
+    WrongHolderType,
+    ShankOutsideClampingRange {
+        connection_diameter: f32,
+        clamping_range: (f32, f32),
+    },
+    HolderSlowerThanTool {
+        tool_max_rpm: u32,
+        holder_max_rpm: u32,
+    },
+    HandednessMismatch {
+        tool: Handedness,
+        holder: Handedness,
+    },
+    /// The holder needs a larger bore than the tool could work in.
+    BoreLimitedByHolder {
+        tool_min_bore: f32,
+        holder_min_bore: f32,
+    },
+    /// A turning part on a machine that only mills.
+    NotForMachine {
+        kind: MachineKind,
+    },
+    /// A rotating tool on a lathe, which only works in a driven tool station.
+    NeedsDrivenTool,
+    TaperMismatch {
+        spindle: String,
+        holder: String,
+    },
+    TooLong {
+        gauge_length: f32,
+        max: f32,
+    },
+    TooWide {
+        diameter: f32,
+        max: f32,
+    },
+    TooHeavy {
+        weight: f32,
+        max: f32,
+    },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::WrongHolderType
+            | Issue::ShankOutsideClampingRange { .. }
+            | Issue::HandednessMismatch { .. }
+            | Issue::NotForMachine { .. }
+            | Issue::TaperMismatch { .. }
+            | Issue::TooLong { .. }
+            | Issue::TooWide { .. }
+            | Issue::TooHeavy { .. } => Severity::Error,
+            Issue::HolderSlowerThanTool { .. }
+            | Issue::BoreLimitedByHolder { .. }
+            | Issue::NeedsDrivenTool => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::WrongHolderType => {
+                write!(
+                    f,
+                    "Rotating tools need a rotating holder, turning tools a turning holder"
+                )
+            }
+            Issue::ShankOutsideClampingRange {
+                connection_diameter,
+                clamping_range: (min, max),
+            } => write!(
+                f,
+                "Tool shank Ø{:.2} mm is outside the holder's clamping range of {:.2} to {:.2} mm",
+                connection_diameter, min, max
+            ),
+            Issue::HolderSlowerThanTool {
+                tool_max_rpm,
+                holder_max_rpm,
+            } => write!(
+                f,
+                "Holder is limited to {} RPM, below the tool's {} RPM",
+                holder_max_rpm, tool_max_rpm
+            ),
+            Issue::HandednessMismatch { tool, holder } => {
+                write!(f, "{} handed tool in a {} handed holder", tool, holder)
+            }
+            Issue::BoreLimitedByHolder {
+                tool_min_bore,
+                holder_min_bore,
+            } => write!(
+                f,
+                "Holder needs a bore of at least Ø{:.2} mm, the tool fits Ø{:.2} mm",
+                holder_min_bore, tool_min_bore
+            ),
+            Issue::NotForMachine { kind } => {
+                write!(f, "A {} cannot use turning tools or holders", kind)
+            }
+            Issue::NeedsDrivenTool => {
+                write!(f, "Rotating tools on a lathe need a driven tool station")
+            }
+            Issue::TaperMismatch { spindle, holder } => write!(
+                f,
+                "Holder taper {} does not fit the {} spindle",
+                holder, spindle
+            ),
+            Issue::TooLong { gauge_length, max } => write!(
+                f,
+                "Gauge length of {:.1} mm is above the machine's {:.1} mm",
+                gauge_length, max
+            ),
+            Issue::TooWide { diameter, max } => write!(
+                f,
+                "Ø{:.1} mm is above the machine's Ø{:.1} mm",
+                diameter, max
+            ),
+            Issue::TooHeavy { weight, max } => {
+                write!(f, "{:.0} g is above the machine's {:.0} g", weight, max)
+            }
+        }
+    }
+}
+
+/// Every problem with using `tool` in `holder`, errors first.
+pub fn check(tool: &Tool, holder: &Holder) -> Vec<Issue> {
+    let mut issues = match (tool, holder) {
+        (Tool::Rotating(tool), Holder::Rotating(holder)) => check_rotating(tool, holder),
+        (Tool::Turning(tool), Holder::Turning(holder)) => check_turning(tool, holder),
+        _ => vec![Issue::WrongHolderType],
+    };
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity()));
+    issues
+}
+
+/// Problems of the assembly in `slot` once `tool` and `holder`, where given, replace
+/// what is in it. Empty while the slot lacks a tool or a holder.
+pub fn check_slot(slot: &Slot, tool: Option<&Tool>, holder: Option<&Holder>) -> Vec<Issue> {
+    match (tool.or(slot.tool.as_ref()), holder.or(slot.holder.as_ref())) {
+        (Some(tool), Some(holder)) => check(tool, holder),
+        _ => vec![],
+    }
+}
+
+/// Problems of the assembly in `slot` of `machine` once `tool` and `holder`, where given,
+/// replace what is in it: those of the pair, then those with the machine's limits. Limits
+/// that only need one part are checked while the slot lacks the other.
+pub fn check_in_machine(
+    machine: &Machine,
+    slot: &Slot,
+    tool: Option<&Tool>,
+    holder: Option<&Holder>,
+) -> Vec<Issue> {
+    let tool = tool.or(slot.tool.as_ref());
+    let holder = holder.or(slot.holder.as_ref());
+    let mut issues = match (tool, holder) {
+        (Some(tool), Some(holder)) => check(tool, holder),
+        _ => vec![],
+    };
+    issues.extend(check_capabilities(
+        &machine.capabilities,
+        tool,
+        holder,
+        slot.overhang,
+    ));
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity()));
+    issues
+}
+
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues
+        .iter()
+        .any(|issue| issue.severity() == Severity::Error)
+}
+
+fn check_rotating(tool: &RotatingTool, holder: &RotatingHolder) -> Vec<Issue> {
+    let mut issues = vec![];
+    let (min, max) = holder.tool_clamping_range;
+    if tool.connection_diameter > 0.0
+        && max > 0.0
+        && !(min..=max).contains(&tool.connection_diameter)
+    {
+        issues.push(Issue::ShankOutsideClampingRange {
+            connection_diameter: tool.connection_diameter,
+            clamping_range: holder.tool_clamping_range,
+        });
+    }
+    if holder.max_rpm > 0 && holder.max_rpm < tool.max_rpm {
+        issues.push(Issue::HolderSlowerThanTool {
+            tool_max_rpm: tool.max_rpm,
+            holder_max_rpm: holder.max_rpm,
+        });
+    }
+    issues
+}
+
+fn check_turning(tool: &TurningTool, holder: &TurningHolder) -> Vec<Issue> {
+    let mut issues = vec![];
+    // Neutral parts fit either hand
+    if tool.handedness != holder.handedness
+        && tool.handedness != Handedness::Neutral
+        && holder.handedness != Handedness::Neutral
+    {
+        issues.push(Issue::HandednessMismatch {
+            tool: tool.handedness,
+            holder: holder.handedness,
+        });
+    }
+    if tool.uses_bore_diameter()
+        && holder.uses_bore_limits()
+        && tool.minimum_bore_diameter > 0.0
+        && holder.min_bore_diameter > tool.minimum_bore_diameter
+    {
+        issues.push(Issue::BoreLimitedByHolder {
+            tool_min_bore: tool.minimum_bore_diameter,
+            holder_min_bore: holder.min_bore_diameter,
+        });
+    }
+    issues
+}
+
+fn check_capabilities(
+    capabilities: &MachineCapabilities,
+    tool: Option<&Tool>,
+    holder: Option<&Holder>,
+    overhang: Option<Overhang>,
+) -> Vec<Issue> {
+    let mut issues = vec![];
+    let turning = tool.is_some_and(|tool| tool.is_turning())
+        || holder.is_some_and(|holder| holder.is_turning());
+    match capabilities.kind {
+        Some(MachineKind::Mill) if turning => issues.push(Issue::NotForMachine {
+            kind: MachineKind::Mill,
+        }),
+        Some(MachineKind::Lathe) if tool.is_some_and(|tool| tool.is_rotating()) => {
+            issues.push(Issue::NeedsDrivenTool)
+        }
+        _ => {}
+    }
+
+    if let Some(Holder::Rotating(holder)) = holder {
+        let spindle = capabilities.spindle_taper.trim();
+        let taper = holder.taper_type.trim();
+        if !spindle.is_empty() && !taper.is_empty() && !spindle.eq_ignore_ascii_case(taper) {
+            issues.push(Issue::TaperMismatch {
+                spindle: spindle.to_string(),
+                holder: taper.to_string(),
+            });
+        }
+    }
+
+    if let (Some(max), Some(tool), Some(holder)) = (capabilities.max_tool_length, tool, holder) {
+        let gauge_length = assembly(tool, holder, overhang, None).gauge_length;
+        if let Some(gauge_length) = gauge_length.filter(|length| *length > max) {
+            issues.push(Issue::TooLong { gauge_length, max });
+        }
+    }
+
+    if let Some(max) = capabilities.max_tool_diameter {
+        let tool_diameter = match tool {
+            Some(Tool::Rotating(tool)) => tool.cutting_diameter,
+            _ => 0.0,
+        };
+        let holder_diameter = match holder {
+            Some(Holder::Rotating(holder)) => holder.diameter,
+            _ => 0.0,
+        };
+        let diameter = tool_diameter.max(holder_diameter);
+        if diameter > max {
+            issues.push(Issue::TooWide { diameter, max });
+        }
+    }
+
+    if let Some(max) = capabilities.max_tool_weight {
+        let tool_weight = match tool {
+            Some(Tool::Rotating(tool)) => tool.weight_of_tool,
+            Some(Tool::Turning(tool)) => tool.tool_weight,
+            None => 0.0,
+        };
+        let holder_weight = match holder {
+            Some(Holder::Rotating(holder)) => holder.weight,
+            Some(Holder::Turning(holder)) => holder.weight,
+            None => 0.0,
+        };
+        let weight = tool_weight + holder_weight;
+        if weight > max {
+            issues.push(Issue::TooHeavy { weight, max });
+        }
+    }
+    issues
+}
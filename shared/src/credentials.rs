@@ -0,0 +1,79 @@
+//! Password hashing shared by the backend and the native frontend's local database.
+//!
+//! Passwords are stored as Argon2 PHC strings, e.g. `$argon2id$v=19$...`, which carry their
+//! own salt and parameters. [`verify_password`] only accepts those. Local databases from older
+//! builds hold plain passwords, [`verify_legacy_password`] lets them verify once so the caller
+//! can store a hash in their place.
+
+use std::fmt;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
+#[derive(Debug)]
+pub struct HashError(argon2::password_hash::Error);
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Could not hash the password: {}", self.0)
+    }
+}
+
+impl std::error::Error for HashError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Valid,
+    /// The password matches a stored plain password, which should be replaced by a hash
+    ValidPlaintext,
+    Invalid,
+}
+
+impl Verification {
+    pub fn is_valid(self) -> bool {
+        self != Verification::Invalid
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, HashError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(HashError)
+}
+
+/// Checks `password` against the stored hash. Anything that is not a valid hash never
+/// matches.
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    PasswordHash::new(stored).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Checks `password` against what a local database from an older build may hold, a hash or
+/// a plain password. Only for the native frontend's local database, the backend has always
+/// stored hashes.
+pub fn verify_legacy_password(password: &str, stored: &str) -> Verification {
+    if PasswordHash::new(stored).is_err() && is_plaintext_match(password, stored) {
+        Verification::ValidPlaintext
+    } else if verify_password(password, stored) {
+        Verification::Valid
+    } else {
+        Verification::Invalid
+    }
+}
+
+/// Compares every byte, so the time taken does not tell how much of the password matched.
+fn is_plaintext_match(password: &str, stored: &str) -> bool {
+    password.len() == stored.len()
+        && password
+            .bytes()
+            .zip(stored.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
@@ -25,12 +25,14 @@ pub trait GetTurningHolderCategory {
     fn get_turning_holder_category(&self) -> Option<TurningHolderCategory>;
 }
 
+/// `None` when the item has no meaningful diameter, e.g. a turning tool.
 pub trait GetDiameter {
-    fn get_diameter(&self) -> f32;
+    fn get_diameter(&self) -> Option<f32>;
 }
 
+/// `None` when the item has no meaningful angle, e.g. a rotating tool.
 pub trait GetDegree {
-    fn get_degree(&self) -> f32;
+    fn get_degree(&self) -> Option<f32>;
 }
 
 pub trait GetHolderType {
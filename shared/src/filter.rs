@@ -0,0 +1,142 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_traits::{
+        GetRotatingHolderCategory, GetRotatingToolCategory, GetTurningHolderCategory,
+        GetTurningToolCategory,
+    },
+    holders::holder::{RotatingHolderCategory, TurningHolderCategory},
+    magazine::Magazine,
+    slot::Slot,
+    sorting::SortField,
+    tools::tool::{RotatingToolCategory, TurningToolCategory},
+};
+
+/// A condition on a magazine slot. Conditions combine with `And`, `Or` and `Not`, e.g.
+/// end mills between 6 and 12 mm in a shrink fit holder:
+///
+/// ```
+/// # use shared::filter::Filter;
+/// # use shared::holders::holder::RotatingHolderCategory;
+/// # use shared::sorting::SortField;
+/// # use shared::tools::tool::RotatingToolCategory;
+/// let filter = Filter::And(vec![
+///     Filter::RotatingToolCategory(RotatingToolCategory::EndMill),
+///     Filter::Range { field: SortField::ToolDiameter, min: Some(6.0), max: Some(12.0) },
+///     Filter::RotatingHolderCategory(RotatingHolderCategory::ShrinkFit(Default::default())),
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum Filter {
+    /// Matches every slot.
+    #[default]
+    All,
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    RotatingToolCategory(RotatingToolCategory),
+    TurningToolCategory(TurningToolCategory),
+    /// Matches holders of the same category, whatever their subcategory.
+    RotatingHolderCategory(RotatingHolderCategory),
+    /// Matches holders of the same category, whatever their subcategory.
+    TurningHolderCategory(TurningHolderCategory),
+    /// Inclusive range, an open bound is `None`. Slots without the value never match.
+    Range {
+        field: SortField,
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+}
+
+impl Filter {
+    pub fn matches(&self, index: usize, slot: &Slot) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::And(filters) => filters.iter().all(|filter| filter.matches(index, slot)),
+            Filter::Or(filters) => filters.iter().any(|filter| filter.matches(index, slot)),
+            Filter::Not(filter) => !filter.matches(index, slot),
+            Filter::RotatingToolCategory(category) => slot
+                .tool
+                .as_ref()
+                .and_then(|tool| tool.get_rotating_tool_category())
+                .is_some_and(|c| c == *category),
+            Filter::TurningToolCategory(category) => slot
+                .tool
+                .as_ref()
+                .and_then(|tool| tool.get_turning_tool_category())
+                .is_some_and(|c| c == *category),
+            Filter::RotatingHolderCategory(category) => slot
+                .holder
+                .as_ref()
+                .and_then(|holder| holder.get_rotating_holder_category())
+                .is_some_and(|c| c.same_kind(category)),
+            Filter::TurningHolderCategory(category) => slot
+                .holder
+                .as_ref()
+                .and_then(|holder| holder.get_turning_holder_category())
+                .is_some_and(|c| c.same_kind(category)),
+            Filter::Range { field, min, max } => field.value(index, slot).is_some_and(|value| {
+                min.map_or(true, |min| value >= min) && max.map_or(true, |max| value <= max)
+            }),
+        }
+    }
+
+    /// Indices of the slots in `magazine` that match, in slot order.
+    pub fn matching_indices(&self, magazine: &Magazine) -> Vec<usize> {
+        magazine
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(index, slot)| self.matches(*index, slot))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn join(f: &mut fmt::Formatter, filters: &[Filter], separator: &str) -> fmt::Result {
+            for (i, filter) in filters.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", separator)?;
+                }
+                match filter {
+                    Filter::And(_) | Filter::Or(_) => write!(f, "({})", filter)?,
+                    _ => write!(f, "{}", filter)?,
+                }
+            }
+            Ok(())
+        }
+
+        match self {
+            Filter::All => write!(f, "All"),
+            Filter::And(filters) => join(f, filters, "AND"),
+            Filter::Or(filters) => join(f, filters, "OR"),
+            Filter::Not(filter) => match filter.as_ref() {
+                Filter::And(_) | Filter::Or(_) => write!(f, "NOT ({})", filter),
+                _ => write!(f, "NOT {}", filter),
+            },
+            Filter::RotatingToolCategory(category) => write!(f, "tool is {}", category),
+            Filter::TurningToolCategory(category) => write!(f, "tool is {}", category),
+            Filter::RotatingHolderCategory(category) => {
+                write!(f, "holder is {}", category.name())
+            }
+            Filter::TurningHolderCategory(category) => write!(f, "holder is {}", category.name()),
+            Filter::Range { field, min, max } => match (min, max) {
+                (Some(min), Some(max)) => write!(f, "{} between {} and {}", field, min, max),
+                (Some(min), None) => write!(f, "{} at least {}", field, min),
+                (None, Some(max)) => write!(f, "{} at most {}", field, max),
+                (None, None) => write!(f, "{} set", field),
+            },
+        }
+    }
+}
+
+/// A filter the user stored under a name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedFilter {
+    pub name: String,
+    pub filter: Filter,
+}
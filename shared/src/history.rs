@@ -0,0 +1,431 @@
+//! Undo and redo for edits of [`UserData`].
+//!
+//! An [`Edit`] holds both sides of a change, so it can be reverted and applied again.
+//! Edits are found by comparing the data before and after a change with [`Edit::between`],
+//! which leaves the widgets free to keep changing `UserData` directly. Selections are not
+//! edits and are never undone.
+
+use std::{collections::VecDeque, fmt};
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filter::SavedFilter, inventory::HolderInventory, library::ToolLibrary, machine::Machine,
+    settings::Settings, UserData,
+};
+
+/// Number of edits kept for undo when nothing else is asked for.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum Edit {
+    AddMachine {
+        index: usize,
+        machine: Box<Machine>,
+    },
+    DeleteMachine {
+        index: usize,
+        machine: Box<Machine>,
+    },
+    UpdateMachine {
+        index: usize,
+        before: Box<Machine>,
+        after: Box<Machine>,
+    },
+    /// Several machines were added, deleted or reordered at once.
+    ReplaceMachines {
+        before: Vec<Machine>,
+        after: Vec<Machine>,
+    },
+    UpdateFavoriteColor {
+        before: Color32,
+        after: Color32,
+    },
+    UpdateSettings {
+        before: Settings,
+        after: Settings,
+    },
+    UpdateSavedFilters {
+        before: Vec<SavedFilter>,
+        after: Vec<SavedFilter>,
+    },
+    UpdateToolLibrary {
+        before: ToolLibrary,
+        after: ToolLibrary,
+    },
+    UpdateHolderInventory {
+        before: HolderInventory,
+        after: HolderInventory,
+    },
+    /// Edits made together, undone and redone as one.
+    Batch(Vec<Edit>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryError {
+    NothingToUndo,
+    NothingToRedo,
+    /// The data no longer matches the edit, e.g. because it was loaded again since.
+    NoSuchMachine(usize),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::NothingToUndo => write!(f, "There is nothing to undo"),
+            HistoryError::NothingToRedo => write!(f, "There is nothing to redo"),
+            HistoryError::NoSuchMachine(index) => {
+                write!(f, "Machine {} no longer exists", index + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+impl Edit {
+    /// The edit that turns `before` into `after`, or `None` when they only differ in
+    /// selections.
+    pub fn between(before: &UserData, after: &UserData) -> Option<Edit> {
+        let mut edits = machine_edits(&before.machines, &after.machines);
+        if before.favorite_color != after.favorite_color {
+            edits.push(Edit::UpdateFavoriteColor {
+                before: before.favorite_color,
+                after: after.favorite_color,
+            });
+        }
+        if before.settings != after.settings {
+            edits.push(Edit::UpdateSettings {
+                before: before.settings.clone(),
+                after: after.settings.clone(),
+            });
+        }
+        if before.saved_filters != after.saved_filters {
+            edits.push(Edit::UpdateSavedFilters {
+                before: before.saved_filters.clone(),
+                after: after.saved_filters.clone(),
+            });
+        }
+        if before.tool_library != after.tool_library {
+            edits.push(Edit::UpdateToolLibrary {
+                before: before.tool_library.clone(),
+                after: after.tool_library.clone(),
+            });
+        }
+        if before.holder_inventory != after.holder_inventory {
+            edits.push(Edit::UpdateHolderInventory {
+                before: before.holder_inventory.clone(),
+                after: after.holder_inventory.clone(),
+            });
+        }
+        match edits.len() {
+            0 => None,
+            1 => edits.pop(),
+            _ => Some(Edit::Batch(edits)),
+        }
+    }
+
+    /// Makes the change again. Nothing changes when it fails.
+    pub fn apply(&self, data: &mut UserData) -> Result<(), HistoryError> {
+        let mut next = data.clone();
+        self.step(&mut next, true)?;
+        *data = next;
+        Ok(())
+    }
+
+    /// Takes the change back. Nothing changes when it fails.
+    pub fn revert(&self, data: &mut UserData) -> Result<(), HistoryError> {
+        let mut next = data.clone();
+        self.step(&mut next, false)?;
+        *data = next;
+        Ok(())
+    }
+
+    /// A short line for menus and hover texts, e.g. "Delete machine 'DMU 50'".
+    pub fn describe(&self) -> String {
+        match self {
+            Edit::AddMachine { machine, .. } => format!("Add machine '{}'", machine.name),
+            Edit::DeleteMachine { machine, .. } => format!("Delete machine '{}'", machine.name),
+            Edit::UpdateMachine { after, .. } => format!("Edit machine '{}'", after.name),
+            Edit::ReplaceMachines { .. } => "Change machines".to_string(),
+            Edit::UpdateFavoriteColor { .. } => "Change favorite color".to_string(),
+            Edit::UpdateSettings { .. } => "Change settings".to_string(),
+            Edit::UpdateSavedFilters { .. } => "Change saved filters".to_string(),
+            Edit::UpdateToolLibrary { .. } => "Edit tool library".to_string(),
+            Edit::UpdateHolderInventory { .. } => "Edit holder inventory".to_string(),
+            Edit::Batch(edits) => format!("{} changes", edits.len()),
+        }
+    }
+
+    /// Applies the edit when `forward`, reverts it otherwise.
+    fn step(&self, data: &mut UserData, forward: bool) -> Result<(), HistoryError> {
+        fn pick<'a, T>(forward: bool, before: &'a T, after: &'a T) -> &'a T {
+            if forward {
+                after
+            } else {
+                before
+            }
+        }
+
+        let machines = &mut data.machines;
+        match self {
+            Edit::AddMachine { index, machine } | Edit::DeleteMachine { index, machine } => {
+                let adds = matches!(self, Edit::AddMachine { .. }) == forward;
+                if adds {
+                    if *index > machines.len() {
+                        return Err(HistoryError::NoSuchMachine(*index));
+                    }
+                    machines.insert(*index, machine.as_ref().clone());
+                } else {
+                    if *index >= machines.len() {
+                        return Err(HistoryError::NoSuchMachine(*index));
+                    }
+                    machines.remove(*index);
+                }
+            }
+            Edit::UpdateMachine {
+                index,
+                before,
+                after,
+            } => {
+                let machine = machines
+                    .get_mut(*index)
+                    .ok_or(HistoryError::NoSuchMachine(*index))?;
+                *machine = pick(forward, before, after).as_ref().clone();
+            }
+            Edit::ReplaceMachines { before, after } => {
+                *machines = pick(forward, before, after).clone();
+            }
+            Edit::UpdateFavoriteColor { before, after } => {
+                data.favorite_color = *pick(forward, before, after);
+            }
+            Edit::UpdateSettings { before, after } => {
+                data.settings = pick(forward, before, after).clone();
+            }
+            Edit::UpdateSavedFilters { before, after } => {
+                data.saved_filters = pick(forward, before, after).clone();
+            }
+            Edit::UpdateToolLibrary { before, after } => {
+                data.tool_library = pick(forward, before, after).clone();
+            }
+            Edit::UpdateHolderInventory { before, after } => {
+                data.holder_inventory = pick(forward, before, after).clone();
+            }
+            Edit::Batch(edits) => {
+                if forward {
+                    for edit in edits {
+                        edit.step(data, forward)?;
+                    }
+                } else {
+                    for edit in edits.iter().rev() {
+                        edit.step(data, forward)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Folds `later` into this edit when both change the same thing, so e.g. dragging a
+    /// color picker is undone in one step. Returns `later` when they cannot be merged.
+    fn merge(&mut self, later: Edit) -> Option<Edit> {
+        match (self, later) {
+            (
+                Edit::UpdateMachine { index, after, .. },
+                Edit::UpdateMachine {
+                    index: later_index,
+                    after: later_after,
+                    ..
+                },
+            ) if *index == later_index => *after = later_after,
+            (
+                Edit::UpdateFavoriteColor { after, .. },
+                Edit::UpdateFavoriteColor {
+                    after: later_after, ..
+                },
+            ) => *after = later_after,
+            (
+                Edit::UpdateSettings { after, .. },
+                Edit::UpdateSettings {
+                    after: later_after, ..
+                },
+            ) => *after = later_after,
+            (
+                Edit::UpdateSavedFilters { after, .. },
+                Edit::UpdateSavedFilters {
+                    after: later_after, ..
+                },
+            ) => *after = later_after,
+            (
+                Edit::UpdateToolLibrary { after, .. },
+                Edit::UpdateToolLibrary {
+                    after: later_after, ..
+                },
+            ) => *after = later_after,
+            (
+                Edit::UpdateHolderInventory { after, .. },
+                Edit::UpdateHolderInventory {
+                    after: later_after, ..
+                },
+            ) => *after = later_after,
+            (_, later) => return Some(later),
+        }
+        None
+    }
+
+    /// Whether applying the edit changes nothing.
+    fn is_noop(&self) -> bool {
+        match self {
+            Edit::AddMachine { .. } | Edit::DeleteMachine { .. } => false,
+            Edit::UpdateMachine { before, after, .. } => before == after,
+            Edit::ReplaceMachines { before, after } => before == after,
+            Edit::UpdateFavoriteColor { before, after } => before == after,
+            Edit::UpdateSettings { before, after } => before == after,
+            Edit::UpdateSavedFilters { before, after } => before == after,
+            Edit::UpdateToolLibrary { before, after } => before == after,
+            Edit::UpdateHolderInventory { before, after } => before == after,
+            Edit::Batch(edits) => edits.iter().all(Edit::is_noop),
+        }
+    }
+}
+
+/// A single added or deleted machine is its own edit, as is every changed machine when the
+/// count stays the same. Anything else replaces the whole list.
+fn machine_edits(before: &[Machine], after: &[Machine]) -> Vec<Edit> {
+    if before == after {
+        return vec![];
+    }
+    if after.len() == before.len() + 1 {
+        let index = first_difference(before, after);
+        if before[index..] == after[index + 1..] {
+            return vec![Edit::AddMachine {
+                index,
+                machine: Box::new(after[index].clone()),
+            }];
+        }
+    }
+    if before.len() == after.len() + 1 {
+        let index = first_difference(after, before);
+        if after[index..] == before[index + 1..] {
+            return vec![Edit::DeleteMachine {
+                index,
+                machine: Box::new(before[index].clone()),
+            }];
+        }
+    }
+    if before.len() == after.len() {
+        return before
+            .iter()
+            .zip(after)
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(index, (old, new))| Edit::UpdateMachine {
+                index,
+                before: Box::new(old.clone()),
+                after: Box::new(new.clone()),
+            })
+            .collect();
+    }
+    vec![Edit::ReplaceMachines {
+        before: before.to_vec(),
+        after: after.to_vec(),
+    }]
+}
+
+/// Index of the first machine in `shorter` that differs from `longer`.
+fn first_difference(shorter: &[Machine], longer: &[Machine]) -> usize {
+    shorter
+        .iter()
+        .zip(longer)
+        .position(|(a, b)| a != b)
+        .unwrap_or(shorter.len())
+}
+
+/// Edits that can be undone, and undone edits that can be redone. Making a new edit drops
+/// what could be redone. Only the last `limit` edits are kept.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct History {
+    done: VecDeque<Edit>,
+    undone: Vec<Edit>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_HISTORY_LIMIT)
+    }
+}
+
+impl History {
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: vec![],
+            limit,
+        }
+    }
+
+    /// Adds an edit that was just made.
+    pub fn record(&mut self, edit: Edit) {
+        self.undone.clear();
+        self.done.push_back(edit);
+        while self.done.len() > self.limit {
+            self.done.pop_front();
+        }
+    }
+
+    /// Like [`History::record`], but folds the edit into the last one when both change the
+    /// same thing. An edit that ends up changing nothing is dropped.
+    pub fn record_merging(&mut self, edit: Edit) {
+        let Some(last) = self.done.back_mut() else {
+            self.record(edit);
+            return;
+        };
+        match last.merge(edit) {
+            Some(edit) => self.record(edit),
+            None => {
+                self.undone.clear();
+                if last.is_noop() {
+                    self.done.pop_back();
+                }
+            }
+        }
+    }
+
+    pub fn undo(&mut self, data: &mut UserData) -> Result<(), HistoryError> {
+        let edit = self.done.pop_back().ok_or(HistoryError::NothingToUndo)?;
+        if let Err(e) = edit.revert(data) {
+            self.done.push_back(edit);
+            return Err(e);
+        }
+        self.undone.push(edit);
+        Ok(())
+    }
+
+    pub fn redo(&mut self, data: &mut UserData) -> Result<(), HistoryError> {
+        let edit = self.undone.pop().ok_or(HistoryError::NothingToRedo)?;
+        if let Err(e) = edit.apply(data) {
+            self.undone.push(edit);
+            return Err(e);
+        }
+        self.done.push_back(edit);
+        Ok(())
+    }
+
+    /// The edit [`History::undo`] would take back.
+    pub fn next_undo(&self) -> Option<&Edit> {
+        self.done.back()
+    }
+
+    /// The edit [`History::redo`] would make again.
+    pub fn next_redo(&self) -> Option<&Edit> {
+        self.undone.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
@@ -1,19 +1,21 @@
 use std::fmt;
 
 use egui::Ui;
+use enum_iterator::Sequence;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     custom_traits::{
-        AddHolderCopy, DeleteHolder, GetHolderType, GetRotatingHolderCategory,
-        GetTurningHolderCategory, GetUuid, UiDisplay,
+        AddHolderCopy, DeleteHolder, GetDegree, GetDiameter, GetHolderType,
+        GetRotatingHolderCategory, GetTurningHolderCategory, GetUuid, UiDisplay,
     },
     tools::tool::Handedness,
+    validation::{FieldError, Validator},
 };
 
 // Highest level holder
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Holder {
     Rotating(RotatingHolder),
     Turning(TurningHolder),
@@ -30,13 +32,13 @@ impl AddHolderCopy for Holder {
 
 impl AddHolderCopy for RotatingHolder {
     fn add_copy(&mut self) {
-        self.duplicates = self.duplicates + 1;
+        self.duplicates += 1;
     }
 }
 
 impl AddHolderCopy for TurningHolder {
     fn add_copy(&mut self) {
-        self.duplicates = self.duplicates + 1;
+        self.duplicates += 1;
     }
 }
 
@@ -52,7 +54,7 @@ impl DeleteHolder for Holder {
 impl DeleteHolder for RotatingHolder {
     fn delete_holder(&mut self) -> bool {
         if self.duplicates > 1 {
-            self.duplicates = self.duplicates - 1;
+            self.duplicates -= 1;
             false
         } else {
             true
@@ -63,7 +65,7 @@ impl DeleteHolder for RotatingHolder {
 impl DeleteHolder for TurningHolder {
     fn delete_holder(&mut self) -> bool {
         if self.duplicates > 1 {
-            self.duplicates = self.duplicates - 1;
+            self.duplicates -= 1;
             false
         } else {
             true
@@ -92,6 +94,33 @@ impl GetUuid for TurningHolder {
     }
 }
 
+impl Holder {
+    /// Number of physical copies owned.
+    pub fn copies(&self) -> usize {
+        match self {
+            Holder::Rotating(holder) => holder.duplicates,
+            Holder::Turning(holder) => holder.duplicates,
+        }
+    }
+
+    /// Short one line description, e.g. for a table cell.
+    pub fn summary(&self) -> String {
+        match self {
+            Holder::Rotating(holder) => format!("{} {}", holder.category, holder.taper_type)
+                .trim_end()
+                .to_string(),
+            Holder::Turning(holder) => format!("{} {:.0}°", holder.category, holder.degree),
+        }
+    }
+
+    pub fn hover_ui(&self, ui: &mut Ui) {
+        match self {
+            Holder::Rotating(holder) => rotating_hover_ui(ui, holder),
+            Holder::Turning(holder) => turning_hover_ui(ui, holder),
+        }
+    }
+}
+
 impl UiDisplay for Holder {
     fn display(&self, ui: &mut egui::Ui) {
         match self {
@@ -101,7 +130,7 @@ impl UiDisplay for Holder {
     }
 }
 
-fn rotating_hover_ui(ui: &mut Ui, rotating_holder: &RotatingHolder) {
+pub fn rotating_hover_ui(ui: &mut Ui, rotating_holder: &RotatingHolder) {
     egui::Grid::new("rotating_holder_info")
         .num_columns(2)
         .striped(true)
@@ -133,6 +162,9 @@ fn rotating_hover_ui(ui: &mut Ui, rotating_holder: &RotatingHolder) {
             ui.label("Taper Type:");
             ui.label(&rotating_holder.taper_type);
             ui.end_row();
+            ui.label("Weight:");
+            ui.label(format!("{:.0} g", rotating_holder.weight));
+            ui.end_row();
             ui.label("Tension Compression Range:");
             ui.label(format!("{:?}", rotating_holder.tension_compression_range));
             ui.end_row();
@@ -224,7 +256,7 @@ impl UiDisplay for RotatingHolder {
     }
 }
 
-fn turning_hover_ui(ui: &mut Ui, turning_holder: &TurningHolder) {
+pub fn turning_hover_ui(ui: &mut Ui, turning_holder: &TurningHolder) {
     egui::Grid::new("turning_holder_info")
         .num_columns(2)
         .striped(true)
@@ -241,6 +273,9 @@ fn turning_hover_ui(ui: &mut Ui, turning_holder: &TurningHolder) {
             ui.label("Overall Length:");
             ui.label(format!("{:.2} mm", turning_holder.overall_length));
             ui.end_row();
+            ui.label("Weight:");
+            ui.label(format!("{:.0} g", turning_holder.weight));
+            ui.end_row();
             ui.label("Insert Size:");
             ui.label(&turning_holder.insert_size);
             ui.end_row();
@@ -398,8 +433,50 @@ impl GetTurningHolderCategory for TurningHolder {
         Some(self.category.clone())
     }
 }
+impl GetDiameter for Holder {
+    fn get_diameter(&self) -> Option<f32> {
+        match self {
+            Holder::Rotating(item) => item.get_diameter(),
+            Holder::Turning(item) => item.get_diameter(),
+        }
+    }
+}
+
+impl GetDiameter for RotatingHolder {
+    fn get_diameter(&self) -> Option<f32> {
+        Some(self.diameter)
+    }
+}
+
+impl GetDiameter for TurningHolder {
+    fn get_diameter(&self) -> Option<f32> {
+        None
+    }
+}
+
+impl GetDegree for Holder {
+    fn get_degree(&self) -> Option<f32> {
+        match self {
+            Holder::Rotating(item) => item.get_degree(),
+            Holder::Turning(item) => item.get_degree(),
+        }
+    }
+}
+
+impl GetDegree for RotatingHolder {
+    fn get_degree(&self) -> Option<f32> {
+        None
+    }
+}
+
+impl GetDegree for TurningHolder {
+    fn get_degree(&self) -> Option<f32> {
+        Some(self.degree)
+    }
+}
+
 // Second highest level holder
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RotatingHolder {
     pub uuid: String,
     pub duplicates: usize,
@@ -412,6 +489,9 @@ pub struct RotatingHolder {
     pub taper_type: String,
     pub runout: f32,
     pub balance_grade: String,
+    /// Grams, 0 when unknown
+    #[serde(default)]
+    pub weight: f32,
     // New subcategory-specific fields
     pub collet_type: String,                   // For Collet
     pub weldon_flat_size: f32,                 // For EndMill (WeldonFlat)
@@ -419,6 +499,50 @@ pub struct RotatingHolder {
     pub tension_compression_range: (f32, f32), // For Tapping (TensionCompression)
 }
 
+impl RotatingHolder {
+    pub fn uses_collet_type(&self) -> bool {
+        matches!(self.category, RotatingHolderCategory::Collet(_))
+    }
+
+    pub fn uses_weldon_flat_size(&self) -> bool {
+        self.category == RotatingHolderCategory::EndMill(EndMillSubcategory::WeldonFlat)
+    }
+
+    pub fn uses_adjustable_range(&self) -> bool {
+        matches!(self.category, RotatingHolderCategory::BoringHead(_))
+    }
+
+    pub fn uses_tension_compression_range(&self) -> bool {
+        self.category == RotatingHolderCategory::Tapping(TappingSubcategory::TensionCompression)
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut v = Validator::default();
+        v.check(
+            self.category != RotatingHolderCategory::Empty,
+            "Category",
+            "choose a category",
+        );
+        v.check(self.duplicates > 0, "Copies", "must be at least 1");
+        v.positive("Diameter", self.diameter);
+        v.positive("Length", self.length);
+        v.check(self.max_rpm > 0, "Maximum RPM", "must be greater than 0");
+        v.range("Tool Clamping Range", self.tool_clamping_range);
+        v.non_negative("Runout", self.runout);
+        v.non_negative("Weight", self.weight);
+        if self.uses_weldon_flat_size() {
+            v.positive("Weldon Flat Size", self.weldon_flat_size);
+        }
+        if self.uses_adjustable_range() {
+            v.range("Adjustable Range", self.adjustable_range);
+        }
+        if self.uses_tension_compression_range() {
+            v.range("Tension Compression Range", self.tension_compression_range);
+        }
+        v.finish()
+    }
+}
+
 impl Default for RotatingHolder {
     fn default() -> Self {
         Self {
@@ -433,6 +557,7 @@ impl Default for RotatingHolder {
             taper_type: "".to_string(),
             runout: 1.0,
             balance_grade: "".to_string(),
+            weight: 0.0,
             collet_type: "".to_string(),
             weldon_flat_size: 1.0,
             adjustable_range: (0.0, 1.0),
@@ -441,7 +566,7 @@ impl Default for RotatingHolder {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TurningHolder {
     pub uuid: String,
     pub duplicates: usize,
@@ -457,6 +582,9 @@ pub struct TurningHolder {
     pub min_bore_diameter: f32,
     pub max_cutting_diameter: f32,
     pub quick_change_compatible: bool,
+    /// Grams, 0 when unknown
+    #[serde(default)]
+    pub weight: f32,
     // New subcategory-specific fields
     pub cartridge_type: String, // For PartingGrooving (CartridgeType)
     pub thread_pitch_range: (f32, f32), // For Threading
@@ -464,6 +592,54 @@ pub struct TurningHolder {
     pub tool_post_size: String, // For QuickChangePost
 }
 
+impl TurningHolder {
+    pub fn uses_bore_limits(&self) -> bool {
+        matches!(self.category, TurningHolderCategory::Internal(_))
+    }
+
+    pub fn uses_cartridge_type(&self) -> bool {
+        self.category
+            == TurningHolderCategory::PartingGrooving(PartingGroovingSubcategory::CartridgeType)
+    }
+
+    pub fn uses_thread_pitch_range(&self) -> bool {
+        matches!(self.category, TurningHolderCategory::Threading(_))
+    }
+
+    pub fn uses_form_profile(&self) -> bool {
+        matches!(self.category, TurningHolderCategory::Form(_))
+    }
+
+    pub fn uses_tool_post_size(&self) -> bool {
+        matches!(self.category, TurningHolderCategory::QuickChangePost(_))
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut v = Validator::default();
+        v.check(
+            self.category != TurningHolderCategory::Empty,
+            "Category",
+            "choose a category",
+        );
+        v.check(self.duplicates > 0, "Copies", "must be at least 1");
+        v.angle("Degree", self.degree);
+        v.positive("Shank Height", self.shank_height);
+        v.positive("Shank Width", self.shank_width);
+        v.positive("Overall Length", self.overall_length);
+        v.non_negative("Max Cutting Diameter", self.max_cutting_diameter);
+        v.non_negative("Weight", self.weight);
+        if self.uses_bore_limits() {
+            v.positive("Max Bore Depth", self.max_bore_depth);
+            v.positive("Min Bore Diameter", self.min_bore_diameter);
+        }
+        if self.uses_thread_pitch_range() {
+            v.range("Thread Pitch Range", self.thread_pitch_range);
+            v.positive("Thread Pitch Range", self.thread_pitch_range.0);
+        }
+        v.finish()
+    }
+}
+
 impl Default for TurningHolder {
     fn default() -> Self {
         Self {
@@ -481,6 +657,7 @@ impl Default for TurningHolder {
             min_bore_diameter: 1.0,
             max_cutting_diameter: 3.0,
             quick_change_compatible: false,
+            weight: 0.0,
             cartridge_type: "".to_string(),
             thread_pitch_range: (0.0, 1.0),
             form_profile: "".to_string(),
@@ -490,7 +667,7 @@ impl Default for TurningHolder {
 }
 
 // ------------- ROTATING HOLDERS ---------------- //
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum RotatingHolderCategory {
     #[default]
     Empty,
@@ -504,6 +681,28 @@ pub enum RotatingHolderCategory {
     Tapping(TappingSubcategory),
 }
 
+impl RotatingHolderCategory {
+    /// Name of the category without its subcategory.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RotatingHolderCategory::Empty => "Empty",
+            RotatingHolderCategory::Collet(_) => "Collet",
+            RotatingHolderCategory::EndMill(_) => "End Mill",
+            RotatingHolderCategory::ShellMill(_) => "Shell Mill",
+            RotatingHolderCategory::ShrinkFit(_) => "Shrink Fit",
+            RotatingHolderCategory::Hydraulic(_) => "Hydraulic",
+            RotatingHolderCategory::DrillChuck(_) => "Drill Chuck",
+            RotatingHolderCategory::BoringHead(_) => "Boring Head",
+            RotatingHolderCategory::Tapping(_) => "Tapping",
+        }
+    }
+
+    /// True when both are the same category, whatever their subcategories.
+    pub fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
 impl fmt::Display for RotatingHolderCategory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -520,6 +719,26 @@ impl fmt::Display for RotatingHolderCategory {
     }
 }
 
+impl TurningHolderCategory {
+    /// Name of the category without its subcategory.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TurningHolderCategory::Empty => "Empty",
+            TurningHolderCategory::External(_) => "External",
+            TurningHolderCategory::Internal(_) => "Internal",
+            TurningHolderCategory::PartingGrooving(_) => "Parting/Grooving",
+            TurningHolderCategory::Threading(_) => "Threading",
+            TurningHolderCategory::Form(_) => "Form",
+            TurningHolderCategory::QuickChangePost(_) => "Quick Change Post",
+        }
+    }
+
+    /// True when both are the same category, whatever their subcategories.
+    pub fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
 impl fmt::Display for TurningHolderCategory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -533,7 +752,7 @@ impl fmt::Display for TurningHolderCategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum ColletSubCategory {
     #[default]
     ER,
@@ -551,7 +770,7 @@ impl fmt::Display for ColletSubCategory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum EndMillSubcategory {
     #[default]
     WeldonFlat,
@@ -567,7 +786,7 @@ impl fmt::Display for EndMillSubcategory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum ShellMillSubcategory {
     #[default]
     ShellMill,
@@ -581,7 +800,7 @@ impl fmt::Display for ShellMillSubcategory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum ShrinkFitSubcategory {
     #[default]
     ShrinkFit,
@@ -595,7 +814,7 @@ impl fmt::Display for ShrinkFitSubcategory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum HydraulicSubcategory {
     #[default]
     Hydraulic,
@@ -609,7 +828,7 @@ impl fmt::Display for HydraulicSubcategory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum DrillChuckSubcategory {
     #[default]
     DrillChuck,
@@ -622,7 +841,7 @@ impl fmt::Display for DrillChuckSubcategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum BoringHeadSubcategory {
     #[default]
     Adjustable,
@@ -637,7 +856,7 @@ impl fmt::Display for BoringHeadSubcategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum TappingSubcategory {
     #[default]
     TensionCompression,
@@ -654,7 +873,7 @@ impl fmt::Display for TappingSubcategory {
 }
 
 // ------------- TURNING HOLDERS ---------------- //
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum TurningHolderCategory {
     #[default]
     Empty,
@@ -666,7 +885,7 @@ pub enum TurningHolderCategory {
     QuickChangePost(QuickChangePostSubcategory),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum ExternalSubcategory {
     #[default]
     RightHand,
@@ -684,7 +903,7 @@ impl fmt::Display for ExternalSubcategory {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum InternalSubcategory {
     #[default]
     BoringBar,
@@ -699,7 +918,7 @@ impl fmt::Display for InternalSubcategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum PartingGroovingSubcategory {
     #[default]
     BladeType,
@@ -714,7 +933,7 @@ impl fmt::Display for PartingGroovingSubcategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum ThreadingSubcategory {
     #[default]
     External,
@@ -729,7 +948,7 @@ impl fmt::Display for ThreadingSubcategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum FormSubcategory {
     #[default]
     Form,
@@ -742,7 +961,7 @@ impl fmt::Display for FormSubcategory {
         }
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Sequence)]
 pub enum QuickChangePostSubcategory {
     #[default]
     QCTP,
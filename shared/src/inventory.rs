@@ -0,0 +1,255 @@
+//! The holders the shop owns and how many copies of each are in machine slots.
+//!
+//! Each entry's `duplicates` is the number of physical copies owned. Copies in use are
+//! counted from the slots, so the two can only disagree when a slot is edited around the
+//! inventory, e.g. by a restore or an import. The inventory never raises the owned count on
+//! its own, it reports the [`Shortage`] instead.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_traits::{AddHolderCopy, DeleteHolder, GetUuid},
+    holders::holder::Holder,
+    machine::Machine,
+    placement::{
+        machine_slot_mut, place_holder, slot, slots_where, MachineSlot, PlacementError, SlotAddress,
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct HolderInventory {
+    holders: Vec<Holder>,
+}
+
+/// One line of the inventory report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HolderStock<'a> {
+    pub holder: &'a Holder,
+    pub owned: usize,
+    pub in_use: usize,
+}
+
+impl HolderStock<'_> {
+    pub fn free(&self) -> usize {
+        self.owned.saturating_sub(self.in_use)
+    }
+
+    /// Copies in slots beyond the ones owned.
+    pub fn missing(&self) -> usize {
+        self.in_use.saturating_sub(self.owned)
+    }
+}
+
+/// A holder with more copies in slots than the inventory owns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortage {
+    pub uuid: String,
+    /// The holder in a few words, see [`Holder::summary`]
+    pub summary: String,
+    pub owned: usize,
+    pub in_use: usize,
+}
+
+impl fmt::Display for Shortage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is in {} slots but only {} copies are owned",
+            self.summary, self.in_use, self.owned
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryError {
+    NotFound(String),
+    Duplicate(String),
+    /// Every owned copy is already in a slot.
+    NoFreeCopy {
+        owned: usize,
+    },
+    Placement(PlacementError),
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::NotFound(uuid) => {
+                write!(f, "Holder {} is not in the inventory", uuid)
+            }
+            InventoryError::Duplicate(uuid) => {
+                write!(f, "Holder {} is already in the inventory", uuid)
+            }
+            InventoryError::NoFreeCopy { owned } => {
+                write!(f, "All {} copies of this holder are in use", owned)
+            }
+            InventoryError::Placement(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+impl From<PlacementError> for InventoryError {
+    fn from(e: PlacementError) -> Self {
+        InventoryError::Placement(e)
+    }
+}
+
+impl HolderInventory {
+    pub fn holders(&self) -> &[Holder] {
+        &self.holders
+    }
+
+    pub fn get(&self, uuid: &str) -> Option<&Holder> {
+        self.holders.iter().find(|holder| holder.get_uuid() == uuid)
+    }
+
+    fn get_mut(&mut self, uuid: &str) -> Result<&mut Holder, InventoryError> {
+        self.holders
+            .iter_mut()
+            .find(|holder| holder.get_uuid() == uuid)
+            .ok_or_else(|| InventoryError::NotFound(uuid.to_string()))
+    }
+
+    pub fn add(&mut self, holder: Holder) -> Result<(), InventoryError> {
+        let uuid = holder.get_uuid();
+        if self.get(&uuid).is_some() {
+            return Err(InventoryError::Duplicate(uuid));
+        }
+        self.holders.push(holder);
+        Ok(())
+    }
+
+    /// Adds loaded holders that have no entry yet. Returns the holders with more copies in
+    /// use than owned, the owned counts are left for the user to correct.
+    pub fn adopt_loaded(&mut self, machines: &[Machine]) -> Vec<Shortage> {
+        let loaded: Vec<Holder> = machines
+            .iter()
+            .flat_map(|machine| &machine.magazines)
+            .flat_map(|magazine| &magazine.slots)
+            .filter_map(|slot| slot.holder.clone())
+            .collect();
+        for holder in loaded {
+            // Duplicates keep the first copy found
+            let _ = self.add(holder);
+        }
+
+        self.shortages(machines)
+    }
+
+    /// Every holder with more copies in use than owned.
+    pub fn shortages(&self, machines: &[Machine]) -> Vec<Shortage> {
+        self.report(machines)
+            .into_iter()
+            .filter(|stock| stock.missing() > 0)
+            .map(|stock| Shortage {
+                uuid: stock.holder.get_uuid(),
+                summary: stock.holder.summary(),
+                owned: stock.owned,
+                in_use: stock.in_use,
+            })
+            .collect()
+    }
+
+    /// Owned, in use and free copies of every holder, in inventory order.
+    pub fn report(&self, machines: &[Machine]) -> Vec<HolderStock<'_>> {
+        self.holders
+            .iter()
+            .map(|holder| HolderStock {
+                holder,
+                owned: holder.copies(),
+                in_use: uses(machines, &holder.get_uuid()).len(),
+            })
+            .collect()
+    }
+
+    /// Records one more owned copy. Returns the new number of copies.
+    pub fn add_copy(
+        &mut self,
+        machines: &mut [Machine],
+        uuid: &str,
+    ) -> Result<usize, InventoryError> {
+        let holder = self.get_mut(uuid)?;
+        holder.add_copy();
+        let copies = holder.copies();
+        self.sync(machines, uuid);
+        Ok(copies)
+    }
+
+    /// Records one copy less, removing the entry with its last copy. Only free copies can
+    /// be removed. Returns the number of copies left.
+    pub fn remove_copy(
+        &mut self,
+        machines: &mut [Machine],
+        uuid: &str,
+    ) -> Result<usize, InventoryError> {
+        let in_use = uses(machines, uuid).len();
+        let holder = self.get_mut(uuid)?;
+        let owned = holder.copies();
+        if owned <= in_use {
+            return Err(InventoryError::NoFreeCopy { owned });
+        }
+        if holder.delete_holder() {
+            self.holders.retain(|holder| holder.get_uuid() != uuid);
+            return Ok(0);
+        }
+        let copies = holder.copies();
+        self.sync(machines, uuid);
+        Ok(copies)
+    }
+
+    /// Puts a free copy of the holder into a slot, returning the holder it replaced.
+    /// A slot that already holds a copy keeps it without using another.
+    pub fn place(
+        &self,
+        machines: &mut [Machine],
+        machine_index: usize,
+        address: SlotAddress,
+        uuid: &str,
+        changed_by: &str,
+    ) -> Result<Option<Holder>, InventoryError> {
+        let holder = self
+            .get(uuid)
+            .ok_or_else(|| InventoryError::NotFound(uuid.to_string()))?;
+        let machine = machines
+            .get(machine_index)
+            .ok_or(PlacementError::NoSuchMachine(machine_index))?;
+        let already_there = slot(machine, address)?
+            .holder
+            .as_ref()
+            .is_some_and(|holder| holder.get_uuid() == uuid);
+        let owned = holder.copies();
+        if !already_there && uses(machines, uuid).len() >= owned {
+            return Err(InventoryError::NoFreeCopy { owned });
+        }
+        Ok(place_holder(
+            &mut machines[machine_index],
+            address,
+            holder.clone(),
+            changed_by,
+        )?)
+    }
+
+    /// Keeps the copies in slots equal to the entry, e.g. after its count changed.
+    fn sync(&self, machines: &mut [Machine], uuid: &str) {
+        let Some(holder) = self.get(uuid) else {
+            return;
+        };
+        for at in uses(machines, uuid) {
+            machine_slot_mut(machines, at).holder = Some(holder.clone());
+        }
+    }
+}
+
+/// Every slot a copy of the holder with `uuid` is in.
+pub fn uses(machines: &[Machine], uuid: &str) -> Vec<MachineSlot> {
+    slots_where(machines, |slot| {
+        slot.holder
+            .as_ref()
+            .is_some_and(|holder| holder.get_uuid() == uuid)
+    })
+}
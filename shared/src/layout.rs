@@ -0,0 +1,207 @@
+//! Adding, resizing and removing the magazines of a machine.
+//!
+//! Slots that go away with a smaller or removed magazine are handled by a [`ShrinkPolicy`],
+//! so nothing in them is dropped without the caller deciding to.
+
+use std::fmt;
+
+use crate::{
+    machine::Machine,
+    magazine::{Magazine, MagazineKind},
+    placement::{PlacementError, SlotAddress},
+    slot::Slot,
+};
+
+/// What to do with the contents of slots that go away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShrinkPolicy {
+    /// Refuse while any of them holds something.
+    Block,
+    /// Move them into empty slots, the rest of the same magazine first, then the other
+    /// magazines in order.
+    Relocate,
+    /// Take them out of the machine. Tools stay in the library and holders become free
+    /// copies in the inventory.
+    Unload,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    /// Slots that would go away and still hold something.
+    Occupied(Vec<SlotAddress>),
+    NotEnoughFreeSlots {
+        needed: usize,
+        free: usize,
+    },
+    Placement(PlacementError),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Occupied(addresses) => {
+                write!(
+                    f,
+                    "{} slot(s) that would go away are in use",
+                    addresses.len()
+                )
+            }
+            LayoutError::NotEnoughFreeSlots { needed, free } => write!(
+                f,
+                "{} slot(s) need to move but only {} empty slot(s) are left",
+                needed, free
+            ),
+            LayoutError::Placement(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+impl From<PlacementError> for LayoutError {
+    fn from(e: PlacementError) -> Self {
+        LayoutError::Placement(e)
+    }
+}
+
+/// Appends an empty magazine and returns its index.
+pub fn add_magazine(
+    machine: &mut Machine,
+    name: String,
+    capacity: usize,
+    kind: Option<MagazineKind>,
+) -> usize {
+    let index = machine.magazines.len();
+    machine.magazines.push(Magazine {
+        name,
+        kind,
+        ..Magazine::new(index, capacity)
+    });
+    machine.magazine_count = machine.magazines.len();
+    index
+}
+
+/// Gives the magazine `capacity` slots. Growing adds empty slots at the end, shrinking
+/// drops the last ones after handling their contents by `policy`. Returns where relocated
+/// slots ended up.
+pub fn resize_magazine(
+    machine: &mut Machine,
+    index: usize,
+    capacity: usize,
+    policy: ShrinkPolicy,
+    changed_by: &str,
+) -> Result<Vec<SlotAddress>, LayoutError> {
+    let magazine = machine
+        .magazines
+        .get_mut(index)
+        .ok_or(PlacementError::NoSuchMagazine(index))?;
+    if capacity >= magazine.slots.len() {
+        magazine.slots.resize(capacity, Slot::default());
+        magazine.capacity = capacity;
+        return Ok(vec![]);
+    }
+
+    let moved_to = clear_tail(machine, index, capacity, policy, changed_by)?;
+    let magazine = &mut machine.magazines[index];
+    magazine.slots.truncate(capacity);
+    magazine.capacity = capacity;
+    Ok(moved_to)
+}
+
+/// Removes the magazine after handling its contents by `policy`. Later magazines move one
+/// index down. Returns where relocated slots ended up, with the new indices.
+pub fn remove_magazine(
+    machine: &mut Machine,
+    index: usize,
+    policy: ShrinkPolicy,
+    changed_by: &str,
+) -> Result<Vec<SlotAddress>, LayoutError> {
+    if index >= machine.magazines.len() {
+        return Err(PlacementError::NoSuchMagazine(index).into());
+    }
+    let moved_to = clear_tail(machine, index, 0, policy, changed_by)?;
+    machine.magazines.remove(index);
+    for (position, magazine) in machine.magazines.iter_mut().enumerate() {
+        magazine.index_in_machine = position;
+    }
+    machine.magazine_count = machine.magazines.len();
+    Ok(moved_to
+        .into_iter()
+        .map(|address| {
+            if address.magazine > index {
+                SlotAddress::new(address.magazine - 1, address.slot)
+            } else {
+                address
+            }
+        })
+        .collect())
+}
+
+/// Empties the slots of magazine `index` from `from` on by `policy`.
+/// Nothing changes when it fails.
+fn clear_tail(
+    machine: &mut Machine,
+    index: usize,
+    from: usize,
+    policy: ShrinkPolicy,
+    changed_by: &str,
+) -> Result<Vec<SlotAddress>, LayoutError> {
+    let tail = &machine.magazines[index].slots[from..];
+    // A reservation is never dropped, whatever the policy
+    if let Some(offset) = tail.iter().position(|slot| slot.locked) {
+        return Err(PlacementError::Locked(SlotAddress::new(index, from + offset)).into());
+    }
+    let occupied: Vec<SlotAddress> = tail
+        .iter()
+        .enumerate()
+        .filter(|(_, slot)| !slot.is_empty())
+        .map(|(offset, _)| SlotAddress::new(index, from + offset))
+        .collect();
+
+    match policy {
+        _ if occupied.is_empty() => Ok(vec![]),
+        ShrinkPolicy::Block => Err(LayoutError::Occupied(occupied)),
+        ShrinkPolicy::Unload => Ok(vec![]),
+        ShrinkPolicy::Relocate => {
+            let free = free_slots(machine, index, from);
+            if free.len() < occupied.len() {
+                return Err(LayoutError::NotEnoughFreeSlots {
+                    needed: occupied.len(),
+                    free: free.len(),
+                });
+            }
+            for (source, &target) in occupied.iter().zip(&free) {
+                let mut slot =
+                    std::mem::take(&mut machine.magazines[source.magazine].slots[source.slot]);
+                slot.touch(changed_by);
+                machine.magazines[target.magazine].slots[target.slot] = slot;
+            }
+            Ok(free.into_iter().take(occupied.len()).collect())
+        }
+    }
+}
+
+/// Empty, unreserved slots outside the tail of magazine `index` that starts at `from`,
+/// that magazine first.
+fn free_slots(machine: &Machine, index: usize, from: usize) -> Vec<SlotAddress> {
+    let is_free = |slot: &Slot| slot.is_empty() && !slot.locked;
+    let same_magazine = machine.magazines[index].slots[..from]
+        .iter()
+        .enumerate()
+        .filter(|(_, slot)| is_free(slot))
+        .map(|(position, _)| SlotAddress::new(index, position));
+    let other_magazines = machine
+        .magazines
+        .iter()
+        .enumerate()
+        .filter(|(position, _)| *position != index)
+        .flat_map(|(magazine_index, magazine)| {
+            magazine
+                .slots
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| is_free(slot))
+                .map(move |(position, _)| SlotAddress::new(magazine_index, position))
+        });
+    same_magazine.chain(other_magazines).collect()
+}
@@ -1,24 +1,76 @@
 use egui::Color32;
+use filter::SavedFilter;
+use inventory::HolderInventory;
+use library::ToolLibrary;
 use machine::Machine;
 use selection::Selections;
 use serde::{Deserialize, Serialize};
 use settings::*;
+use upgrade::CURRENT_USER_DATA_VERSION;
 
+pub mod assembly;
+pub mod audit;
+pub mod backup;
+pub mod compatibility;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod credentials;
 pub mod custom_traits;
 pub mod description;
+pub mod filter;
+pub mod history;
 pub mod holders;
+pub mod inventory;
+pub mod layout;
+pub mod library;
 pub mod machine;
 pub mod magazine;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod migrations;
+pub mod placement;
+pub mod saving;
 pub mod selection;
 pub mod settings;
+pub mod slot;
+pub mod sorting;
+pub mod spreadsheet;
+pub mod sync;
 pub mod tools;
+pub mod transfer;
+pub mod upgrade;
+pub mod validation;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct UserData {
+    /// Shape of the serialized data, see [`upgrade`]. Missing in blobs from before versioning.
+    #[serde(default)]
+    pub version: u32,
     pub favorite_color: Color32,
     pub machines: Vec<Machine>,
     pub selections: Selections,
     pub settings: Settings,
+    #[serde(default)]
+    pub saved_filters: Vec<SavedFilter>,
+    /// Every tool the user owns, loaded or not
+    #[serde(default)]
+    pub tool_library: ToolLibrary,
+    /// Every holder the user owns, with the number of copies
+    #[serde(default)]
+    pub holder_inventory: HolderInventory,
+}
+
+impl Default for UserData {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_USER_DATA_VERSION,
+            favorite_color: Color32::default(),
+            machines: vec![],
+            selections: Selections::default(),
+            settings: Settings::default(),
+            saved_filters: vec![],
+            tool_library: ToolLibrary::default(),
+            holder_inventory: HolderInventory::default(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -0,0 +1,184 @@
+//! Tools the shop owns, whether or not they are loaded into a magazine.
+//!
+//! A loaded tool is a copy of its library entry with the same uuid. Edits and removals go
+//! through [`ToolLibrary`] so those copies never drift from the entry they were loaded from.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_traits::GetUuid,
+    machine::Machine,
+    placement::{
+        machine_slot_mut, place_tool, slots_where, MachineSlot, PlacementError, SlotAddress,
+    },
+    tools::tool::Tool,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct ToolLibrary {
+    tools: Vec<Tool>,
+}
+
+/// What to do with the loaded copies of a tool that is removed from the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovePolicy {
+    /// Refuse while the tool is loaded anywhere.
+    Block,
+    /// Take the tool out of every slot it is loaded into.
+    Unload,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryError {
+    NotFound(String),
+    Duplicate(String),
+    InUse(Vec<MachineSlot>),
+    Placement(PlacementError),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LibraryError::NotFound(uuid) => write!(f, "Tool {} is not in the library", uuid),
+            LibraryError::Duplicate(uuid) => {
+                write!(f, "Tool {} is already in the library", uuid)
+            }
+            LibraryError::InUse(uses) => write!(f, "Tool is loaded in {} slot(s)", uses.len()),
+            LibraryError::Placement(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+impl From<PlacementError> for LibraryError {
+    fn from(e: PlacementError) -> Self {
+        LibraryError::Placement(e)
+    }
+}
+
+impl ToolLibrary {
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    pub fn get(&self, uuid: &str) -> Option<&Tool> {
+        self.tools.iter().find(|tool| tool.get_uuid() == uuid)
+    }
+
+    pub fn add(&mut self, tool: Tool) -> Result<(), LibraryError> {
+        let uuid = tool.get_uuid();
+        if self.get(&uuid).is_some() {
+            return Err(LibraryError::Duplicate(uuid));
+        }
+        self.tools.push(tool);
+        Ok(())
+    }
+
+    /// Adds every loaded tool that has no library entry yet, e.g. tools loaded before
+    /// the library existed.
+    pub fn adopt_loaded(&mut self, machines: &[Machine]) {
+        let loaded = machines
+            .iter()
+            .flat_map(|machine| &machine.magazines)
+            .flat_map(|magazine| &magazine.slots)
+            .filter_map(|slot| slot.tool.as_ref());
+        for tool in loaded {
+            // Duplicates keep the first copy found
+            let _ = self.add(tool.clone());
+        }
+    }
+
+    /// Replaces the entry with the same uuid and every loaded copy of it.
+    /// Returns the slots that were updated.
+    pub fn update(
+        &mut self,
+        machines: &mut [Machine],
+        tool: Tool,
+        changed_by: &str,
+    ) -> Result<Vec<MachineSlot>, LibraryError> {
+        let uuid = tool.get_uuid();
+        let entry = self
+            .tools
+            .iter_mut()
+            .find(|entry| entry.get_uuid() == uuid)
+            .ok_or_else(|| LibraryError::NotFound(uuid.clone()))?;
+
+        let uses = uses(machines, &uuid);
+        // Reserved slots are updated too, the reservation is for the tool, not its numbers
+        for &at in &uses {
+            let slot = machine_slot_mut(machines, at);
+            slot.tool = Some(tool.clone());
+            slot.touch(changed_by);
+        }
+        *entry = tool;
+        Ok(uses)
+    }
+
+    /// Removes the entry and, depending on `policy`, unloads it.
+    /// Nothing changes when the tool is loaded into a reserved slot.
+    pub fn remove(
+        &mut self,
+        machines: &mut [Machine],
+        uuid: &str,
+        policy: RemovePolicy,
+        changed_by: &str,
+    ) -> Result<Tool, LibraryError> {
+        let position = self
+            .tools
+            .iter()
+            .position(|tool| tool.get_uuid() == uuid)
+            .ok_or_else(|| LibraryError::NotFound(uuid.to_string()))?;
+
+        let uses = uses(machines, uuid);
+        if !uses.is_empty() {
+            if policy == RemovePolicy::Block {
+                return Err(LibraryError::InUse(uses));
+            }
+            for &at in &uses {
+                if machine_slot_mut(machines, at).locked {
+                    return Err(PlacementError::Locked(at.address).into());
+                }
+            }
+            for &at in &uses {
+                let slot = machine_slot_mut(machines, at);
+                slot.tool = None;
+                slot.touch(changed_by);
+            }
+        }
+        Ok(self.tools.remove(position))
+    }
+
+    /// Loads a copy of the library tool into a slot, returning the tool it replaced.
+    pub fn load(
+        &self,
+        machine: &mut Machine,
+        address: SlotAddress,
+        uuid: &str,
+        tool_number: Option<String>,
+        changed_by: &str,
+    ) -> Result<Option<Tool>, LibraryError> {
+        let tool = self
+            .get(uuid)
+            .ok_or_else(|| LibraryError::NotFound(uuid.to_string()))?;
+        Ok(place_tool(
+            machine,
+            address,
+            tool.clone(),
+            tool_number,
+            changed_by,
+        )?)
+    }
+}
+
+/// Every slot the tool with `uuid` is loaded into.
+pub fn uses(machines: &[Machine], uuid: &str) -> Vec<MachineSlot> {
+    slots_where(machines, |slot| {
+        slot.tool
+            .as_ref()
+            .is_some_and(|tool| tool.get_uuid() == uuid)
+    })
+}
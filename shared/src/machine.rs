@@ -1,18 +1,84 @@
 use crate::custom_traits::*;
 use crate::description::*;
 use crate::magazine::Magazine;
+use enum_iterator::Sequence;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Machine {
+    #[serde(default = "new_uuid")]
+    pub uuid: String,
     pub name: String,
     pub manufacturer: String,
     pub model: String,
     pub description: Description,
+    #[serde(default)]
+    pub capabilities: MachineCapabilities,
     pub magazine_count: usize,
     pub magazines: Vec<Magazine>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum MachineKind {
+    Mill,
+    Lathe,
+    MillTurn,
+}
+
+impl fmt::Display for MachineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineKind::Mill => write!(f, "Mill"),
+            MachineKind::Lathe => write!(f, "Lathe"),
+            MachineKind::MillTurn => write!(f, "Mill-Turn"),
+        }
+    }
+}
+
+/// What a machine can take. Every field is `None` or empty when unknown, which skips the
+/// checks that need it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MachineCapabilities {
+    pub kind: Option<MachineKind>,
+    pub spindle_max_rpm: Option<u32>,
+    /// Compared with `RotatingHolder::taper_type`, e.g. "HSK-A63"
+    pub spindle_taper: String,
+    /// Longest gauge length the tool changer takes, in mm
+    pub max_tool_length: Option<f32>,
+    /// Widest tool or holder the tool changer takes, in mm
+    pub max_tool_diameter: Option<f32>,
+    /// Heaviest tool and holder together the tool changer takes, in grams
+    pub max_tool_weight: Option<f32>,
+}
+
+fn new_uuid() -> String {
+    Uuid::new_v4().to_string()
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self {
+            uuid: new_uuid(),
+            name: String::new(),
+            manufacturer: String::new(),
+            model: String::new(),
+            description: Description::default(),
+            capabilities: MachineCapabilities::default(),
+            magazine_count: 0,
+            magazines: vec![],
+        }
+    }
+}
+
+impl GetUuid for Machine {
+    fn get_uuid(&self) -> String {
+        self.uuid.clone()
+    }
+}
+
 impl GetName for Machine {
     fn get_name(&self) -> String {
         self.name.clone()
@@ -27,10 +93,12 @@ impl Machine {
         description: Description,
     ) -> Self {
         Self {
+            uuid: new_uuid(),
             name,
             manufacturer,
             model,
             description,
+            capabilities: MachineCapabilities::default(),
             magazine_count: 0,
             magazines: vec![],
         }
@@ -1,35 +1,55 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use crate::{description::Description, holders::holder::Holder, tools::tool::Tool};
+use enum_iterator::Sequence;
+use serde::{Deserialize, Serialize};
 
-pub type Content = (
-    Option<String>,
-    Option<Tool>,
-    Option<Holder>,
-    Option<f32>,
-    Description,
-);
+use crate::slot::Slot;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct Magazine {
     pub name: String,
     pub index_in_machine: usize,
-    pub content: Vec<Content>,
+    /// Called `content` before slots had a type of their own. The alias keeps state
+    /// persisted by older builds and requests from older clients readable.
+    #[serde(alias = "content")]
+    pub slots: Vec<Slot>,
     pub capacity: usize,
+    /// `None` when unknown
+    #[serde(default)]
+    pub kind: Option<MagazineKind>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum MagazineKind {
+    Carousel,
+    Chain,
+    Rack,
+    /// Lathe turret
+    Turret,
+    ToolChangerArm,
+}
+
+impl fmt::Display for MagazineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MagazineKind::Carousel => write!(f, "Carousel"),
+            MagazineKind::Chain => write!(f, "Chain"),
+            MagazineKind::Rack => write!(f, "Rack"),
+            MagazineKind::Turret => write!(f, "Turret"),
+            MagazineKind::ToolChangerArm => write!(f, "Tool-Changer Arm"),
+        }
+    }
 }
 
 impl Magazine {
     pub fn new(index_in_machine: usize, capacity: usize) -> Self {
         let name = format!("Magazine {}", index_in_machine + 1);
-        let mut content = vec![];
-        for index in 0..capacity {
-            content.push((None, None, None, None, Description::new("...".to_string())));
-        }
         Self {
-            name: name.clone(),
+            name,
             index_in_machine,
-            content: content.clone(),
+            slots: vec![Slot::default(); capacity],
             capacity,
+            kind: None,
         }
     }
 }
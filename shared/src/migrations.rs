@@ -0,0 +1,136 @@
+use rusqlite::{params, Connection};
+use std::fmt;
+
+/// A numbered schema change. Versions start at 1 and must be strictly increasing.
+/// `up` runs inside a transaction that is rolled back if it fails.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The migration list itself is broken, e.g. two migrations share a version.
+    InvalidOrder {
+        version: u32,
+    },
+    /// The database was migrated by a newer build than this one.
+    DatabaseTooNew {
+        found: u32,
+        latest: u32,
+    },
+    Failed {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::InvalidOrder { version } => {
+                write!(f, "Migration {} is out of order", version)
+            }
+            MigrationError::DatabaseTooNew { found, latest } => write!(
+                f,
+                "Database schema version {} is newer than the latest known version {}",
+                found, latest
+            ),
+            MigrationError::Failed {
+                version,
+                description,
+                source,
+            } => write!(
+                f,
+                "Migration {} ({}) failed: {}",
+                version, description, source
+            ),
+            MigrationError::Sqlite(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MigrationError::Failed { source, .. } => Some(source),
+            MigrationError::Sqlite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(e: rusqlite::Error) -> Self {
+        MigrationError::Sqlite(e)
+    }
+}
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// Applies every migration newer than the database's schema version, each in its own
+/// transaction. Stops at the first failure, leaving the database at the last good version.
+/// Returns the resulting schema version.
+pub fn run_migrations(
+    conn: &mut Connection,
+    migrations: &[Migration],
+) -> Result<u32, MigrationError> {
+    let mut previous = 0;
+    for migration in migrations {
+        if migration.version <= previous {
+            return Err(MigrationError::InvalidOrder {
+                version: migration.version,
+            });
+        }
+        previous = migration.version;
+    }
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+    )?;
+
+    let mut current = schema_version(conn)?;
+    let pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > current).collect();
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if current > latest {
+        return Err(MigrationError::DatabaseTooNew {
+            found: current,
+            latest,
+        });
+    }
+
+    for migration in pending {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx).map_err(|source| MigrationError::Failed {
+            version: migration.version,
+            description: migration.description,
+            source,
+        })?;
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at)
+             VALUES (?1, ?2, datetime('now'))",
+            params![migration.version, migration.description],
+        )?;
+        tx.commit()?;
+        log::info!(
+            "Applied migration {} ({})",
+            migration.version,
+            migration.description
+        );
+        current = migration.version;
+    }
+    Ok(current)
+}
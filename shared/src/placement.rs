@@ -0,0 +1,143 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{holders::holder::Holder, machine::Machine, slot::Slot, tools::tool::Tool};
+
+/// Position of a slot within a machine, both indices zero based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub struct SlotAddress {
+    pub magazine: usize,
+    pub slot: usize,
+}
+
+impl SlotAddress {
+    pub fn new(magazine: usize, slot: usize) -> Self {
+        Self { magazine, slot }
+    }
+}
+
+impl fmt::Display for SlotAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "magazine {}, slot {}", self.magazine + 1, self.slot + 1)
+    }
+}
+
+/// A slot in one of the user's machines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MachineSlot {
+    pub machine: usize,
+    pub address: SlotAddress,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementError {
+    NoSuchMachine(usize),
+    NoSuchMagazine(usize),
+    NoSuchSlot(SlotAddress),
+    Locked(SlotAddress),
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::NoSuchMachine(index) => {
+                write!(f, "Machine {} does not exist", index + 1)
+            }
+            PlacementError::NoSuchMagazine(index) => {
+                write!(f, "Magazine {} does not exist", index + 1)
+            }
+            PlacementError::NoSuchSlot(address) => write!(f, "There is no {}", address),
+            PlacementError::Locked(address) => write!(f, "{} is reserved", address),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+pub fn slot(machine: &Machine, address: SlotAddress) -> Result<&Slot, PlacementError> {
+    machine
+        .magazines
+        .get(address.magazine)
+        .ok_or(PlacementError::NoSuchMagazine(address.magazine))?
+        .slots
+        .get(address.slot)
+        .ok_or(PlacementError::NoSuchSlot(address))
+}
+
+pub fn slot_mut(machine: &mut Machine, address: SlotAddress) -> Result<&mut Slot, PlacementError> {
+    machine
+        .magazines
+        .get_mut(address.magazine)
+        .ok_or(PlacementError::NoSuchMagazine(address.magazine))?
+        .slots
+        .get_mut(address.slot)
+        .ok_or(PlacementError::NoSuchSlot(address))
+}
+
+/// Every slot of every machine that `predicate` accepts, in machine, magazine and slot order.
+pub fn slots_where(machines: &[Machine], predicate: impl Fn(&Slot) -> bool) -> Vec<MachineSlot> {
+    let mut found = vec![];
+    for (machine_index, machine) in machines.iter().enumerate() {
+        for (magazine_index, magazine) in machine.magazines.iter().enumerate() {
+            for (slot_index, slot) in magazine.slots.iter().enumerate() {
+                if predicate(slot) {
+                    found.push(MachineSlot {
+                        machine: machine_index,
+                        address: SlotAddress::new(magazine_index, slot_index),
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// The slot `at` points to. Panics if it is out of bounds, as locations from
+/// [`slots_where`] on the same machines never are.
+pub fn machine_slot_mut(machines: &mut [Machine], at: MachineSlot) -> &mut Slot {
+    &mut machines[at.machine].magazines[at.address.magazine].slots[at.address.slot]
+}
+
+/// The slot at `address`, unless it is reserved.
+pub fn editable_slot_mut(
+    machine: &mut Machine,
+    address: SlotAddress,
+) -> Result<&mut Slot, PlacementError> {
+    let slot = slot_mut(machine, address)?;
+    if slot.locked {
+        return Err(PlacementError::Locked(address));
+    }
+    Ok(slot)
+}
+
+/// Puts `tool` into the slot, returning the tool it replaced.
+/// The tool number is only changed when one is given.
+pub fn place_tool(
+    machine: &mut Machine,
+    address: SlotAddress,
+    tool: Tool,
+    tool_number: Option<String>,
+    changed_by: &str,
+) -> Result<Option<Tool>, PlacementError> {
+    let slot = editable_slot_mut(machine, address)?;
+    let replaced = slot.tool.replace(tool);
+    if tool_number.is_some() {
+        slot.tool_number = tool_number;
+    }
+    slot.touch(changed_by);
+    Ok(replaced)
+}
+
+/// Puts `holder` into the slot, returning the holder it replaced.
+pub fn place_holder(
+    machine: &mut Machine,
+    address: SlotAddress,
+    holder: Holder,
+    changed_by: &str,
+) -> Result<Option<Holder>, PlacementError> {
+    let slot = editable_slot_mut(machine, address)?;
+    let replaced = slot.holder.replace(holder);
+    slot.touch(changed_by);
+    Ok(replaced)
+}
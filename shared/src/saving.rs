@@ -0,0 +1,110 @@
+//! When to save [`UserData`](crate::UserData), and whether the last save went through.
+//!
+//! Every edit marks the data dirty. A save starts once no edit came for [`SAVE_DEBOUNCE`]
+//! seconds, so a burst of edits is saved once. A failed save is tried again after a delay
+//! that doubles with each failure, up to [`MAX_RETRY_DELAY`]. Times are in seconds on any
+//! clock that only moves forward, e.g. egui's input time.
+
+/// Seconds without edits before the data is saved.
+pub const SAVE_DEBOUNCE: f64 = 1.0;
+/// Seconds before the first retry of a failed save.
+pub const FIRST_RETRY_DELAY: f64 = 2.0;
+pub const MAX_RETRY_DELAY: f64 = 60.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveStatus {
+    Saved,
+    /// Edited, waiting for the edits to settle
+    Pending,
+    Saving,
+    Failed {
+        error: String,
+        retry_at: f64,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SaveTracker {
+    /// Edits made so far
+    edits: u64,
+    /// Edits the store holds
+    saved: u64,
+    /// Edits the save on its way holds
+    saving: Option<u64>,
+    last_edit_at: Option<f64>,
+    failures: u32,
+    retry_at: Option<f64>,
+    error: Option<String>,
+}
+
+impl SaveTracker {
+    pub fn edited(&mut self, now: f64) {
+        self.edits += 1;
+        self.last_edit_at = Some(now);
+    }
+
+    /// Whether there are edits the store does not hold yet, including those being saved.
+    pub fn is_dirty(&self) -> bool {
+        self.saved < self.edits
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.saving.is_some()
+    }
+
+    /// When the next save should start, `None` while nothing waits to be saved.
+    pub fn due_at(&self) -> Option<f64> {
+        if !self.is_dirty() || self.is_saving() {
+            return None;
+        }
+        let settled = self.last_edit_at.map_or(0.0, |at| at + SAVE_DEBOUNCE);
+        Some(
+            self.retry_at
+                .map_or(settled, |retry_at| retry_at.max(settled)),
+        )
+    }
+
+    pub fn is_due(&self, now: f64) -> bool {
+        self.due_at().is_some_and(|due_at| now >= due_at)
+    }
+
+    /// Call when a save of everything edited so far starts.
+    pub fn started(&mut self) {
+        self.saving = Some(self.edits);
+    }
+
+    pub fn succeeded(&mut self) {
+        if let Some(saving) = self.saving.take() {
+            self.saved = saving;
+        }
+        self.failures = 0;
+        self.retry_at = None;
+        self.error = None;
+    }
+
+    pub fn failed(&mut self, now: f64, error: String) {
+        self.saving = None;
+        self.failures += 1;
+        let delay = FIRST_RETRY_DELAY * 2f64.powi(self.failures as i32 - 1);
+        self.retry_at = Some(now + delay.min(MAX_RETRY_DELAY));
+        self.error = Some(error);
+    }
+
+    /// Saves at once instead of waiting for the edits to settle or for the next retry.
+    pub fn save_now(&mut self) {
+        self.last_edit_at = None;
+        self.retry_at = None;
+    }
+
+    pub fn status(&self) -> SaveStatus {
+        match (&self.error, self.retry_at) {
+            _ if self.is_saving() => SaveStatus::Saving,
+            (Some(error), Some(retry_at)) => SaveStatus::Failed {
+                error: error.clone(),
+                retry_at,
+            },
+            _ if self.is_dirty() => SaveStatus::Pending,
+            _ => SaveStatus::Saved,
+        }
+    }
+}
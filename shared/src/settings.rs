@@ -1,7 +1,7 @@
 use egui::Color32;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 
 pub struct Settings {
     pub dark_mode: bool,
@@ -9,4 +9,7 @@ pub struct Settings {
     pub color1: Color32,
     pub color2: Color32,
     pub color3: Color32,
+    /// Refuse to put a tool and a holder together when the compatibility check finds errors
+    #[serde(default)]
+    pub block_incompatible_assemblies: bool,
 }
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{description::Description, holders::holder::Holder, tools::tool::Tool};
+
+const MM_PER_INCH: f32 = 25.4;
+
+/// Text that older builds put in the description of every empty slot.
+const PLACEHOLDER_DESCRIPTION: &str = "...";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    #[default]
+    Millimeter,
+    Inch,
+}
+
+impl fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LengthUnit::Millimeter => write!(f, "mm"),
+            LengthUnit::Inch => write!(f, "in"),
+        }
+    }
+}
+
+/// How far the tool sticks out of the holder.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Overhang {
+    pub value: f32,
+    pub unit: LengthUnit,
+}
+
+impl Overhang {
+    pub fn millimeters(value: f32) -> Self {
+        Self {
+            value,
+            unit: LengthUnit::Millimeter,
+        }
+    }
+
+    pub fn to_millimeters(&self) -> f32 {
+        match self.unit {
+            LengthUnit::Millimeter => self.value,
+            LengthUnit::Inch => self.value * MM_PER_INCH,
+        }
+    }
+}
+
+impl fmt::Display for Overhang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.unit)
+    }
+}
+
+/// One pocket of a magazine.
+/// Also deserializes from the `(tool number, tool, holder, overhang, description)` tuple
+/// that slots used to be stored as.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(from = "SlotRepr")]
+pub struct Slot {
+    pub tool_number: Option<String>,
+    pub tool: Option<Tool>,
+    pub holder: Option<Holder>,
+    pub overhang: Option<Overhang>,
+    pub description: Option<Description>,
+    /// Reserved slot that should be left alone until it is unlocked.
+    pub locked: bool,
+    pub last_changed_at: Option<DateTime<Utc>>,
+    pub last_changed_by: Option<String>,
+}
+
+impl Slot {
+    /// Whether the slot holds nothing the user can see, so something can go in without
+    /// replacing anything. Whether it is reserved is not part of this.
+    pub fn is_empty(&self) -> bool {
+        self.tool_number.is_none()
+            && self.tool.is_none()
+            && self.holder.is_none()
+            && self.overhang.is_none()
+            && self.description.is_none()
+    }
+
+    /// Records who changed the slot and when.
+    pub fn touch(&mut self, changed_by: &str) {
+        self.last_changed_at = Some(Utc::now());
+        self.last_changed_by = Some(changed_by.to_string());
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SlotRepr {
+    // Tried first, a struct would also accept a five element array
+    Legacy(
+        Option<String>,
+        Option<Tool>,
+        Option<Holder>,
+        Option<f32>,
+        Description,
+    ),
+    Current(SlotFields),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SlotFields {
+    tool_number: Option<String>,
+    tool: Option<Tool>,
+    holder: Option<Holder>,
+    overhang: Option<Overhang>,
+    description: Option<Description>,
+    locked: bool,
+    last_changed_at: Option<DateTime<Utc>>,
+    last_changed_by: Option<String>,
+}
+
+impl From<SlotRepr> for Slot {
+    fn from(repr: SlotRepr) -> Self {
+        match repr {
+            SlotRepr::Legacy(tool_number, tool, holder, overhang, description) => Self {
+                tool_number,
+                tool,
+                holder,
+                overhang: overhang.map(Overhang::millimeters),
+                description: (!description.text.is_empty()
+                    && description.text != PLACEHOLDER_DESCRIPTION)
+                    .then_some(description),
+                ..Self::default()
+            },
+            SlotRepr::Current(fields) => Self {
+                tool_number: fields.tool_number,
+                tool: fields.tool,
+                holder: fields.holder,
+                overhang: fields.overhang,
+                description: fields.description,
+                locked: fields.locked,
+                last_changed_at: fields.last_changed_at,
+                last_changed_by: fields.last_changed_by,
+            },
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use enum_iterator::Sequence;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_traits::{GetDegree, GetDiameter},
+    holders::holder::Holder,
+    magazine::Magazine,
+    slot::Slot,
+    tools::tool::Tool,
+};
+
+/// A numeric attribute of a slot's tool or holder that slots can be ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Sequence, Default)]
+pub enum SortField {
+    /// Physical slot order.
+    #[default]
+    Index,
+    ToolDiameter,
+    ToolAngle,
+    ToolLength,
+    ToolMaxRpm,
+    ToolWeight,
+    HolderDiameter,
+    HolderAngle,
+    HolderLength,
+    HolderMaxRpm,
+    Overhang,
+}
+
+impl fmt::Display for SortField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortField::Index => write!(f, "Slot"),
+            SortField::ToolDiameter => write!(f, "Tool Diameter"),
+            SortField::ToolAngle => write!(f, "Tool Angle"),
+            SortField::ToolLength => write!(f, "Tool Length"),
+            SortField::ToolMaxRpm => write!(f, "Tool Max RPM"),
+            SortField::ToolWeight => write!(f, "Tool Weight"),
+            SortField::HolderDiameter => write!(f, "Holder Diameter"),
+            SortField::HolderAngle => write!(f, "Holder Angle"),
+            SortField::HolderLength => write!(f, "Holder Length"),
+            SortField::HolderMaxRpm => write!(f, "Holder Max RPM"),
+            SortField::Overhang => write!(f, "Overhang"),
+        }
+    }
+}
+
+impl SortField {
+    /// The value slot `index` is sorted by, `None` if the slot has nothing to compare.
+    pub fn value(&self, index: usize, slot: &Slot) -> Option<f32> {
+        let tool = slot.tool.as_ref();
+        let holder = slot.holder.as_ref();
+        match self {
+            SortField::Index => Some(index as f32),
+            SortField::ToolDiameter => tool.and_then(GetDiameter::get_diameter),
+            SortField::ToolAngle => tool.and_then(GetDegree::get_degree),
+            SortField::ToolLength => tool.map(|tool| match tool {
+                Tool::Rotating(tool) => tool.usable_length,
+                Tool::Turning(tool) => tool.usable_length,
+            }),
+            SortField::ToolMaxRpm => tool.map(|tool| match tool {
+                Tool::Rotating(tool) => tool.max_rpm as f32,
+                Tool::Turning(tool) => tool.maximum_rpm as f32,
+            }),
+            SortField::ToolWeight => tool.map(|tool| match tool {
+                Tool::Rotating(tool) => tool.weight_of_tool,
+                Tool::Turning(tool) => tool.tool_weight,
+            }),
+            SortField::HolderDiameter => holder.and_then(GetDiameter::get_diameter),
+            SortField::HolderAngle => holder.and_then(GetDegree::get_degree),
+            SortField::HolderLength => holder.map(|holder| match holder {
+                Holder::Rotating(holder) => holder.length,
+                Holder::Turning(holder) => holder.overall_length,
+            }),
+            SortField::HolderMaxRpm => holder.and_then(|holder| match holder {
+                Holder::Rotating(holder) => Some(holder.max_rpm as f32),
+                Holder::Turning(_) => None,
+            }),
+            SortField::Overhang => slot.overhang.map(|overhang| overhang.to_millimeters()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortDirection::Ascending => write!(f, "Ascending"),
+            SortDirection::Descending => write!(f, "Descending"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct SlotSort {
+    pub field: SortField,
+    pub direction: SortDirection,
+}
+
+impl SlotSort {
+    /// Slot indices of `magazine` in display order. The slots themselves are not moved.
+    /// Slots without a value for the field come last in either direction, and equal
+    /// values keep their slot order.
+    pub fn sorted_indices(&self, magazine: &Magazine) -> Vec<usize> {
+        let mut keyed: Vec<(usize, Option<f32>)> = magazine
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| (index, self.field.value(index, slot)))
+            .collect();
+
+        keyed.sort_by(|(a_index, a), (b_index, b)| {
+            let by_value = match (a, b) {
+                (Some(a), Some(b)) => match self.direction {
+                    SortDirection::Ascending => a.total_cmp(b),
+                    SortDirection::Descending => b.total_cmp(a),
+                },
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            by_value.then(a_index.cmp(b_index))
+        });
+
+        keyed.into_iter().map(|(index, _)| index).collect()
+    }
+}
@@ -0,0 +1,674 @@
+//! Magazine contents as CSV, for the tool lists that are kept in spreadsheets.
+//!
+//! Every row is one slot. Tool and holder fields get a column each, named after the field,
+//! e.g. `tool.cutting_diameter` or `holder.taper_type`, so an exported sheet reads back in
+//! as it is. Sheets from elsewhere are read by mapping their columns onto these.
+//!
+//! Importing is done in two steps: [`plan_import`] works out what would change and which
+//! rows are wrong without touching anything, [`apply_import`] then makes the changes.
+
+use std::{collections::HashMap, fmt};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{
+    custom_traits::GetUuid,
+    description::Description,
+    holders::holder::{Holder, RotatingHolder, TurningHolder},
+    inventory::{HolderInventory, Shortage},
+    library::ToolLibrary,
+    machine::Machine,
+    placement::{self, PlacementError, SlotAddress},
+    slot::{LengthUnit, Overhang, Slot},
+    tools::tool::{RotatingTool, Tool, TurningTool},
+};
+
+/// Separates the two ends of a range in a cell, e.g. `6..12`.
+const RANGE_SEPARATOR: &str = "..";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// Magazine number, counted from 1
+    Magazine,
+    /// Slot number, counted from 1
+    Slot,
+    ToolNumber,
+    /// `Rotating` or `Turning`, empty for no tool
+    ToolType,
+    Tool(String),
+    /// `Rotating` or `Turning`, empty for no holder
+    HolderType,
+    Holder(String),
+    /// In the unit of [`Column::OverhangUnit`], millimeters when there is none
+    Overhang,
+    OverhangUnit,
+    Description,
+    /// Not imported
+    Ignore,
+}
+
+impl Column {
+    /// Every column an export writes, in order.
+    pub fn all() -> Vec<Column> {
+        let mut columns = vec![
+            Column::Magazine,
+            Column::Slot,
+            Column::ToolNumber,
+            Column::ToolType,
+        ];
+        columns.extend(tool_fields().into_iter().map(Column::Tool));
+        columns.push(Column::HolderType);
+        columns.extend(holder_fields().into_iter().map(Column::Holder));
+        columns.extend([Column::Overhang, Column::OverhangUnit, Column::Description]);
+        columns
+    }
+
+    /// The column a header stands for, ignoring case and surrounding space.
+    pub fn from_header(header: &str) -> Column {
+        let header = header.trim().to_lowercase();
+        Column::all()
+            .into_iter()
+            .find(|column| column.header() == header)
+            .unwrap_or(Column::Ignore)
+    }
+
+    pub fn header(&self) -> String {
+        match self {
+            Column::Magazine => "magazine".to_string(),
+            Column::Slot => "slot".to_string(),
+            Column::ToolNumber => "tool_number".to_string(),
+            Column::ToolType => "tool_type".to_string(),
+            Column::Tool(field) => format!("tool.{}", field),
+            Column::HolderType => "holder_type".to_string(),
+            Column::Holder(field) => format!("holder.{}", field),
+            Column::Overhang => "overhang".to_string(),
+            Column::OverhangUnit => "overhang_unit".to_string(),
+            Column::Description => "description".to_string(),
+            Column::Ignore => String::new(),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::Ignore => write!(f, "(ignore)"),
+            column => write!(f, "{}", column.header()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpreadsheetError {
+    Csv(String),
+    /// The text has no header row.
+    Empty,
+    /// No column is mapped to the slot number.
+    NoSlotColumn,
+    NoSuchMagazine(usize),
+}
+
+impl fmt::Display for SpreadsheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpreadsheetError::Csv(e) => write!(f, "Not a readable CSV file: {}", e),
+            SpreadsheetError::Empty => write!(f, "The file is empty"),
+            SpreadsheetError::NoSlotColumn => write!(f, "No column is mapped to the slot"),
+            SpreadsheetError::NoSuchMagazine(index) => {
+                write!(f, "Magazine {} does not exist", index + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpreadsheetError {}
+
+impl From<csv::Error> for SpreadsheetError {
+    fn from(e: csv::Error) -> Self {
+        SpreadsheetError::Csv(e.to_string())
+    }
+}
+
+/// A row that cannot be imported. `line` counts from 1, like a spreadsheet does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+/// A CSV file as read, before its columns are mapped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub headers: Vec<String>,
+    rows: Vec<(u64, Vec<String>)>,
+}
+
+impl Sheet {
+    /// Maps every column by its header, see [`Column::from_header`].
+    pub fn guess_mapping(&self) -> Vec<Column> {
+        self.headers
+            .iter()
+            .map(|header| Column::from_header(header))
+            .collect()
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+/// What importing a row would do to its slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotChange {
+    pub line: u64,
+    pub address: SlotAddress,
+    pub before: Slot,
+    pub after: Slot,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportPlan {
+    pub changes: Vec<SlotChange>,
+    pub errors: Vec<RowError>,
+    /// Rows that match their slot already
+    pub unchanged: usize,
+}
+
+/// Every slot of one magazine, empty ones included.
+pub fn export_magazine(machine: &Machine, index: usize) -> Result<String, SpreadsheetError> {
+    let magazine = machine
+        .magazines
+        .get(index)
+        .ok_or(SpreadsheetError::NoSuchMagazine(index))?;
+    write(
+        magazine
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(position, slot)| (SlotAddress::new(index, position), slot)),
+    )
+}
+
+/// Every slot of every magazine of the machine, empty ones included.
+pub fn export_machine(machine: &Machine) -> Result<String, SpreadsheetError> {
+    write(
+        machine
+            .magazines
+            .iter()
+            .enumerate()
+            .flat_map(|(index, magazine)| {
+                magazine
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .map(move |(position, slot)| (SlotAddress::new(index, position), slot))
+            }),
+    )
+}
+
+fn write<'a>(
+    slots: impl Iterator<Item = (SlotAddress, &'a Slot)>,
+) -> Result<String, SpreadsheetError> {
+    let columns = Column::all();
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(columns.iter().map(Column::header))?;
+    for (address, slot) in slots {
+        let tool = slot.tool.as_ref().map(variant_fields);
+        let holder = slot.holder.as_ref().map(variant_fields);
+        let record = columns.iter().map(|column| match column {
+            Column::Magazine => (address.magazine + 1).to_string(),
+            Column::Slot => (address.slot + 1).to_string(),
+            Column::ToolNumber => slot.tool_number.clone().unwrap_or_default(),
+            Column::ToolType => tool
+                .as_ref()
+                .map(|(kind, _)| kind.clone())
+                .unwrap_or_default(),
+            Column::Tool(field) => field_cell(&tool, field),
+            Column::HolderType => holder
+                .as_ref()
+                .map(|(kind, _)| kind.clone())
+                .unwrap_or_default(),
+            Column::Holder(field) => field_cell(&holder, field),
+            Column::Overhang => slot
+                .overhang
+                .map(|overhang| overhang.value.to_string())
+                .unwrap_or_default(),
+            Column::OverhangUnit => slot
+                .overhang
+                .map(|overhang| overhang.unit.to_string())
+                .unwrap_or_default(),
+            Column::Description => slot
+                .description
+                .as_ref()
+                .map(|description| description.text.clone())
+                .unwrap_or_default(),
+            Column::Ignore => String::new(),
+        });
+        writer.write_record(record)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| SpreadsheetError::Csv(e.to_string()))?;
+    Ok(String::from_utf8(bytes).expect("CSV of strings is UTF-8"))
+}
+
+/// Reads CSV text with a header row. Spreadsheets set to a locale with a decimal comma
+/// separate with semicolons, so those are used when the header has more of them.
+pub fn read(text: &str) -> Result<Sheet, SpreadsheetError> {
+    let header_line = text.lines().next().ok_or(SpreadsheetError::Empty)?;
+    let delimiter = if header_line.matches(';').count() > header_line.matches(',').count() {
+        b';'
+    } else {
+        b','
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()?.iter().map(str::to_string).collect();
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+        rows.push((line, record.iter().map(str::to_string).collect()));
+    }
+    Ok(Sheet { headers, rows })
+}
+
+/// Works out what importing the sheet into the machine would change, without changing
+/// anything. `mapping` has a column for every column of the sheet. Rows without a magazine
+/// go into `default_magazine`.
+///
+/// A tool whose `tool.uuid` is in the library has to match the library entry, and a holder
+/// whose `holder.uuid` is in the inventory has to match the inventory entry. Leaving the
+/// uuid empty imports a new tool or holder.
+pub fn plan_import(
+    sheet: &Sheet,
+    mapping: &[Column],
+    machine: &Machine,
+    library: &ToolLibrary,
+    inventory: &HolderInventory,
+    default_magazine: usize,
+) -> Result<ImportPlan, SpreadsheetError> {
+    if !mapping.contains(&Column::Slot) {
+        return Err(SpreadsheetError::NoSlotColumn);
+    }
+    let mut plan = ImportPlan::default();
+    let mut taken: HashMap<SlotAddress, u64> = HashMap::new();
+    for (line, cells) in &sheet.rows {
+        let cells: Vec<(&Column, &str)> = mapping
+            .iter()
+            .zip(cells.iter().map(|cell| cell.trim()))
+            .filter(|(column, _)| **column != Column::Ignore)
+            .collect();
+        if cells.iter().all(|(_, cell)| cell.is_empty()) {
+            continue;
+        }
+        let row = Row { cells: &cells };
+        let result = row
+            .address(default_magazine)
+            .and_then(|address| {
+                let before = placement::slot(machine, address).map_err(|e| e.to_string())?;
+                if before.locked {
+                    return Err(PlacementError::Locked(address).to_string());
+                }
+                if let Some(first) = taken.insert(address, *line) {
+                    return Err(format!("{} is already set by line {}", address, first));
+                }
+                Ok((address, before))
+            })
+            .and_then(|(address, before)| Ok((address, before, row.contents(library, inventory)?)));
+        match result {
+            Ok((address, before, contents)) => {
+                if same_contents(before, &contents) {
+                    plan.unchanged += 1;
+                    continue;
+                }
+                plan.changes.push(SlotChange {
+                    line: *line,
+                    address,
+                    before: before.clone(),
+                    after: Slot {
+                        locked: before.locked,
+                        last_changed_at: before.last_changed_at,
+                        last_changed_by: before.last_changed_by.clone(),
+                        ..contents
+                    },
+                });
+            }
+            Err(message) => plan.errors.push(RowError {
+                line: *line,
+                message,
+            }),
+        }
+    }
+    Ok(plan)
+}
+
+/// Makes the planned changes. Tools and holders that are new to the library and the
+/// inventory are added to them. Returns the holders the import uses more copies of than the
+/// inventory owns. Nothing changes when a slot was reserved since planning.
+pub fn apply_import(
+    plan: &ImportPlan,
+    machine: &mut Machine,
+    library: &mut ToolLibrary,
+    inventory: &mut HolderInventory,
+    changed_by: &str,
+) -> Result<Vec<Shortage>, PlacementError> {
+    for change in &plan.changes {
+        placement::editable_slot_mut(machine, change.address)?;
+    }
+    for change in &plan.changes {
+        let slot = placement::slot_mut(machine, change.address)?;
+        *slot = Slot {
+            locked: slot.locked,
+            ..change.after.clone()
+        };
+        slot.touch(changed_by);
+    }
+    let machines = std::slice::from_mut(machine);
+    library.adopt_loaded(machines);
+    Ok(inventory.adopt_loaded(machines))
+}
+
+fn same_contents(slot: &Slot, contents: &Slot) -> bool {
+    slot.tool_number == contents.tool_number
+        && slot.tool == contents.tool
+        && slot.holder == contents.holder
+        && slot.overhang == contents.overhang
+        && slot.description == contents.description
+}
+
+/// The mapped, non-ignored cells of one row.
+struct Row<'a> {
+    cells: &'a [(&'a Column, &'a str)],
+}
+
+impl Row<'_> {
+    fn cell(&self, column: &Column) -> &str {
+        self.cells
+            .iter()
+            .find(|(mapped, _)| *mapped == column)
+            .map_or("", |(_, cell)| cell)
+    }
+
+    fn address(&self, default_magazine: usize) -> Result<SlotAddress, String> {
+        let magazine = match self.cell(&Column::Magazine) {
+            "" => default_magazine,
+            cell => number(cell, "magazine")?,
+        };
+        let slot = number(self.cell(&Column::Slot), "slot")?;
+        Ok(SlotAddress::new(magazine, slot))
+    }
+
+    /// The slot the row describes, without reservation or change stamps.
+    fn contents(&self, library: &ToolLibrary, inventory: &HolderInventory) -> Result<Slot, String> {
+        let tool_cells = self.fields(|column| match column {
+            Column::Tool(field) => Some(field),
+            _ => None,
+        });
+        let tool = build::<Tool>(
+            "tool",
+            self.cell(&Column::ToolType),
+            &tool_cells,
+            |uuid| library.get(uuid).map(variant_fields),
+            |kind| match kind {
+                "Rotating" => Some(fields_of(&RotatingTool::default())),
+                "Turning" => Some(fields_of(&TurningTool::default())),
+                _ => None,
+            },
+        )?;
+        if let Some(tool) = &tool {
+            if library
+                .get(&tool.get_uuid())
+                .is_some_and(|entry| entry != tool)
+            {
+                return Err(
+                    "the tool differs from its library entry, change it there or clear \
+                     tool.uuid to import it as a new tool"
+                        .to_string(),
+                );
+            }
+        }
+
+        let holder_cells = self.fields(|column| match column {
+            Column::Holder(field) => Some(field),
+            _ => None,
+        });
+        let holder = build::<Holder>(
+            "holder",
+            self.cell(&Column::HolderType),
+            &holder_cells,
+            |uuid| inventory.get(uuid).map(variant_fields),
+            |kind| match kind {
+                "Rotating" => Some(fields_of(&RotatingHolder::default())),
+                "Turning" => Some(fields_of(&TurningHolder::default())),
+                _ => None,
+            },
+        )?;
+        if let Some(holder) = &holder {
+            if inventory
+                .get(&holder.get_uuid())
+                .is_some_and(|entry| entry != holder)
+            {
+                return Err(
+                    "the holder differs from its inventory entry, change it there or clear \
+                     holder.uuid to import it as a new holder"
+                        .to_string(),
+                );
+            }
+        }
+
+        let overhang = match self.cell(&Column::Overhang) {
+            "" => None,
+            cell => {
+                let value = decimal(cell).map_err(|e| format!("overhang {}", e))?;
+                let unit = match self.cell(&Column::OverhangUnit).to_lowercase().as_str() {
+                    "" | "mm" => LengthUnit::Millimeter,
+                    "in" | "inch" | "\"" => LengthUnit::Inch,
+                    unit => return Err(format!("'{}' is not mm or in", unit)),
+                };
+                Some(Overhang {
+                    value: value as f32,
+                    unit,
+                })
+            }
+        };
+
+        let text = |column| Some(self.cell(&column).to_string()).filter(|text| !text.is_empty());
+        Ok(Slot {
+            tool_number: text(Column::ToolNumber),
+            tool,
+            holder,
+            overhang,
+            description: text(Column::Description).map(Description::new),
+            ..Slot::default()
+        })
+    }
+
+    /// The cells of the columns `field` picks, by field name.
+    fn fields(&self, field: impl Fn(&Column) -> Option<&String>) -> Vec<(&str, &str)> {
+        self.cells
+            .iter()
+            .filter_map(|(column, cell)| field(column).map(|name| (name.as_str(), *cell)))
+            .collect()
+    }
+}
+
+/// A tool or holder from its type cell and field cells. A uuid that `known` finds starts
+/// from that entry, anything else from the defaults `new` gives for the type. Returns `None`
+/// when the type cell is empty.
+fn build<T: DeserializeOwned>(
+    what: &str,
+    kind: &str,
+    cells: &[(&str, &str)],
+    known: impl Fn(&str) -> Option<(String, Map<String, Value>)>,
+    new: impl Fn(&str) -> Option<Map<String, Value>>,
+) -> Result<Option<T>, String> {
+    if kind.is_empty() {
+        return match cells.iter().find(|(_, cell)| !cell.is_empty()) {
+            Some((field, _)) => Err(format!(
+                "{}.{} is filled in but {}_type is empty",
+                what, field, what
+            )),
+            None => Ok(None),
+        };
+    }
+    let kind = match kind.to_lowercase().as_str() {
+        "rotating" => "Rotating",
+        "turning" => "Turning",
+        _ => return Err(format!("'{}' is not Rotating or Turning", kind)),
+    };
+    let uuid = cells
+        .iter()
+        .find(|(field, _)| *field == "uuid")
+        .map_or("", |(_, cell)| cell);
+    let mut fields = match known(uuid) {
+        Some((known_kind, _)) if known_kind != kind => {
+            return Err(format!("{} {} is not a {} {}", what, uuid, kind, what));
+        }
+        Some((_, fields)) => fields,
+        None => new(kind).expect("kind is Rotating or Turning"),
+    };
+    for (field, cell) in cells {
+        if cell.is_empty() {
+            continue;
+        }
+        let template = fields
+            .get(*field)
+            .ok_or_else(|| format!("{} {}s have no {}", kind, what, field))?;
+        let value = parse_cell(template, cell).map_err(|e| format!("{}.{} {}", what, field, e))?;
+        fields.insert(field.to_string(), value);
+    }
+    let mut variant = Map::new();
+    variant.insert(kind.to_string(), Value::Object(fields));
+    serde_json::from_value(Value::Object(variant))
+        .map(Some)
+        .map_err(|e| format!("{}: {}", what, e))
+}
+
+/// The variant name and fields of a tool or holder.
+fn variant_fields<T: serde::Serialize>(value: &T) -> (String, Map<String, Value>) {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(variant)) => {
+            let (kind, fields) = variant.into_iter().next().expect("one variant");
+            match fields {
+                Value::Object(fields) => (kind, fields),
+                _ => unreachable!("tools and holders are structs"),
+            }
+        }
+        _ => unreachable!("tools and holders are enums"),
+    }
+}
+
+fn fields_of<T: serde::Serialize>(value: &T) -> Map<String, Value> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(fields)) => fields,
+        _ => unreachable!("tools and holders are structs"),
+    }
+}
+
+/// Field names of both kinds, each once.
+fn union(kinds: [Map<String, Value>; 2]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for fields in kinds {
+        for name in fields.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    names
+}
+
+fn tool_fields() -> Vec<String> {
+    union([
+        fields_of(&RotatingTool::default()),
+        fields_of(&TurningTool::default()),
+    ])
+}
+
+/// Without the number of copies, which the inventory keeps.
+fn holder_fields() -> Vec<String> {
+    let mut fields = union([
+        fields_of(&RotatingHolder::default()),
+        fields_of(&TurningHolder::default()),
+    ]);
+    fields.retain(|field| field != "duplicates");
+    fields
+}
+
+fn field_cell(part: &Option<(String, Map<String, Value>)>, field: &str) -> String {
+    part.as_ref()
+        .and_then(|(_, fields)| fields.get(field))
+        .map(format_cell)
+        .unwrap_or_default()
+}
+
+fn format_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(format_cell)
+            .collect::<Vec<_>>()
+            .join(RANGE_SEPARATOR),
+        value => value.to_string(),
+    }
+}
+
+/// Parses a cell into the JSON type of `template`, the field's current value.
+fn parse_cell(template: &Value, cell: &str) -> Result<Value, String> {
+    match template {
+        Value::Number(number) if number.is_u64() => cell
+            .parse::<u64>()
+            .map(Value::from)
+            .map_err(|_| format!("'{}' is not a whole number", cell)),
+        Value::Number(_) => decimal(cell).map(Value::from),
+        Value::Bool(_) => match cell.to_lowercase().as_str() {
+            "true" | "yes" | "1" | "x" => Ok(Value::Bool(true)),
+            "false" | "no" | "0" => Ok(Value::Bool(false)),
+            _ => Err(format!("'{}' is not yes or no", cell)),
+        },
+        Value::Array(items) => {
+            let parts: Vec<&str> = cell.split(RANGE_SEPARATOR).map(str::trim).collect();
+            if parts.len() != items.len() {
+                return Err(format!(
+                    "'{}' is not a range like 6{}12",
+                    cell, RANGE_SEPARATOR
+                ));
+            }
+            items
+                .iter()
+                .zip(parts)
+                .map(|(item, part)| parse_cell(item, part))
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        }
+        _ => Ok(Value::String(cell.to_string())),
+    }
+}
+
+/// A decimal number, with a point or a comma.
+fn decimal(cell: &str) -> Result<f64, String> {
+    cell.replace(',', ".")
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", cell))
+}
+
+/// A number counted from 1, returned counted from 0.
+fn number(cell: &str, what: &str) -> Result<usize, String> {
+    match cell.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number - 1),
+        _ if cell.is_empty() => Err(format!("the {} is missing", what)),
+        _ => Err(format!("'{}' is not a {} number", cell, what)),
+    }
+}
@@ -0,0 +1,645 @@
+//! Offline-first sync of machines between the native client's local database and the backend.
+//!
+//! The backend counts a revision for every machine and every slot, raised by each change. A
+//! machine's revision covers its own fields and its magazines without their slots, a slot's
+//! revision covers the slot. After each sync the client keeps what it and the backend agreed
+//! on, the [`SyncBase`]. The queue of changes to send is the difference between the local
+//! machines and the base, so edits made offline are kept until a sync gets through.
+//!
+//! [`plan`] merges the local machines and the backend's with the base as common ancestor.
+//! Whatever changed on one side only is taken from that side. Whatever changed on both
+//! sides, differently, is a [`Conflict`] that the user resolves with [`SyncPlan::resolve`].
+//! Local edits made while the user resolves are merged in by [`SyncPlan::replan`].
+//! The backend applies each [`SyncOperation`] only if the revision it expects is still
+//! current, which catches changes made between fetching and sending.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{machine::Machine, placement::SlotAddress, slot::Slot};
+
+pub type Revision = u64;
+
+/// A machine as the backend holds it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RevisedMachine {
+    pub machine: Machine,
+    pub revision: Revision,
+    /// Revision of every slot, by magazine
+    pub slot_revisions: Vec<Vec<Revision>>,
+}
+
+impl RevisedMachine {
+    /// A machine the backend has just created, everything at revision 0.
+    pub fn new(machine: Machine) -> Self {
+        let slot_revisions = machine
+            .magazines
+            .iter()
+            .map(|magazine| vec![0; magazine.slots.len()])
+            .collect();
+        Self {
+            machine,
+            revision: 0,
+            slot_revisions,
+        }
+    }
+
+    /// The slot and its revision, an empty slot at revision 0 where there is none.
+    fn slot(&self, address: SlotAddress) -> (Slot, Revision) {
+        let slot = slot_at(&self.machine, address);
+        let revision = self
+            .slot_revisions
+            .get(address.magazine)
+            .and_then(|revisions| revisions.get(address.slot))
+            .copied()
+            .unwrap_or(0);
+        (slot, revision)
+    }
+
+    fn set_slot(&mut self, address: SlotAddress, slot: Slot, revision: Revision) {
+        let (Some(target), Some(revisions)) = (
+            self.machine
+                .magazines
+                .get_mut(address.magazine)
+                .and_then(|magazine| magazine.slots.get_mut(address.slot)),
+            self.slot_revisions.get_mut(address.magazine),
+        ) else {
+            return;
+        };
+        *target = slot;
+        if let Some(target) = revisions.get_mut(address.slot) {
+            *target = revision;
+        }
+    }
+
+    /// Takes the fields and magazines of `machine`, keeping the slots that are still there.
+    fn reshape(&mut self, machine: &Machine, revision: Revision) {
+        let mut reshaped = machine.clone();
+        for (index, magazine) in reshaped.magazines.iter_mut().enumerate() {
+            magazine.slots = (0..magazine.capacity)
+                .map(|slot| self.slot(SlotAddress::new(index, slot)).0)
+                .collect();
+        }
+        self.slot_revisions = reshaped
+            .magazines
+            .iter()
+            .enumerate()
+            .map(|(index, magazine)| {
+                (0..magazine.slots.len())
+                    .map(|slot| self.slot(SlotAddress::new(index, slot)).1)
+                    .collect()
+            })
+            .collect();
+        self.machine = reshaped;
+        self.revision = revision;
+    }
+}
+
+/// What the client and the backend agreed on at the last sync.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SyncBase {
+    pub machines: Vec<RevisedMachine>,
+}
+
+impl SyncBase {
+    pub fn get(&self, uuid: &str) -> Option<&RevisedMachine> {
+        self.machines
+            .iter()
+            .find(|machine| machine.machine.uuid == uuid)
+    }
+
+    fn get_mut(&mut self, uuid: &str) -> Option<&mut RevisedMachine> {
+        self.machines
+            .iter_mut()
+            .find(|machine| machine.machine.uuid == uuid)
+    }
+
+    /// Puts back the entry `old` had for the machine, or drops it when `old` had none.
+    fn restore(&mut self, old: &SyncBase, uuid: &str) {
+        let restored = old.get(uuid).cloned();
+        match (self.get_mut(uuid), restored) {
+            (Some(entry), Some(restored)) => *entry = restored,
+            (None, Some(restored)) => self.machines.push(restored),
+            (Some(_), None) => self.machines.retain(|m| m.machine.uuid != uuid),
+            (None, None) => {}
+        }
+    }
+}
+
+/// A change for the backend, applied only while the machine or slot is at `expected`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SyncOperation {
+    CreateMachine {
+        machine: Box<Machine>,
+    },
+    /// Sets the fields and magazines of the machine. Slots are left as they are, new ones
+    /// are empty.
+    UpdateMachine {
+        machine: Box<Machine>,
+        expected: Revision,
+    },
+    /// Refused if the machine or any of its slots changed since `expected`
+    DeleteMachine {
+        machine_id: String,
+        expected: Revision,
+        expected_slots: Vec<Vec<Revision>>,
+    },
+    UpdateSlot {
+        machine_id: String,
+        address: SlotAddress,
+        slot: Box<Slot>,
+        expected: Revision,
+    },
+}
+
+impl SyncOperation {
+    pub fn machine_id(&self) -> &str {
+        match self {
+            SyncOperation::CreateMachine { machine }
+            | SyncOperation::UpdateMachine { machine, .. } => &machine.uuid,
+            SyncOperation::DeleteMachine { machine_id, .. }
+            | SyncOperation::UpdateSlot { machine_id, .. } => machine_id,
+        }
+    }
+}
+
+/// The backend's answer to one [`SyncOperation`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SyncOutcome {
+    /// The revision the machine or slot is at now
+    Applied { revision: Revision },
+    /// The revision did not match, or there is no such machine or slot. `current` is the
+    /// machine as the backend holds it, if it still has it.
+    Conflict {
+        current: Option<Box<RevisedMachine>>,
+    },
+}
+
+/// Something that changed both locally and on the backend since the last sync.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// The fields or magazines changed on both sides
+    Machine {
+        local: Box<Machine>,
+        remote: Box<RevisedMachine>,
+    },
+    Slot {
+        machine_id: String,
+        machine_name: String,
+        address: SlotAddress,
+        local: Box<Slot>,
+        remote: Box<Slot>,
+        revision: Revision,
+    },
+    /// Deleted here, changed on the backend
+    DeletedLocally { remote: Box<RevisedMachine> },
+    /// Changed here, deleted on the backend
+    DeletedRemotely { local: Box<Machine> },
+}
+
+impl Conflict {
+    /// Whether both are about the same machine or slot, in the same way.
+    fn is_about(&self, other: &Conflict) -> bool {
+        match (self, other) {
+            (Conflict::Machine { local: a, .. }, Conflict::Machine { local: b, .. })
+            | (Conflict::DeletedRemotely { local: a }, Conflict::DeletedRemotely { local: b }) => {
+                a.uuid == b.uuid
+            }
+            (
+                Conflict::Slot {
+                    machine_id: a,
+                    address: x,
+                    ..
+                },
+                Conflict::Slot {
+                    machine_id: b,
+                    address: y,
+                    ..
+                },
+            ) => a == b && x == y,
+            (Conflict::DeletedLocally { remote: a }, Conflict::DeletedLocally { remote: b }) => {
+                a.machine.uuid == b.machine.uuid
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Machine { local, .. } => {
+                write!(f, "{} was changed here and on the server", local.name)
+            }
+            Conflict::Slot {
+                machine_name,
+                address,
+                ..
+            } => write!(
+                f,
+                "{}, {} was changed here and on the server",
+                machine_name, address
+            ),
+            Conflict::DeletedLocally { remote } => write!(
+                f,
+                "{} was deleted here but changed on the server",
+                remote.machine.name
+            ),
+            Conflict::DeletedRemotely { local } => write!(
+                f,
+                "{} was changed here but deleted on the server",
+                local.name
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    KeepLocal,
+    KeepRemote,
+}
+
+/// The outcome of merging, to be sent to the backend once every conflict is resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPlan {
+    /// The local machines with every remote change that does not conflict. Conflicting
+    /// parts hold the local version until they are resolved.
+    pub merged: Vec<Machine>,
+    pub operations: Vec<SyncOperation>,
+    pub conflicts: Vec<Conflict>,
+    /// The backend's machines when the plan was made
+    remote: SyncBase,
+    /// Every conflict resolved so far, and how
+    resolved: Vec<(Conflict, Resolution)>,
+}
+
+/// Number of machines and slots changed locally since the last sync.
+pub fn pending_changes(base: &SyncBase, local: &[Machine]) -> usize {
+    let removed = base
+        .machines
+        .iter()
+        .filter(|base| !local.iter().any(|m| m.uuid == base.machine.uuid))
+        .count();
+    let changed: usize = local
+        .iter()
+        .map(|machine| match base.get(&machine.uuid) {
+            None => 1,
+            Some(base) => {
+                let shape_changed = usize::from(shape(machine) != shape(&base.machine));
+                let slots_changed = addresses(machine)
+                    .filter(|&address| slot_at(machine, address) != base.slot(address).0)
+                    .count();
+                shape_changed + slots_changed
+            }
+        })
+        .sum();
+    removed + changed
+}
+
+pub fn plan(base: &SyncBase, local: &[Machine], remote: &[RevisedMachine]) -> SyncPlan {
+    let remote = SyncBase {
+        machines: remote.to_vec(),
+    };
+    let mut plan = SyncPlan {
+        merged: vec![],
+        operations: vec![],
+        conflicts: vec![],
+        remote: remote.clone(),
+        resolved: vec![],
+    };
+
+    for machine in local {
+        match (base.get(&machine.uuid), remote.get(&machine.uuid)) {
+            (None, None) => {
+                plan.operations.push(SyncOperation::CreateMachine {
+                    machine: Box::new(machine.clone()),
+                });
+                plan.merged.push(machine.clone());
+            }
+            (Some(base), None) => {
+                // Unchanged machines follow the deletion
+                if *machine != base.machine {
+                    plan.conflicts.push(Conflict::DeletedRemotely {
+                        local: Box::new(machine.clone()),
+                    });
+                    plan.merged.push(machine.clone());
+                }
+            }
+            // Created on both sides with the same uuid, nothing to merge against
+            (None, Some(remote)) if *machine == remote.machine => plan.merged.push(machine.clone()),
+            (None, Some(remote)) => {
+                plan.conflicts.push(Conflict::Machine {
+                    local: Box::new(machine.clone()),
+                    remote: Box::new(remote.clone()),
+                });
+                plan.merged.push(machine.clone());
+            }
+            (Some(base), Some(remote)) => plan.merge_machine(base, machine, remote),
+        }
+    }
+
+    for remote in &remote.machines {
+        if local.iter().any(|m| m.uuid == remote.machine.uuid) {
+            continue;
+        }
+        match base.get(&remote.machine.uuid) {
+            None => plan.merged.push(remote.machine.clone()),
+            Some(base)
+                if base.revision == remote.revision
+                    && base.slot_revisions == remote.slot_revisions =>
+            {
+                plan.operations.push(SyncOperation::DeleteMachine {
+                    machine_id: remote.machine.uuid.clone(),
+                    expected: remote.revision,
+                    expected_slots: remote.slot_revisions.clone(),
+                });
+            }
+            Some(_) => plan.conflicts.push(Conflict::DeletedLocally {
+                remote: Box::new(remote.clone()),
+            }),
+        }
+    }
+    plan
+}
+
+impl SyncPlan {
+    fn merge_machine(&mut self, base: &RevisedMachine, local: &Machine, remote: &RevisedMachine) {
+        let local_shape = shape(local) != shape(&base.machine);
+        let remote_shape = remote.revision != base.revision;
+        let conflict = Conflict::Machine {
+            local: Box::new(local.clone()),
+            remote: Box::new(remote.clone()),
+        };
+        if local_shape && remote_shape && shape(local) != shape(&remote.machine) {
+            self.conflicts.push(conflict);
+            self.merged.push(local.clone());
+            return;
+        }
+
+        let (mut merged, dropped) = if local_shape {
+            (local.clone(), &remote.machine)
+        } else {
+            (remote.machine.clone(), local)
+        };
+        // A slot that the new shape drops must not have changed on the side it comes from
+        let lost = addresses(dropped).any(|address| {
+            slot_ref(&merged, address).is_none()
+                && if local_shape {
+                    remote.slot(address).1 != base.slot(address).1
+                } else {
+                    slot_at(local, address) != base.slot(address).0
+                }
+        });
+        if lost {
+            self.conflicts.push(conflict);
+            self.merged.push(local.clone());
+            return;
+        }
+
+        let mut operations = vec![];
+        if local_shape && shape(local) != shape(&remote.machine) {
+            operations.push(SyncOperation::UpdateMachine {
+                machine: Box::new(shape(local)),
+                expected: remote.revision,
+            });
+        }
+        let merged_addresses: Vec<_> = addresses(&merged).collect();
+        for address in merged_addresses {
+            let (base_slot, base_revision) = base.slot(address);
+            let local_slot = slot_at(local, address);
+            let (remote_slot, remote_revision) = remote.slot(address);
+            let slot = if local_slot == base_slot {
+                remote_slot
+            } else if remote_revision == base_revision || local_slot == remote_slot {
+                if local_slot != remote_slot {
+                    operations.push(SyncOperation::UpdateSlot {
+                        machine_id: local.uuid.clone(),
+                        address,
+                        slot: Box::new(local_slot.clone()),
+                        expected: remote_revision,
+                    });
+                }
+                local_slot
+            } else {
+                self.conflicts.push(Conflict::Slot {
+                    machine_id: local.uuid.clone(),
+                    machine_name: local.name.clone(),
+                    address,
+                    local: Box::new(local_slot.clone()),
+                    remote: Box::new(remote_slot),
+                    revision: remote_revision,
+                });
+                local_slot
+            };
+            merged.magazines[address.magazine].slots[address.slot] = slot;
+        }
+        self.operations.extend(operations);
+        self.merged.push(merged);
+    }
+
+    /// Settles `self.conflicts[index]`, which is removed.
+    pub fn resolve(&mut self, index: usize, resolution: Resolution) {
+        let conflict = self.conflicts.remove(index);
+        self.resolved.push((conflict.clone(), resolution));
+        match (conflict, resolution) {
+            (Conflict::Machine { local, remote }, Resolution::KeepLocal) => {
+                self.forget(&local.uuid);
+                self.operations.extend(overwrite(&local, &remote));
+                self.replace(&local.uuid, Some(local.as_ref().clone()));
+            }
+            (Conflict::Machine { local, remote }, Resolution::KeepRemote) => {
+                self.forget(&local.uuid);
+                self.replace(&local.uuid, Some(remote.machine));
+            }
+            (
+                Conflict::Slot {
+                    machine_id,
+                    address,
+                    local,
+                    revision,
+                    ..
+                },
+                Resolution::KeepLocal,
+            ) => self.operations.push(SyncOperation::UpdateSlot {
+                machine_id,
+                address,
+                slot: local,
+                expected: revision,
+            }),
+            (
+                Conflict::Slot {
+                    machine_id,
+                    address,
+                    remote,
+                    ..
+                },
+                Resolution::KeepRemote,
+            ) => {
+                if let Some(machine) = self.merged.iter_mut().find(|m| m.uuid == machine_id) {
+                    if let Some(slot) = machine
+                        .magazines
+                        .get_mut(address.magazine)
+                        .and_then(|magazine| magazine.slots.get_mut(address.slot))
+                    {
+                        *slot = *remote;
+                    }
+                }
+            }
+            (Conflict::DeletedLocally { remote }, Resolution::KeepLocal) => {
+                self.operations.push(SyncOperation::DeleteMachine {
+                    machine_id: remote.machine.uuid,
+                    expected: remote.revision,
+                    expected_slots: remote.slot_revisions,
+                })
+            }
+            (Conflict::DeletedLocally { remote }, Resolution::KeepRemote) => {
+                self.merged.push(remote.machine)
+            }
+            (Conflict::DeletedRemotely { local }, Resolution::KeepLocal) => self
+                .operations
+                .push(SyncOperation::CreateMachine { machine: local }),
+            (Conflict::DeletedRemotely { local }, Resolution::KeepRemote) => {
+                self.replace(&local.uuid, None)
+            }
+        }
+    }
+
+    /// The plan made again from `local` as it is now, against the same backend machines.
+    /// Conflicts resolved so far are resolved the same way, those that come from edits made
+    /// since are left to the user.
+    pub fn replan(&self, base: &SyncBase, local: &[Machine]) -> SyncPlan {
+        let mut replanned = plan(base, local, &self.remote.machines);
+        for (conflict, resolution) in &self.resolved {
+            if let Some(index) = replanned
+                .conflicts
+                .iter()
+                .position(|other| other.is_about(conflict))
+            {
+                replanned.resolve(index, *resolution);
+            }
+        }
+        replanned
+    }
+
+    /// Drops the operations and slot conflicts of a machine that is settled as a whole.
+    fn forget(&mut self, uuid: &str) {
+        self.operations
+            .retain(|operation| operation.machine_id() != uuid);
+        self.conflicts.retain(
+            |conflict| !matches!(conflict, Conflict::Slot { machine_id, .. } if machine_id == uuid),
+        );
+    }
+
+    fn replace(&mut self, uuid: &str, machine: Option<Machine>) {
+        let Some(index) = self.merged.iter().position(|m| m.uuid == uuid) else {
+            return;
+        };
+        match machine {
+            Some(machine) => self.merged[index] = machine,
+            None => {
+                self.merged.remove(index);
+            }
+        }
+    }
+
+    /// The base to keep once the backend answered `outcomes`, one for each operation.
+    /// Parts the backend refused keep their entry from `old`, so the next sync finds the
+    /// conflict again.
+    pub fn finish(&self, old: &SyncBase, outcomes: &[SyncOutcome]) -> SyncBase {
+        let mut base = self.remote.clone();
+        for (operation, outcome) in self.operations.iter().zip(outcomes) {
+            let uuid = operation.machine_id();
+            let revision = match outcome {
+                SyncOutcome::Applied { revision } => *revision,
+                SyncOutcome::Conflict { .. } => {
+                    match operation {
+                        SyncOperation::UpdateSlot { address, .. } => {
+                            if let (Some(entry), Some(old)) = (base.get_mut(uuid), old.get(uuid)) {
+                                let (slot, revision) = old.slot(*address);
+                                entry.set_slot(*address, slot, revision);
+                            }
+                        }
+                        _ => base.restore(old, uuid),
+                    }
+                    continue;
+                }
+            };
+            match operation {
+                SyncOperation::CreateMachine { machine } => base
+                    .machines
+                    .push(RevisedMachine::new(machine.as_ref().clone())),
+                SyncOperation::UpdateMachine { machine, .. } => {
+                    if let Some(entry) = base.get_mut(uuid) {
+                        entry.reshape(machine, revision);
+                    }
+                }
+                SyncOperation::DeleteMachine { .. } => {
+                    base.machines.retain(|m| m.machine.uuid != uuid)
+                }
+                SyncOperation::UpdateSlot { address, slot, .. } => {
+                    if let Some(entry) = base.get_mut(uuid) {
+                        entry.set_slot(*address, slot.as_ref().clone(), revision);
+                    }
+                }
+            }
+        }
+        base
+    }
+}
+
+/// Operations that make the backend's `remote` into `local`.
+fn overwrite(local: &Machine, remote: &RevisedMachine) -> Vec<SyncOperation> {
+    let mut operations = vec![];
+    if shape(local) != shape(&remote.machine) {
+        operations.push(SyncOperation::UpdateMachine {
+            machine: Box::new(shape(local)),
+            expected: remote.revision,
+        });
+    }
+    for address in addresses(local) {
+        let slot = slot_at(local, address);
+        let (remote_slot, revision) = remote.slot(address);
+        if slot != remote_slot {
+            operations.push(SyncOperation::UpdateSlot {
+                machine_id: local.uuid.clone(),
+                address,
+                slot: Box::new(slot),
+                expected: revision,
+            });
+        }
+    }
+    operations
+}
+
+/// The machine without slot contents, what the machine's revision covers.
+fn shape(machine: &Machine) -> Machine {
+    let mut shape = machine.clone();
+    for magazine in &mut shape.magazines {
+        magazine.slots.clear();
+    }
+    shape
+}
+
+fn addresses(machine: &Machine) -> impl Iterator<Item = SlotAddress> + '_ {
+    machine
+        .magazines
+        .iter()
+        .enumerate()
+        .flat_map(|(index, magazine)| {
+            (0..magazine.slots.len()).map(move |slot| SlotAddress::new(index, slot))
+        })
+}
+
+fn slot_ref(machine: &Machine, address: SlotAddress) -> Option<&Slot> {
+    machine
+        .magazines
+        .get(address.magazine)
+        .and_then(|magazine| magazine.slots.get(address.slot))
+}
+
+/// The slot at `address`, an empty one where there is none.
+fn slot_at(machine: &Machine, address: SlotAddress) -> Slot {
+    slot_ref(machine, address).cloned().unwrap_or_default()
+}
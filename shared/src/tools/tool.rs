@@ -7,10 +7,11 @@ use crate::custom_traits::{
     GetDegree, GetDiameter, GetRotatingToolCategory, GetToolType, GetTurningToolCategory, GetUuid,
     UiDisplay,
 };
+use crate::validation::{FieldError, Validator};
 use serde::{Deserialize, Serialize};
 
 // Highest level tool
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Tool {
     Rotating(RotatingTool),
     Turning(TurningTool),
@@ -22,6 +23,25 @@ impl Default for Tool {
     }
 }
 
+impl Tool {
+    /// Short one line description, e.g. for a table cell.
+    pub fn summary(&self) -> String {
+        match self {
+            Tool::Rotating(tool) => format!("{} Ø{:.2} mm", tool.category, tool.cutting_diameter),
+            Tool::Turning(tool) => format!("{} {}", tool.category, tool.insert_type)
+                .trim_end()
+                .to_string(),
+        }
+    }
+
+    pub fn hover_ui(&self, ui: &mut Ui) {
+        match self {
+            Tool::Rotating(tool) => rotating_tool_hover_ui(ui, tool),
+            Tool::Turning(tool) => turning_tool_hover_ui(ui, tool),
+        }
+    }
+}
+
 impl GetUuid for Tool {
     fn get_uuid(&self) -> String {
         match self {
@@ -42,7 +62,7 @@ impl GetUuid for TurningTool {
     }
 }
 
-fn rotating_tool_hover_ui(ui: &mut Ui, rotating_tool: &RotatingTool) {
+pub fn rotating_tool_hover_ui(ui: &mut Ui, rotating_tool: &RotatingTool) {
     egui::Grid::new("rotating_tool_info")
         .num_columns(2)
         .striped(true)
@@ -74,7 +94,7 @@ fn rotating_tool_hover_ui(ui: &mut Ui, rotating_tool: &RotatingTool) {
         });
 }
 
-fn turning_tool_hover_ui(ui: &mut Ui, turning_tool: &TurningTool) {
+pub fn turning_tool_hover_ui(ui: &mut Ui, turning_tool: &TurningTool) {
     egui::Grid::new("turning_tool_info")
         .num_columns(2)
         .striped(true)
@@ -246,7 +266,7 @@ impl UiDisplay for TurningTool {
                     ui.end_row();
 
                     ui.label("Insert Type:");
-                    ui.label(format!("{}", self.insert_type));
+                    ui.label(self.insert_type.to_string());
                     ui.end_row();
 
                     ui.label("Handedness:");
@@ -357,7 +377,7 @@ impl GetTurningToolCategory for TurningTool {
 }
 
 impl GetDiameter for Tool {
-    fn get_diameter(&self) -> f32 {
+    fn get_diameter(&self) -> Option<f32> {
         match self {
             Tool::Rotating(item) => item.get_diameter(),
             Tool::Turning(item) => item.get_diameter(),
@@ -366,19 +386,19 @@ impl GetDiameter for Tool {
 }
 
 impl GetDiameter for RotatingTool {
-    fn get_diameter(&self) -> f32 {
-        self.cutting_diameter
+    fn get_diameter(&self) -> Option<f32> {
+        Some(self.cutting_diameter)
     }
 }
 
 impl GetDiameter for TurningTool {
-    fn get_diameter(&self) -> f32 {
-        0.0
+    fn get_diameter(&self) -> Option<f32> {
+        None
     }
 }
 
 impl GetDegree for Tool {
-    fn get_degree(&self) -> f32 {
+    fn get_degree(&self) -> Option<f32> {
         match self {
             Tool::Rotating(item) => item.get_degree(),
             Tool::Turning(item) => item.get_degree(),
@@ -387,19 +407,19 @@ impl GetDegree for Tool {
 }
 
 impl GetDegree for RotatingTool {
-    fn get_degree(&self) -> f32 {
-        0.0
+    fn get_degree(&self) -> Option<f32> {
+        None
     }
 }
 
 impl GetDegree for TurningTool {
-    fn get_degree(&self) -> f32 {
-        self.cutting_edge_angle
+    fn get_degree(&self) -> Option<f32> {
+        Some(self.cutting_edge_angle)
     }
 }
 
 // Second highest level tool
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RotatingTool {
     pub uuid: String,
     pub category: RotatingToolCategory,
@@ -413,6 +433,30 @@ pub struct RotatingTool {
     pub coolant_pressure: u32, // BAR
 }
 
+impl RotatingTool {
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut v = Validator::default();
+        v.check(
+            self.category != RotatingToolCategory::Empty,
+            "Category",
+            "choose a category",
+        );
+        v.positive("Cutting Diameter", self.cutting_diameter);
+        v.positive("Connection Diameter", self.connection_diameter);
+        v.positive("Usable Length", self.usable_length);
+        v.positive("Functional Length", self.functional_length);
+        v.check(
+            self.usable_length <= self.functional_length,
+            "Usable Length",
+            "cannot be longer than the functional length",
+        );
+        v.non_negative("Achievable Hole Tolerance", self.achievable_hole_tolerance);
+        v.non_negative("Weight of Tool", self.weight_of_tool);
+        v.check(self.max_rpm > 0, "Max RPM", "must be greater than 0");
+        v.finish()
+    }
+}
+
 impl Default for RotatingTool {
     fn default() -> Self {
         Self {
@@ -430,7 +474,7 @@ impl Default for RotatingTool {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TurningTool {
     pub uuid: String,
     pub category: TurningToolCategory,
@@ -455,6 +499,55 @@ pub struct TurningTool {
     pub tool_weight: f32,
 }
 
+impl TurningTool {
+    /// Only internal tools work inside a bore, so only they have a minimum bore diameter.
+    pub fn uses_bore_diameter(&self) -> bool {
+        matches!(
+            self.category,
+            TurningToolCategory::InternalTurningTool | TurningToolCategory::BoringBar
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut v = Validator::default();
+        v.check(
+            self.category != TurningToolCategory::Empty,
+            "Category",
+            "choose a category",
+        );
+        v.angle("Lead Angle", self.lead_angle);
+        v.angle("Cutting Edge Angle", self.cutting_edge_angle);
+        v.check(
+            (0.0..=90.0).contains(&self.maximum_ramping_angle),
+            "Maximum Ramping Angle",
+            "must be between 0° and 90°",
+        );
+        v.angle("Workpiece Side Body Angle", self.workpiece_side_body_angle);
+        v.angle("Machine Side Body Angle", self.machine_side_body_angle);
+        if self.uses_bore_diameter() {
+            v.positive("Minimum Bore Diameter", self.minimum_bore_diameter);
+        }
+        v.positive("Cutting Depth Maximum", self.cutting_depth_maximum);
+        v.range("Overhang", (self.minimum_overhang, self.maximum_overhang));
+        v.positive("Usable Length", self.usable_length);
+        v.positive("Body Length", self.body_length);
+        v.positive("Body Diameter", self.body_diameter);
+        v.positive("Functional Diameter", self.functional_diameter);
+        v.non_negative(
+            "Peripheral Effective Cutting",
+            self.peripheral_effective_cutting,
+        );
+        v.positive("Connection Diameter", self.connection_diameter);
+        v.check(
+            self.maximum_rpm > 0,
+            "Maximum RPM",
+            "must be greater than 0",
+        );
+        v.non_negative("Tool Weight", self.tool_weight);
+        v.finish()
+    }
+}
+
 impl Default for TurningTool {
     fn default() -> Self {
         Self {
@@ -483,7 +576,7 @@ impl Default for TurningTool {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Sequence)]
 pub enum Handedness {
     #[default]
     Neutral,
@@ -0,0 +1,242 @@
+//! Moving, swapping and copying what is in a slot, within a magazine, across magazines and
+//! across machines.
+//!
+//! A slot's contents are its tool number, tool, holder, overhang and description. Whether a
+//! slot is reserved stays with the slot, and reserved slots are never changed. Transfers are
+//! undone like any other edit, through [`History`](crate::history::History).
+//!
+//! An assembly keeps its parts and the machine's limits within a machine, so compatibility
+//! is only checked when it goes to another machine.
+
+use std::fmt;
+
+use crate::{
+    compatibility::{check_in_machine, Issue, Severity},
+    custom_traits::GetUuid,
+    inventory::{self, HolderInventory, InventoryError},
+    machine::Machine,
+    placement::{self, machine_slot_mut, MachineSlot, PlacementError, SlotAddress},
+    slot::Slot,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    /// Moves the contents of `from` into the empty slot `to`.
+    Move { from: MachineSlot, to: MachineSlot },
+    /// Exchanges the contents of two slots, either of which may be empty.
+    Swap { a: MachineSlot, b: MachineSlot },
+    /// Puts the contents of `from` into the empty slot `to` as well, without the tool
+    /// number. The holder takes a free copy from the inventory.
+    Copy { from: MachineSlot, to: MachineSlot },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferError {
+    /// There is nothing to move or copy.
+    Empty(SlotAddress),
+    /// The target already holds something.
+    Occupied(SlotAddress),
+    /// Every slot of the machine holds something or is reserved.
+    NoFreeSlot(usize),
+    /// The assembly breaks the limits of the machine it would go to, and the settings
+    /// block incompatible assemblies.
+    Incompatible {
+        to: MachineSlot,
+        issue: Issue,
+    },
+    Placement(PlacementError),
+    Inventory(InventoryError),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::Empty(address) => write!(f, "There is nothing in {}", address),
+            TransferError::Occupied(address) => write!(f, "{} is not empty", address),
+            TransferError::NoFreeSlot(machine) => {
+                write!(f, "Machine {} has no free slot", machine + 1)
+            }
+            TransferError::Incompatible { to, issue } => write!(
+                f,
+                "Cannot go into {} of machine {}: {}",
+                to.address,
+                to.machine + 1,
+                issue
+            ),
+            TransferError::Placement(e) => write!(f, "{}", e),
+            TransferError::Inventory(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TransferError {}
+
+impl From<PlacementError> for TransferError {
+    fn from(e: PlacementError) -> Self {
+        TransferError::Placement(e)
+    }
+}
+
+impl From<InventoryError> for TransferError {
+    fn from(e: InventoryError) -> Self {
+        TransferError::Inventory(e)
+    }
+}
+
+/// Carries out `transfer`. With `block_incompatible`, assemblies that would break the limits
+/// of another machine are refused. Nothing changes when it fails.
+pub fn apply(
+    machines: &mut [Machine],
+    inventory: &HolderInventory,
+    transfer: Transfer,
+    changed_by: &str,
+    block_incompatible: bool,
+) -> Result<(), TransferError> {
+    match transfer {
+        Transfer::Move { from, to } => {
+            if from == to {
+                return Ok(());
+            }
+            let source = editable(machines, from)?;
+            if source.is_empty() {
+                return Err(TransferError::Empty(from.address));
+            }
+            let moved = contents(source);
+            if !editable(machines, to)?.is_empty() {
+                return Err(TransferError::Occupied(to.address));
+            }
+            if block_incompatible {
+                fits(machines, from, to, &moved)?;
+            }
+            put_contents(
+                machine_slot_mut(machines, from),
+                Slot::default(),
+                changed_by,
+            );
+            put_contents(machine_slot_mut(machines, to), moved, changed_by);
+            Ok(())
+        }
+        Transfer::Swap { a, b } => {
+            if a == b {
+                return Ok(());
+            }
+            let first = contents(editable(machines, a)?);
+            let second = contents(editable(machines, b)?);
+            if block_incompatible {
+                fits(machines, a, b, &first)?;
+                fits(machines, b, a, &second)?;
+            }
+            put_contents(machine_slot_mut(machines, a), second, changed_by);
+            put_contents(machine_slot_mut(machines, b), first, changed_by);
+            Ok(())
+        }
+        Transfer::Copy { from, to } => {
+            let source = find(machines, from)?;
+            if source.is_empty() {
+                return Err(TransferError::Empty(from.address));
+            }
+            if let Some(holder) = &source.holder {
+                let uuid = holder.get_uuid();
+                let owned = inventory
+                    .get(&uuid)
+                    .ok_or_else(|| InventoryError::NotFound(uuid.clone()))?
+                    .copies();
+                if inventory::uses(machines, &uuid).len() >= owned {
+                    return Err(InventoryError::NoFreeCopy { owned }.into());
+                }
+            }
+            if !editable(machines, to)?.is_empty() {
+                return Err(TransferError::Occupied(to.address));
+            }
+            let copy = Slot {
+                tool_number: None,
+                ..contents(find(machines, from)?)
+            };
+            if block_incompatible {
+                fits(machines, from, to, &copy)?;
+            }
+            put_contents(machine_slot_mut(machines, to), copy, changed_by);
+            Ok(())
+        }
+    }
+}
+
+/// The first empty slot of the machine that is not reserved, for dropping a slot onto a
+/// whole machine.
+pub fn free_slot(machines: &[Machine], machine: usize) -> Result<MachineSlot, TransferError> {
+    let target = machines
+        .get(machine)
+        .ok_or(PlacementError::NoSuchMachine(machine))?;
+    for (magazine_index, magazine) in target.magazines.iter().enumerate() {
+        if let Some(slot_index) = magazine
+            .slots
+            .iter()
+            .position(|slot| slot.is_empty() && !slot.locked)
+        {
+            return Ok(MachineSlot {
+                machine,
+                address: SlotAddress::new(magazine_index, slot_index),
+            });
+        }
+    }
+    Err(TransferError::NoFreeSlot(machine))
+}
+
+/// Refuses `contents` going from one machine into `to` of another when it breaks that
+/// machine's limits.
+fn fits(
+    machines: &[Machine],
+    from: MachineSlot,
+    to: MachineSlot,
+    contents: &Slot,
+) -> Result<(), TransferError> {
+    if from.machine == to.machine {
+        return Ok(());
+    }
+    let machine = machines
+        .get(to.machine)
+        .ok_or(PlacementError::NoSuchMachine(to.machine))?;
+    match check_in_machine(machine, contents, None, None)
+        .into_iter()
+        .find(|issue| issue.severity() == Severity::Error)
+    {
+        Some(issue) => Err(TransferError::Incompatible { to, issue }),
+        None => Ok(()),
+    }
+}
+
+fn find(machines: &[Machine], at: MachineSlot) -> Result<&Slot, PlacementError> {
+    let machine = machines
+        .get(at.machine)
+        .ok_or(PlacementError::NoSuchMachine(at.machine))?;
+    placement::slot(machine, at.address)
+}
+
+fn editable(machines: &[Machine], at: MachineSlot) -> Result<&Slot, PlacementError> {
+    let slot = find(machines, at)?;
+    if slot.locked {
+        return Err(PlacementError::Locked(at.address));
+    }
+    Ok(slot)
+}
+
+/// A copy of what is in the slot, without the slot's own state.
+fn contents(slot: &Slot) -> Slot {
+    Slot {
+        tool_number: slot.tool_number.clone(),
+        tool: slot.tool.clone(),
+        holder: slot.holder.clone(),
+        overhang: slot.overhang,
+        description: slot.description.clone(),
+        ..Slot::default()
+    }
+}
+
+/// Replaces what is in the slot, keeping whether it is reserved.
+fn put_contents(slot: &mut Slot, contents: Slot, changed_by: &str) {
+    *slot = Slot {
+        locked: slot.locked,
+        ..contents
+    };
+    slot.touch(changed_by);
+}
@@ -0,0 +1,130 @@
+//! Forward upgrades of serialized [`UserData`].
+//!
+//! Blobs written since version 1 carry a `version` field, blobs without one are version 0.
+//! `UPGRADES[n]` turns version `n` into version `n + 1`, so an old blob is walked through
+//! every step up to [`CURRENT_USER_DATA_VERSION`] before it is deserialized.
+//!
+//! When the serialized shape of `UserData`, or anything it contains, changes: bump
+//! `CURRENT_USER_DATA_VERSION`, append a step and add a fixture of the old shape to
+//! `shared/tests/fixtures`.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::UserData;
+
+pub const CURRENT_USER_DATA_VERSION: u32 = 3;
+
+type Upgrade = fn(&mut Map<String, Value>) -> Result<(), UpgradeError>;
+
+const UPGRADES: &[Upgrade] = &[v0_to_v1, v1_to_v2, v2_to_v3];
+
+#[derive(Debug)]
+pub enum UpgradeError {
+    NotAnObject,
+    InvalidVersion,
+    TooNew { found: u64 },
+    Deserialize(serde_json::Error),
+}
+
+impl fmt::Display for UpgradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpgradeError::NotAnObject => write!(f, "User data is not a JSON object"),
+            UpgradeError::InvalidVersion => write!(f, "User data version is not a number"),
+            UpgradeError::TooNew { found } => write!(
+                f,
+                "User data version {} is newer than the supported version {}",
+                found, CURRENT_USER_DATA_VERSION
+            ),
+            UpgradeError::Deserialize(e) => write!(f, "Invalid user data: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UpgradeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UpgradeError::Deserialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+pub fn user_data_from_json(json: &str) -> Result<UserData, UpgradeError> {
+    let value = serde_json::from_str(json).map_err(UpgradeError::Deserialize)?;
+    upgrade_user_data(value)
+}
+
+pub fn upgrade_user_data(value: Value) -> Result<UserData, UpgradeError> {
+    let Value::Object(mut user_data) = value else {
+        return Err(UpgradeError::NotAnObject);
+    };
+
+    let mut version = match user_data.get("version") {
+        None => 0,
+        Some(version) => version.as_u64().ok_or(UpgradeError::InvalidVersion)?,
+    };
+    if version > CURRENT_USER_DATA_VERSION as u64 {
+        return Err(UpgradeError::TooNew { found: version });
+    }
+
+    while version < CURRENT_USER_DATA_VERSION as u64 {
+        UPGRADES[version as usize](&mut user_data)?;
+        version += 1;
+        user_data.insert("version".to_string(), version.into());
+    }
+
+    serde_json::from_value(Value::Object(user_data)).map_err(UpgradeError::Deserialize)
+}
+
+/// The objects in the array stored under `key`, skipping anything that is not an object.
+fn objects_mut<'a>(
+    parent: &'a mut Map<String, Value>,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    parent
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+// Version 0 machines had no uuid.
+fn v0_to_v1(user_data: &mut Map<String, Value>) -> Result<(), UpgradeError> {
+    for machine in objects_mut(user_data, "machines") {
+        machine
+            .entry("uuid")
+            .or_insert_with(|| Uuid::new_v4().to_string().into());
+    }
+    Ok(())
+}
+
+// Magazine `content` tuples became `slots`. The tuples themselves are still understood by
+// `Slot`'s deserializer, which also reads them from the backend's slot rows.
+fn v1_to_v2(user_data: &mut Map<String, Value>) -> Result<(), UpgradeError> {
+    for machine in objects_mut(user_data, "machines") {
+        for magazine in objects_mut(machine, "magazines") {
+            if let Some(content) = magazine.remove("content") {
+                magazine.insert("slots".to_string(), content);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Machines gained capabilities and magazines a kind, both unknown for existing ones.
+fn v2_to_v3(user_data: &mut Map<String, Value>) -> Result<(), UpgradeError> {
+    for machine in objects_mut(user_data, "machines") {
+        machine
+            .entry("capabilities")
+            .or_insert_with(|| Value::Object(Map::new()));
+        for magazine in objects_mut(machine, "magazines") {
+            magazine.entry("kind").or_insert(Value::Null);
+        }
+    }
+    Ok(())
+}
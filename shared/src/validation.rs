@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// A field that failed validation, with a message that can be shown to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Collects the errors of one item while its fields are checked.
+#[derive(Debug, Default)]
+pub(crate) struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn error(&mut self, field: &'static str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field,
+            message: message.into(),
+        });
+    }
+
+    pub fn check(&mut self, ok: bool, field: &'static str, message: &str) {
+        if !ok {
+            self.error(field, message);
+        }
+    }
+
+    pub fn positive(&mut self, field: &'static str, value: f32) {
+        self.check(value > 0.0, field, "must be greater than 0");
+    }
+
+    pub fn non_negative(&mut self, field: &'static str, value: f32) {
+        self.check(value >= 0.0, field, "cannot be negative");
+    }
+
+    pub fn angle(&mut self, field: &'static str, value: f32) {
+        self.check(
+            (-180.0..=180.0).contains(&value),
+            field,
+            "must be between -180° and 180°",
+        );
+    }
+
+    /// A `(min, max)` pair with `0 <= min <= max`.
+    pub fn range(&mut self, field: &'static str, (min, max): (f32, f32)) {
+        if min < 0.0 {
+            self.error(field, "cannot start below 0");
+        } else if min > max {
+            self.error(field, "minimum is larger than maximum");
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Vec<FieldError>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use shared::assembly::{assembly, slot_assembly, AssemblyKind, RpmLimit};
+use shared::holders::holder::{Holder, RotatingHolder, TurningHolder};
+use shared::slot::{LengthUnit, Overhang, Slot};
+use shared::tools::tool::{RotatingTool, Tool, TurningTool};
+
+fn end_mill(max_rpm: u32) -> Tool {
+    Tool::Rotating(RotatingTool {
+        functional_length: 60.0,
+        weight_of_tool: 80.0,
+        max_rpm,
+        ..RotatingTool::default()
+    })
+}
+
+fn shrink_fit(max_rpm: u32) -> Holder {
+    Holder::Rotating(RotatingHolder {
+        length: 90.0,
+        weight: 900.0,
+        max_rpm,
+        ..RotatingHolder::default()
+    })
+}
+
+#[test]
+fn lowest_known_rpm_limit_wins() {
+    let tool = end_mill(20000);
+    let holder = shrink_fit(25000);
+
+    assert_eq!(
+        assembly(&tool, &holder, None, Some(12000)).max_rpm,
+        Some((12000, RpmLimit::Spindle))
+    );
+    assert_eq!(
+        assembly(&tool, &holder, None, None).max_rpm,
+        Some((20000, RpmLimit::Tool))
+    );
+    assert_eq!(
+        assembly(&end_mill(0), &holder, None, None).max_rpm,
+        Some((25000, RpmLimit::Holder))
+    );
+    assert_eq!(
+        assembly(&end_mill(0), &shrink_fit(0), None, None).max_rpm,
+        None
+    );
+}
+
+#[test]
+fn gauge_length_uses_the_overhang() {
+    let tool = end_mill(20000);
+    let holder = shrink_fit(25000);
+
+    let without_overhang = assembly(&tool, &holder, None, None);
+    assert_eq!(without_overhang.gauge_length, Some(150.0));
+
+    let overhang = Overhang::millimeters(35.0);
+    assert_eq!(
+        assembly(&tool, &holder, Some(overhang), None).gauge_length,
+        Some(125.0)
+    );
+
+    let inch = Overhang {
+        value: 1.0,
+        unit: LengthUnit::Inch,
+    };
+    assert_eq!(
+        assembly(&tool, &holder, Some(inch), None).gauge_length,
+        Some(115.4)
+    );
+
+    // The tool cannot stick out further than it is long
+    let too_long = Overhang::millimeters(100.0);
+    assert_eq!(
+        assembly(&tool, &holder, Some(too_long), None).gauge_length,
+        Some(150.0)
+    );
+}
+
+#[test]
+fn weight_and_kind_combine_both_parts() {
+    let rotating = assembly(&end_mill(20000), &shrink_fit(25000), None, None);
+    assert_eq!(rotating.kind, AssemblyKind::Rotating);
+    assert_eq!(rotating.total_weight, 980.0);
+
+    let turning_tool = Tool::Turning(TurningTool {
+        tool_weight: 120.0,
+        ..TurningTool::default()
+    });
+    let turning_holder = Holder::Turning(TurningHolder {
+        weight: 400.0,
+        ..TurningHolder::default()
+    });
+    let turning = assembly(&turning_tool, &turning_holder, None, None);
+    assert_eq!(turning.kind, AssemblyKind::Turning);
+    assert_eq!(turning.gauge_length, None);
+    assert_eq!(turning.total_weight, 520.0);
+
+    assert_eq!(
+        assembly(&turning_tool, &shrink_fit(25000), None, None).kind,
+        AssemblyKind::Mixed
+    );
+}
+
+#[test]
+fn slots_need_a_tool_and_a_holder() {
+    let mut slot = Slot {
+        tool: Some(end_mill(20000)),
+        ..Slot::default()
+    };
+    assert_eq!(slot_assembly(&slot, None), None);
+
+    slot.holder = Some(shrink_fit(25000));
+    slot.overhang = Some(Overhang::millimeters(40.0));
+    assert_eq!(
+        slot_assembly(&slot, None).and_then(|assembly| assembly.gauge_length),
+        Some(130.0)
+    );
+}
@@ -0,0 +1,74 @@
+use chrono::Utc;
+use shared::audit::{AuditAction, AuditEntry};
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::slot::Slot;
+
+fn entry(
+    magazine: Option<usize>,
+    slot: Option<usize>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+) -> AuditEntry {
+    AuditEntry {
+        id: 1,
+        changed_at: Utc::now(),
+        changed_by: "a".to_string(),
+        action: AuditAction::Updated,
+        machine_id: "m".to_string(),
+        machine_name: "DMU 50".to_string(),
+        magazine,
+        slot,
+        before,
+        after,
+    }
+}
+
+fn json<T: serde::Serialize>(value: &T) -> Option<serde_json::Value> {
+    Some(serde_json::to_value(value).unwrap())
+}
+
+#[test]
+fn slot_changes_show_the_tool_numbers() {
+    let loaded = Slot {
+        tool_number: Some("T1".to_string()),
+        ..Slot::default()
+    };
+    let reserved = Slot {
+        locked: true,
+        ..Slot::default()
+    };
+
+    let cleared = entry(Some(0), Some(11), json(&loaded), json(&reserved));
+    assert_eq!(cleared.location(), "DMU 50, magazine 1, slot 12");
+    assert_eq!(cleared.summary(), "T1 → empty (reserved)");
+
+    let loaded_entry = entry(Some(0), Some(11), None, json(&loaded));
+    assert_eq!(loaded_entry.summary(), "T1");
+}
+
+#[test]
+fn machine_and_magazine_changes_show_their_names() {
+    let renamed = entry(
+        None,
+        None,
+        json(&Machine {
+            name: "DMU".to_string(),
+            ..Machine::default()
+        }),
+        json(&Machine {
+            name: "DMU 50".to_string(),
+            ..Machine::default()
+        }),
+    );
+    assert_eq!(renamed.location(), "DMU 50");
+    assert_eq!(renamed.summary(), "DMU → DMU 50");
+
+    let magazine = Magazine {
+        name: "Chain".to_string(),
+        ..Magazine::new(0, 30)
+    };
+    let deleted = entry(Some(1), None, json(&magazine), None);
+    assert_eq!(deleted.location(), "DMU 50, magazine 2");
+    assert_eq!(deleted.summary(), "Chain (30 slots)");
+}
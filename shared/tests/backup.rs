@@ -0,0 +1,188 @@
+use shared::backup::{self, BackupError, RestoreMode, RestoreReport};
+use shared::custom_traits::GetUuid;
+use shared::holders::holder::{Holder, RotatingHolder};
+use shared::inventory::Shortage;
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::slot::Slot;
+use shared::tools::tool::{RotatingTool, Tool};
+use shared::upgrade::CURRENT_USER_DATA_VERSION;
+use shared::UserData;
+
+const V1_WITH_MACHINE: &str = include_str!("fixtures/user_data_v1_with_machine.json");
+
+fn end_mill(diameter: f32) -> Tool {
+    Tool::Rotating(RotatingTool {
+        uuid: "end-mill".to_string(),
+        cutting_diameter: diameter,
+        ..RotatingTool::default()
+    })
+}
+
+fn collet() -> Holder {
+    Holder::Rotating(RotatingHolder {
+        uuid: "collet".to_string(),
+        ..RotatingHolder::default()
+    })
+}
+
+/// A machine with `tool` and a collet in its first slot.
+fn machine(uuid: &str, tool: Tool) -> Machine {
+    let mut magazine = Magazine::new(0, 2);
+    magazine.slots[0] = Slot {
+        tool_number: Some("T1".to_string()),
+        tool: Some(tool),
+        holder: Some(collet()),
+        ..Slot::default()
+    };
+    Machine {
+        uuid: uuid.to_string(),
+        name: uuid.to_string(),
+        magazine_count: 1,
+        magazines: vec![magazine],
+        ..Machine::default()
+    }
+}
+
+fn user_data(machines: Vec<Machine>) -> UserData {
+    let mut user_data = UserData {
+        machines,
+        ..UserData::default()
+    };
+    user_data.tool_library.adopt_loaded(&user_data.machines);
+    user_data.holder_inventory.adopt_loaded(&user_data.machines);
+    user_data
+}
+
+#[test]
+fn a_backup_reads_back_as_written() {
+    let data = user_data(vec![machine("mill", end_mill(10.0))]);
+    let json = backup::write(&data, "anna").unwrap();
+
+    let read = backup::read(&json).unwrap();
+    assert_eq!(read.username, "anna");
+    assert_eq!(read.user_data, data);
+}
+
+#[test]
+fn replacing_keeps_the_uuids_of_current_machines_only() {
+    let mut backed_up = user_data(vec![
+        machine("mill", end_mill(10.0)),
+        machine("lathe", end_mill(10.0)),
+    ]);
+    backed_up.selections.selected_machine = Some(1);
+    backed_up.settings.dark_mode = true;
+    let mut current = user_data(vec![machine("mill", end_mill(8.0))]);
+
+    let report = backup::restore(&mut current, backed_up.clone(), RestoreMode::Replace);
+
+    assert_eq!(
+        report,
+        RestoreReport {
+            machines: 2,
+            tools: 1,
+            holders: 1,
+            remapped: 1,
+            // Both machines hold the collet, the backup owns one
+            shortages: vec![Shortage {
+                uuid: collet().get_uuid(),
+                summary: collet().summary(),
+                owned: 1,
+                in_use: 2,
+            }],
+        }
+    );
+    assert_eq!(current.machines[0], backed_up.machines[0]);
+    assert_ne!(current.machines[1].uuid, "lathe");
+    assert_eq!(
+        current.machines[1].magazines,
+        backed_up.machines[1].magazines
+    );
+    assert_eq!(current.tool_library.tools(), [end_mill(10.0)]);
+    assert_eq!(current.selections.selected_machine, Some(1));
+    assert!(current.settings.dark_mode);
+}
+
+#[test]
+fn merging_remaps_colliding_tools_and_keeps_identical_ones() {
+    let backed_up = user_data(vec![machine("mill", end_mill(10.0))]);
+    let mut current = user_data(vec![machine("mill", end_mill(8.0))]);
+    current.selections.selected_machine = Some(0);
+
+    let report = backup::restore(&mut current, backed_up, RestoreMode::Merge);
+
+    // The machine and the differing end mill get new uuids, the collet is the same one
+    assert_eq!(
+        report,
+        RestoreReport {
+            machines: 1,
+            tools: 1,
+            holders: 0,
+            remapped: 2,
+            shortages: vec![Shortage {
+                uuid: collet().get_uuid(),
+                summary: collet().summary(),
+                owned: 1,
+                in_use: 2,
+            }],
+        }
+    );
+    assert_eq!(current.machines.len(), 2);
+    assert_eq!(current.machines[0].uuid, "mill");
+    assert_ne!(current.machines[1].uuid, "mill");
+    assert_eq!(current.selections.selected_machine, Some(0));
+
+    let merged_tool = current.machines[1].magazines[0].slots[0]
+        .tool
+        .clone()
+        .unwrap();
+    assert_ne!(merged_tool.get_uuid(), "end-mill");
+    assert_eq!(
+        current.tool_library.tools(),
+        [end_mill(8.0), merged_tool.clone()]
+    );
+    match merged_tool {
+        Tool::Rotating(tool) => assert_eq!(tool.cutting_diameter, 10.0),
+        tool => panic!("expected a rotating tool, got {:?}", tool),
+    }
+
+    // One collet entry, whose owned copies are left for the user to correct
+    assert_eq!(current.holder_inventory.holders().len(), 1);
+    assert_eq!(current.holder_inventory.holders()[0].copies(), 1);
+}
+
+#[test]
+fn files_are_checked_before_anything_is_restored() {
+    assert!(matches!(backup::read("{"), Err(BackupError::Json(_))));
+    assert!(matches!(
+        backup::read(V1_WITH_MACHINE),
+        Err(BackupError::NotABackup)
+    ));
+
+    // User data from an older build is upgraded
+    let old = format!(
+        r#"{{"format": "tool-magazine-backup", "backup_version": 1,
+            "created_at": "2024-05-31T12:00:00Z", "username": "anna",
+            "user_data": {}}}"#,
+        V1_WITH_MACHINE
+    );
+    let read = backup::read(&old).unwrap();
+    assert_eq!(read.user_data.version, CURRENT_USER_DATA_VERSION);
+    assert_eq!(read.user_data.machines[0].name, "DMU 50");
+
+    let newer = old.replace(r#""backup_version": 1"#, r#""backup_version": 2"#);
+    assert!(matches!(
+        backup::read(&newer),
+        Err(BackupError::TooNew { found: 2 })
+    ));
+
+    let mut damaged = user_data(vec![machine("mill", end_mill(10.0))]);
+    damaged.machines.push(damaged.machines[0].clone());
+    damaged.machines[0].magazines[0].capacity = 3;
+    let error = backup::read(&backup::write(&damaged, "anna").unwrap()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The backup is damaged: machine mill is in it twice, \
+         Magazine 1 of mill has 2 slots but room for 3"
+    );
+}
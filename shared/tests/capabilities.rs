@@ -0,0 +1,168 @@
+use shared::compatibility::{check_in_machine, Issue, Severity};
+use shared::holders::holder::{Holder, RotatingHolder, TurningHolder};
+use shared::machine::{Machine, MachineCapabilities, MachineKind};
+use shared::slot::{Overhang, Slot};
+use shared::tools::tool::{RotatingTool, Tool, TurningTool};
+
+fn machine(capabilities: MachineCapabilities) -> Machine {
+    Machine {
+        capabilities,
+        ..Machine::default()
+    }
+}
+
+fn end_mill(cutting_diameter: f32, functional_length: f32, weight_of_tool: f32) -> Tool {
+    Tool::Rotating(RotatingTool {
+        cutting_diameter,
+        connection_diameter: 10.0,
+        functional_length,
+        weight_of_tool,
+        ..RotatingTool::default()
+    })
+}
+
+fn shrink_fit(taper_type: &str, length: f32, weight: f32) -> Holder {
+    Holder::Rotating(RotatingHolder {
+        taper_type: taper_type.to_string(),
+        tool_clamping_range: (6.0, 12.0),
+        diameter: 32.0,
+        length,
+        weight,
+        ..RotatingHolder::default()
+    })
+}
+
+fn loaded(tool: Tool, holder: Holder) -> Slot {
+    Slot {
+        tool: Some(tool),
+        holder: Some(holder),
+        ..Slot::default()
+    }
+}
+
+#[test]
+fn unknown_capabilities_are_not_checked() {
+    let slot = loaded(
+        end_mill(100.0, 500.0, 50000.0),
+        shrink_fit("SK40", 100.0, 900.0),
+    );
+
+    assert_eq!(
+        check_in_machine(&Machine::default(), &slot, None, None),
+        vec![]
+    );
+}
+
+#[test]
+fn holder_taper_must_match_the_spindle() {
+    let spindle = machine(MachineCapabilities {
+        spindle_taper: "HSK-A63".to_string(),
+        ..MachineCapabilities::default()
+    });
+    let slot = Slot::default();
+
+    assert_eq!(
+        check_in_machine(
+            &spindle,
+            &slot,
+            None,
+            Some(&shrink_fit("hsk-a63 ", 80.0, 0.0))
+        ),
+        vec![]
+    );
+    assert_eq!(
+        check_in_machine(&spindle, &slot, None, Some(&shrink_fit("SK40", 80.0, 0.0))),
+        vec![Issue::TaperMismatch {
+            spindle: "HSK-A63".to_string(),
+            holder: "SK40".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn assembly_is_checked_against_size_and_weight_limits() {
+    let small_changer = machine(MachineCapabilities {
+        max_tool_length: Some(150.0),
+        max_tool_diameter: Some(25.0),
+        max_tool_weight: Some(1000.0),
+        ..MachineCapabilities::default()
+    });
+    let mut slot = loaded(end_mill(12.0, 60.0, 200.0), shrink_fit("", 100.0, 900.0));
+
+    assert_eq!(
+        check_in_machine(&small_changer, &slot, None, None),
+        vec![
+            Issue::TooLong {
+                gauge_length: 160.0,
+                max: 150.0,
+            },
+            Issue::TooWide {
+                diameter: 32.0,
+                max: 25.0,
+            },
+            Issue::TooHeavy {
+                weight: 1100.0,
+                max: 1000.0,
+            },
+        ]
+    );
+
+    // A shorter overhang and a slimmer, lighter holder fit
+    slot.overhang = Some(Overhang::millimeters(40.0));
+    let slim = Holder::Rotating(RotatingHolder {
+        diameter: 20.0,
+        weight: 500.0,
+        ..match shrink_fit("", 100.0, 0.0) {
+            Holder::Rotating(holder) => holder,
+            Holder::Turning(_) => unreachable!(),
+        }
+    });
+    assert_eq!(
+        check_in_machine(&small_changer, &slot, None, Some(&slim)),
+        vec![]
+    );
+}
+
+#[test]
+fn machine_type_limits_the_kind_of_tooling() {
+    let turning_tool = Tool::Turning(TurningTool::default());
+    let turning_holder = Holder::Turning(TurningHolder::default());
+    let slot = Slot::default();
+
+    let mill = machine(MachineCapabilities {
+        kind: Some(MachineKind::Mill),
+        ..MachineCapabilities::default()
+    });
+    assert_eq!(
+        check_in_machine(&mill, &slot, None, Some(&turning_holder)),
+        vec![Issue::NotForMachine {
+            kind: MachineKind::Mill
+        }]
+    );
+
+    let lathe = machine(MachineCapabilities {
+        kind: Some(MachineKind::Lathe),
+        ..MachineCapabilities::default()
+    });
+    assert_eq!(
+        check_in_machine(&lathe, &slot, Some(&turning_tool), None),
+        vec![]
+    );
+    let issues = check_in_machine(&lathe, &slot, Some(&end_mill(10.0, 50.0, 0.0)), None);
+    assert_eq!(issues, vec![Issue::NeedsDrivenTool]);
+    assert_eq!(issues[0].severity(), Severity::Warning);
+
+    let mill_turn = machine(MachineCapabilities {
+        kind: Some(MachineKind::MillTurn),
+        ..MachineCapabilities::default()
+    });
+    assert_eq!(
+        check_in_machine(
+            &mill_turn,
+            &slot,
+            Some(&turning_tool),
+            Some(&turning_holder)
+        ),
+        vec![]
+    );
+}
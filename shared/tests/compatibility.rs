@@ -0,0 +1,133 @@
+use shared::compatibility::{check, check_slot, has_errors, Issue, Severity};
+use shared::holders::holder::{
+    Holder, InternalSubcategory, RotatingHolder, TurningHolder, TurningHolderCategory,
+};
+use shared::slot::Slot;
+use shared::tools::tool::{Handedness, RotatingTool, Tool, TurningTool, TurningToolCategory};
+
+fn rotating_tool(connection_diameter: f32, max_rpm: u32) -> Tool {
+    Tool::Rotating(RotatingTool {
+        connection_diameter,
+        max_rpm,
+        ..RotatingTool::default()
+    })
+}
+
+fn rotating_holder(tool_clamping_range: (f32, f32), max_rpm: u32) -> Holder {
+    Holder::Rotating(RotatingHolder {
+        tool_clamping_range,
+        max_rpm,
+        ..RotatingHolder::default()
+    })
+}
+
+fn boring_bar(handedness: Handedness, minimum_bore_diameter: f32) -> Tool {
+    Tool::Turning(TurningTool {
+        category: TurningToolCategory::BoringBar,
+        handedness,
+        minimum_bore_diameter,
+        ..TurningTool::default()
+    })
+}
+
+fn boring_holder(handedness: Handedness, min_bore_diameter: f32) -> Holder {
+    Holder::Turning(TurningHolder {
+        category: TurningHolderCategory::Internal(InternalSubcategory::BoringBar),
+        handedness,
+        min_bore_diameter,
+        ..TurningHolder::default()
+    })
+}
+
+#[test]
+fn matching_parts_have_no_issues() {
+    assert_eq!(
+        check(
+            &rotating_tool(6.0, 20000),
+            &rotating_holder((3.0, 8.0), 25000)
+        ),
+        vec![]
+    );
+    assert_eq!(
+        check(
+            &boring_bar(Handedness::Right, 10.0),
+            &boring_holder(Handedness::Neutral, 8.0)
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn rotating_pairs_check_shank_and_speed() {
+    let issues = check(
+        &rotating_tool(10.0, 30000),
+        &rotating_holder((3.0, 8.0), 20000),
+    );
+
+    assert_eq!(
+        issues,
+        vec![
+            Issue::ShankOutsideClampingRange {
+                connection_diameter: 10.0,
+                clamping_range: (3.0, 8.0),
+            },
+            Issue::HolderSlowerThanTool {
+                tool_max_rpm: 30000,
+                holder_max_rpm: 20000,
+            },
+        ]
+    );
+    assert!(has_errors(&issues));
+}
+
+#[test]
+fn turning_pairs_check_hand_and_bore() {
+    let issues = check(
+        &boring_bar(Handedness::Left, 10.0),
+        &boring_holder(Handedness::Right, 16.0),
+    );
+
+    assert_eq!(
+        issues,
+        vec![
+            Issue::HandednessMismatch {
+                tool: Handedness::Left,
+                holder: Handedness::Right,
+            },
+            Issue::BoreLimitedByHolder {
+                tool_min_bore: 10.0,
+                holder_min_bore: 16.0,
+            },
+        ]
+    );
+    assert_eq!(issues[1].severity(), Severity::Warning);
+}
+
+#[test]
+fn rotating_tool_in_turning_holder_is_an_error() {
+    let issues = check(
+        &rotating_tool(6.0, 20000),
+        &boring_holder(Handedness::Neutral, 8.0),
+    );
+
+    assert_eq!(issues, vec![Issue::WrongHolderType]);
+    assert!(has_errors(&issues));
+}
+
+#[test]
+fn slot_checks_use_the_replacement_parts() {
+    let slot = Slot {
+        tool: Some(rotating_tool(10.0, 20000)),
+        holder: Some(rotating_holder((3.0, 8.0), 20000)),
+        ..Slot::default()
+    };
+    assert!(has_errors(&check_slot(&slot, None, None)));
+
+    let replacement = rotating_tool(6.0, 20000);
+    assert_eq!(check_slot(&slot, Some(&replacement), None), vec![]);
+
+    assert_eq!(
+        check_slot(&Slot::default(), Some(&replacement), None),
+        vec![]
+    );
+}
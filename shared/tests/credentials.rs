@@ -0,0 +1,41 @@
+use shared::credentials::{hash_password, verify_legacy_password, verify_password, Verification};
+
+#[test]
+fn hashed_passwords_verify_and_are_salted() {
+    let hash = hash_password("hunter2").unwrap();
+    assert!(hash.starts_with("$argon2"));
+    assert_ne!(hash, hash_password("hunter2").unwrap());
+
+    assert!(verify_password("hunter2", &hash));
+    assert!(!verify_password("hunter3", &hash));
+}
+
+#[test]
+fn only_hashes_verify_outside_legacy_databases() {
+    assert!(!verify_password("hunter2", "hunter2"));
+    // A mangled hash is not a plain password either
+    let hash = hash_password("hunter2").unwrap();
+    let mangled = &hash[..hash.len() / 2];
+    assert!(!verify_password(mangled, mangled));
+    assert!(!verify_password("hunter2", mangled));
+}
+
+#[test]
+fn plain_passwords_from_older_databases_still_verify() {
+    assert_eq!(
+        verify_legacy_password("hunter2", "hunter2"),
+        Verification::ValidPlaintext
+    );
+    assert_eq!(
+        verify_legacy_password("hunter", "hunter2"),
+        Verification::Invalid
+    );
+    assert_eq!(verify_legacy_password("", "hunter2"), Verification::Invalid);
+
+    let hash = hash_password("hunter2").unwrap();
+    assert_eq!(
+        verify_legacy_password("hunter2", &hash),
+        Verification::Valid
+    );
+    assert_eq!(verify_legacy_password(&hash, &hash), Verification::Invalid);
+}
@@ -0,0 +1,109 @@
+use shared::filter::Filter;
+use shared::holders::holder::{
+    Holder, HydraulicSubcategory, RotatingHolder, RotatingHolderCategory, ShrinkFitSubcategory,
+};
+use shared::magazine::Magazine;
+use shared::slot::Slot;
+use shared::sorting::SortField;
+use shared::tools::tool::{RotatingTool, RotatingToolCategory, Tool};
+
+fn slot(category: RotatingToolCategory, diameter: f32, holder: RotatingHolderCategory) -> Slot {
+    Slot {
+        tool: Some(Tool::Rotating(RotatingTool {
+            category,
+            cutting_diameter: diameter,
+            ..RotatingTool::default()
+        })),
+        holder: Some(Holder::Rotating(RotatingHolder {
+            category: holder,
+            ..RotatingHolder::default()
+        })),
+        ..Slot::default()
+    }
+}
+
+fn magazine() -> Magazine {
+    let shrink_fit = RotatingHolderCategory::ShrinkFit(ShrinkFitSubcategory::ShrinkFit);
+    let hydraulic = RotatingHolderCategory::Hydraulic(HydraulicSubcategory::default());
+    let slots = vec![
+        slot(RotatingToolCategory::EndMill, 8.0, shrink_fit.clone()),
+        slot(RotatingToolCategory::EndMill, 16.0, shrink_fit.clone()),
+        slot(RotatingToolCategory::EndMill, 10.0, hydraulic),
+        slot(RotatingToolCategory::DrillBit, 8.0, shrink_fit),
+        Slot::default(),
+    ];
+    Magazine {
+        capacity: slots.len(),
+        slots,
+        ..Magazine::default()
+    }
+}
+
+fn end_mills_6_to_12_in_shrink_fit() -> Filter {
+    Filter::And(vec![
+        Filter::RotatingToolCategory(RotatingToolCategory::EndMill),
+        Filter::Range {
+            field: SortField::ToolDiameter,
+            min: Some(6.0),
+            max: Some(12.0),
+        },
+        Filter::RotatingHolderCategory(RotatingHolderCategory::ShrinkFit(
+            ShrinkFitSubcategory::ShrinkFit,
+        )),
+    ])
+}
+
+#[test]
+fn all_matches_every_slot() {
+    assert_eq!(
+        Filter::All.matching_indices(&magazine()),
+        vec![0, 1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn and_combines_category_range_and_holder() {
+    assert_eq!(
+        end_mills_6_to_12_in_shrink_fit().matching_indices(&magazine()),
+        vec![0]
+    );
+}
+
+#[test]
+fn or_and_not() {
+    let filter = Filter::Or(vec![
+        Filter::RotatingToolCategory(RotatingToolCategory::DrillBit),
+        Filter::Not(Box::new(Filter::RotatingHolderCategory(
+            RotatingHolderCategory::ShrinkFit(ShrinkFitSubcategory::ShrinkFit),
+        ))),
+    ]);
+
+    assert_eq!(filter.matching_indices(&magazine()), vec![2, 3, 4]);
+}
+
+#[test]
+fn open_range_bounds() {
+    let filter = Filter::Range {
+        field: SortField::ToolDiameter,
+        min: Some(10.0),
+        max: None,
+    };
+
+    assert_eq!(filter.matching_indices(&magazine()), vec![1, 2]);
+}
+
+#[test]
+fn display_reads_like_the_expression() {
+    assert_eq!(
+        end_mills_6_to_12_in_shrink_fit().to_string(),
+        "tool is EndMill AND Tool Diameter between 6 and 12 AND holder is Shrink Fit"
+    );
+}
+
+#[test]
+fn filters_round_trip_through_json() {
+    let filter = Filter::Not(Box::new(end_mills_6_to_12_in_shrink_fit()));
+    let json = serde_json::to_string(&filter).unwrap();
+
+    assert_eq!(serde_json::from_str::<Filter>(&json).unwrap(), filter);
+}
@@ -0,0 +1,114 @@
+use shared::history::{Edit, History, HistoryError};
+use shared::machine::Machine;
+use shared::UserData;
+
+/// A machine that equals every other machine of the same name.
+fn named(name: &str) -> Machine {
+    Machine {
+        uuid: name.to_string(),
+        name: name.to_string(),
+        ..Machine::default()
+    }
+}
+
+fn user_data(names: &[&str]) -> UserData {
+    UserData {
+        machines: names.iter().map(|name| named(name)).collect(),
+        ..UserData::default()
+    }
+}
+
+#[test]
+fn deleting_a_machine_is_undone_and_redone() {
+    let mut data = user_data(&["Mill", "Lathe", "Saw"]);
+    let before = data.clone();
+    data.machines.remove(1);
+    data.selections.selected_machine = None;
+
+    let edit = Edit::between(&before, &data).unwrap();
+    assert_eq!(
+        edit,
+        Edit::DeleteMachine {
+            index: 1,
+            machine: Box::new(named("Lathe")),
+        }
+    );
+    assert_eq!(edit.describe(), "Delete machine 'Lathe'");
+
+    let after = data.clone();
+    let mut history = History::default();
+    history.record(edit);
+    history.undo(&mut data).unwrap();
+    assert_eq!(data.machines, before.machines);
+    history.redo(&mut data).unwrap();
+    assert_eq!(data, after);
+    assert_eq!(history.redo(&mut data), Err(HistoryError::NothingToRedo));
+}
+
+#[test]
+fn selections_are_not_edits() {
+    let before = user_data(&["Mill"]);
+    let mut after = before.clone();
+    after.selections.selected_machine = Some(0);
+
+    assert_eq!(Edit::between(&before, &after), None);
+}
+
+#[test]
+fn edits_to_several_parts_are_undone_together() {
+    let mut data = user_data(&["Mill", "Lathe"]);
+    let before = data.clone();
+    data.machines[0].model = "DMU 50".to_string();
+    data.machines[1].model = "CTX 310".to_string();
+    data.settings.block_incompatible_assemblies = true;
+
+    let edit = Edit::between(&before, &data).unwrap();
+    assert_eq!(edit.describe(), "3 changes");
+    let mut history = History::default();
+    history.record(edit);
+
+    history.undo(&mut data).unwrap();
+    assert_eq!(data, before);
+    assert_eq!(history.next_undo(), None);
+}
+
+#[test]
+fn merged_edits_are_one_step_and_cancel_out() {
+    let mut history = History::default();
+    let mut data = user_data(&["Mill"]);
+    let start = data.clone();
+
+    let mut previous = data.clone();
+    for name in ["Mill 1", "Mill 2", "Mill 3"] {
+        data.machines[0].model = name.to_string();
+        history.record_merging(Edit::between(&previous, &data).unwrap());
+        previous = data.clone();
+    }
+    history.undo(&mut data).unwrap();
+    assert_eq!(data, start);
+    assert_eq!(history.undo(&mut data), Err(HistoryError::NothingToUndo));
+
+    // Changing it back within the same step leaves nothing to undo
+    history.redo(&mut data).unwrap();
+    let previous = data.clone();
+    data.machines[0].model = String::new();
+    history.record_merging(Edit::between(&previous, &data).unwrap());
+    assert_eq!(history.next_undo(), None);
+    assert_eq!(history.next_redo(), None);
+}
+
+#[test]
+fn history_keeps_only_the_newest_edits() {
+    let mut history = History::with_limit(2);
+    let mut data = UserData::default();
+    for name in ["A", "B", "C"] {
+        let before = data.clone();
+        data.machines.push(named(name));
+        history.record(Edit::between(&before, &data).unwrap());
+    }
+
+    history.undo(&mut data).unwrap();
+    history.undo(&mut data).unwrap();
+    assert_eq!(data.machines, vec![named("A")]);
+    assert_eq!(history.undo(&mut data), Err(HistoryError::NothingToUndo));
+}
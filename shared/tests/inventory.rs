@@ -0,0 +1,125 @@
+use shared::custom_traits::GetUuid;
+use shared::holders::holder::{Holder, RotatingHolder};
+use shared::inventory::{HolderInventory, InventoryError, Shortage};
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::placement::SlotAddress;
+
+fn holder(copies: usize) -> Holder {
+    Holder::Rotating(RotatingHolder {
+        duplicates: copies,
+        ..RotatingHolder::default()
+    })
+}
+
+fn machines() -> Vec<Machine> {
+    vec![Machine {
+        magazines: vec![Magazine::new(0, 4)],
+        ..Machine::default()
+    }]
+}
+
+fn stock(inventory: &HolderInventory, machines: &[Machine]) -> Vec<(usize, usize, usize)> {
+    inventory
+        .report(machines)
+        .iter()
+        .map(|stock| (stock.owned, stock.in_use, stock.free()))
+        .collect()
+}
+
+#[test]
+fn placing_uses_up_free_copies() {
+    let holder = holder(2);
+    let uuid = holder.get_uuid();
+    let mut inventory = HolderInventory::default();
+    inventory.add(holder).unwrap();
+    let mut machines = machines();
+
+    for slot in [0, 1] {
+        inventory
+            .place(&mut machines, 0, SlotAddress::new(0, slot), &uuid, "a")
+            .unwrap();
+    }
+    assert_eq!(stock(&inventory, &machines), vec![(2, 2, 0)]);
+
+    assert_eq!(
+        inventory.place(&mut machines, 0, SlotAddress::new(0, 2), &uuid, "a"),
+        Err(InventoryError::NoFreeCopy { owned: 2 })
+    );
+    assert!(machines[0].magazines[0].slots[2].holder.is_none());
+
+    // Putting it where a copy already is does not need another one
+    assert!(inventory
+        .place(&mut machines, 0, SlotAddress::new(0, 1), &uuid, "a")
+        .is_ok());
+}
+
+#[test]
+fn only_free_copies_can_be_removed() {
+    let holder = holder(2);
+    let uuid = holder.get_uuid();
+    let mut inventory = HolderInventory::default();
+    inventory.add(holder).unwrap();
+    let mut machines = machines();
+    inventory
+        .place(&mut machines, 0, SlotAddress::new(0, 0), &uuid, "a")
+        .unwrap();
+
+    assert_eq!(inventory.remove_copy(&mut machines, &uuid), Ok(1));
+    assert_eq!(
+        inventory.remove_copy(&mut machines, &uuid),
+        Err(InventoryError::NoFreeCopy { owned: 1 })
+    );
+    assert_eq!(inventory.add_copy(&mut machines, &uuid), Ok(2));
+    assert_eq!(stock(&inventory, &machines), vec![(2, 1, 1)]);
+    // The copy in the slot follows the count
+    assert_eq!(
+        machines[0].magazines[0].slots[0].holder,
+        inventory.get(&uuid).cloned()
+    );
+}
+
+#[test]
+fn removing_the_last_copy_removes_the_holder() {
+    let holder = holder(1);
+    let uuid = holder.get_uuid();
+    let mut inventory = HolderInventory::default();
+    inventory.add(holder).unwrap();
+
+    assert_eq!(inventory.remove_copy(&mut machines(), &uuid), Ok(0));
+    assert!(inventory.holders().is_empty());
+}
+
+#[test]
+fn adopting_loaded_holders_reports_copies_that_are_not_owned() {
+    let holder = holder(1);
+    let uuid = holder.get_uuid();
+    let mut machines = machines();
+    for slot in 0..3 {
+        machines[0].magazines[0].slots[slot].holder = Some(holder.clone());
+    }
+
+    let mut inventory = HolderInventory::default();
+    let shortages = inventory.adopt_loaded(&machines);
+
+    assert_eq!(
+        shortages,
+        vec![Shortage {
+            uuid: uuid.clone(),
+            summary: holder.summary(),
+            owned: 1,
+            in_use: 3,
+        }]
+    );
+    // The owned count stays as it was recorded
+    assert_eq!(stock(&inventory, &machines), vec![(1, 3, 0)]);
+    assert_eq!(inventory.report(&machines)[0].missing(), 2);
+    assert_eq!(
+        inventory.place(&mut machines, 0, SlotAddress::new(0, 3), &uuid, "a"),
+        Err(InventoryError::NoFreeCopy { owned: 1 })
+    );
+
+    inventory.add_copy(&mut machines, &uuid).unwrap();
+    inventory.add_copy(&mut machines, &uuid).unwrap();
+    assert!(inventory.shortages(&machines).is_empty());
+}
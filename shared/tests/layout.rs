@@ -0,0 +1,143 @@
+use shared::layout::{add_magazine, remove_magazine, resize_magazine, LayoutError, ShrinkPolicy};
+use shared::machine::Machine;
+use shared::magazine::MagazineKind;
+use shared::placement::{PlacementError, SlotAddress};
+use shared::slot::Slot;
+
+fn numbered(tool_number: &str) -> Slot {
+    Slot {
+        tool_number: Some(tool_number.to_string()),
+        ..Slot::default()
+    }
+}
+
+/// Two magazines of four slots. The first holds T1 and T4 in its first and last slot,
+/// the second holds T5 in its first slot.
+fn machine() -> Machine {
+    let mut machine = Machine::default();
+    add_magazine(
+        &mut machine,
+        "Chain".to_string(),
+        4,
+        Some(MagazineKind::Chain),
+    );
+    add_magazine(&mut machine, "Turret".to_string(), 4, None);
+    machine.magazines[0].slots[0] = numbered("T1");
+    machine.magazines[0].slots[3] = numbered("T4");
+    machine.magazines[1].slots[0] = numbered("T5");
+    machine
+}
+
+fn tool_number(machine: &Machine, magazine: usize, slot: usize) -> Option<&str> {
+    machine.magazines[magazine].slots[slot]
+        .tool_number
+        .as_deref()
+}
+
+#[test]
+fn magazines_are_added_with_their_own_size_and_kind() {
+    let mut machine = machine();
+
+    let index = add_magazine(&mut machine, "Arm".to_string(), 2, None);
+
+    assert_eq!(index, 2);
+    assert_eq!(machine.magazine_count, 3);
+    let magazine = &machine.magazines[2];
+    assert_eq!(magazine.index_in_machine, 2);
+    assert_eq!((magazine.capacity, magazine.slots.len()), (2, 2));
+    assert_eq!(machine.magazines[0].kind, Some(MagazineKind::Chain));
+}
+
+#[test]
+fn growing_and_shrinking_an_empty_tail_needs_no_decision() {
+    let mut machine = machine();
+
+    resize_magazine(&mut machine, 1, 6, ShrinkPolicy::Block, "a").unwrap();
+    assert_eq!(machine.magazines[1].slots.len(), 6);
+    assert_eq!(machine.magazines[1].capacity, 6);
+
+    resize_magazine(&mut machine, 1, 1, ShrinkPolicy::Block, "a").unwrap();
+    assert_eq!(machine.magazines[1].slots.len(), 1);
+    assert_eq!(tool_number(&machine, 1, 0), Some("T5"));
+}
+
+#[test]
+fn occupied_tail_is_blocked_relocated_or_unloaded() {
+    let mut machine = machine();
+    let before = machine.clone();
+
+    assert_eq!(
+        resize_magazine(&mut machine, 0, 2, ShrinkPolicy::Block, "a"),
+        Err(LayoutError::Occupied(vec![SlotAddress::new(0, 3)]))
+    );
+    assert_eq!(machine, before);
+
+    let moved_to = resize_magazine(&mut machine, 0, 2, ShrinkPolicy::Relocate, "a").unwrap();
+    assert_eq!(moved_to, vec![SlotAddress::new(0, 1)]);
+    assert_eq!(tool_number(&machine, 0, 1), Some("T4"));
+    assert_eq!(
+        machine.magazines[0].slots[1].last_changed_by.as_deref(),
+        Some("a")
+    );
+
+    // The magazine is full now, so the next slot to go moves to the other magazine
+    let moved_to = resize_magazine(&mut machine, 0, 1, ShrinkPolicy::Relocate, "a").unwrap();
+    assert_eq!(moved_to, vec![SlotAddress::new(1, 1)]);
+    assert_eq!(tool_number(&machine, 1, 1), Some("T4"));
+
+    resize_magazine(&mut machine, 1, 1, ShrinkPolicy::Unload, "a").unwrap();
+    assert_eq!(machine.magazines[1].slots, vec![numbered("T5")]);
+}
+
+#[test]
+fn relocating_needs_enough_empty_slots() {
+    let mut machine = machine();
+    resize_magazine(&mut machine, 1, 1, ShrinkPolicy::Block, "a").unwrap();
+    for slot in 1..3 {
+        machine.magazines[0].slots[slot] = numbered("T");
+    }
+    let before = machine.clone();
+
+    assert_eq!(
+        remove_magazine(&mut machine, 0, ShrinkPolicy::Relocate, "a"),
+        Err(LayoutError::NotEnoughFreeSlots { needed: 4, free: 0 })
+    );
+    assert_eq!(machine, before);
+}
+
+#[test]
+fn removing_a_magazine_renumbers_the_rest() {
+    let mut machine = machine();
+
+    let moved_to = remove_magazine(&mut machine, 0, ShrinkPolicy::Relocate, "a").unwrap();
+
+    assert_eq!(machine.magazine_count, 1);
+    let magazine = &machine.magazines[0];
+    assert_eq!(magazine.name, "Turret");
+    assert_eq!(magazine.index_in_machine, 0);
+    assert_eq!(
+        moved_to,
+        vec![SlotAddress::new(0, 1), SlotAddress::new(0, 2)]
+    );
+    assert_eq!(tool_number(&machine, 0, 1), Some("T1"));
+    assert_eq!(tool_number(&machine, 0, 2), Some("T4"));
+}
+
+#[test]
+fn reserved_slots_are_never_dropped() {
+    let mut machine = machine();
+    machine.magazines[1].slots[3].locked = true;
+
+    for policy in [ShrinkPolicy::Relocate, ShrinkPolicy::Unload] {
+        assert_eq!(
+            resize_magazine(&mut machine, 1, 2, policy, "a"),
+            Err(PlacementError::Locked(SlotAddress::new(1, 3)).into())
+        );
+    }
+    assert_eq!(
+        remove_magazine(&mut machine, 5, ShrinkPolicy::Unload, "a"),
+        Err(PlacementError::NoSuchMagazine(5).into())
+    );
+    assert_eq!(machine.magazines.len(), 2);
+    assert_eq!(machine.magazines[1].slots.len(), 4);
+}
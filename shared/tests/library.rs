@@ -0,0 +1,135 @@
+use shared::custom_traits::GetUuid;
+use shared::library::{LibraryError, RemovePolicy, ToolLibrary};
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::placement::{MachineSlot, PlacementError, SlotAddress};
+use shared::tools::tool::{RotatingTool, RotatingToolCategory, Tool};
+
+fn end_mill(cutting_diameter: f32) -> Tool {
+    Tool::Rotating(RotatingTool {
+        category: RotatingToolCategory::EndMill,
+        cutting_diameter,
+        ..RotatingTool::default()
+    })
+}
+
+fn machine() -> Machine {
+    Machine {
+        magazines: vec![Magazine::new(0, 3)],
+        ..Machine::default()
+    }
+}
+
+/// A library with one tool, loaded into slots 1 and 3 of the first machine.
+fn loaded_twice() -> (ToolLibrary, Vec<Machine>, String) {
+    let tool = end_mill(6.0);
+    let uuid = tool.get_uuid();
+    let mut library = ToolLibrary::default();
+    library.add(tool).unwrap();
+    let mut machines = vec![machine(), machine()];
+    for slot in [0, 2] {
+        library
+            .load(
+                &mut machines[0],
+                SlotAddress::new(0, slot),
+                &uuid,
+                None,
+                "a",
+            )
+            .unwrap();
+    }
+    (library, machines, uuid)
+}
+
+#[test]
+fn editing_a_library_tool_updates_every_loaded_copy() {
+    let (mut library, mut machines, uuid) = loaded_twice();
+
+    let updated = library
+        .update(&mut machines, end_mill(8.0).with_uuid(&uuid), "b")
+        .unwrap();
+
+    assert_eq!(
+        updated,
+        vec![
+            MachineSlot {
+                machine: 0,
+                address: SlotAddress::new(0, 0)
+            },
+            MachineSlot {
+                machine: 0,
+                address: SlotAddress::new(0, 2)
+            },
+        ]
+    );
+    let expected = end_mill(8.0).with_uuid(&uuid);
+    assert_eq!(library.get(&uuid), Some(&expected));
+    for slot in [0, 2] {
+        let slot = &machines[0].magazines[0].slots[slot];
+        assert_eq!(slot.tool.as_ref(), Some(&expected));
+        assert_eq!(slot.last_changed_by.as_deref(), Some("b"));
+    }
+    assert!(machines[0].magazines[0].slots[1].tool.is_none());
+}
+
+#[test]
+fn removing_a_loaded_tool_is_blocked_or_unloads_it() {
+    let (mut library, mut machines, uuid) = loaded_twice();
+
+    match library.remove(&mut machines, &uuid, RemovePolicy::Block, "a") {
+        Err(LibraryError::InUse(uses)) => assert_eq!(uses.len(), 2),
+        other => panic!("expected InUse, got {:?}", other),
+    }
+    assert!(library.get(&uuid).is_some());
+
+    library
+        .remove(&mut machines, &uuid, RemovePolicy::Unload, "a")
+        .unwrap();
+    assert!(library.get(&uuid).is_none());
+    assert!(machines[0].magazines[0]
+        .slots
+        .iter()
+        .all(|slot| slot.tool.is_none()));
+}
+
+#[test]
+fn unloading_never_touches_reserved_slots() {
+    let (mut library, mut machines, uuid) = loaded_twice();
+    machines[0].magazines[0].slots[2].locked = true;
+
+    assert_eq!(
+        library.remove(&mut machines, &uuid, RemovePolicy::Unload, "a"),
+        Err(LibraryError::Placement(PlacementError::Locked(
+            SlotAddress::new(0, 2)
+        )))
+    );
+    assert!(library.get(&uuid).is_some());
+    assert!(machines[0].magazines[0].slots[0].tool.is_some());
+}
+
+#[test]
+fn tools_loaded_before_the_library_are_adopted_once() {
+    let mut machines = vec![machine()];
+    let tool = end_mill(6.0);
+    machines[0].magazines[0].slots[0].tool = Some(tool.clone());
+    machines[0].magazines[0].slots[1].tool = Some(tool.clone());
+
+    let mut library = ToolLibrary::default();
+    library.adopt_loaded(&machines);
+    library.adopt_loaded(&machines);
+
+    assert_eq!(library.tools(), &[tool]);
+}
+
+trait WithUuid {
+    fn with_uuid(self, uuid: &str) -> Self;
+}
+
+impl WithUuid for Tool {
+    fn with_uuid(mut self, uuid: &str) -> Self {
+        if let Tool::Rotating(tool) = &mut self {
+            tool.uuid = uuid.to_string();
+        }
+        self
+    }
+}
@@ -0,0 +1,120 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusqlite::Connection;
+use shared::migrations::{run_migrations, schema_version, Migration, MigrationError};
+
+fn create_machines(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("CREATE TABLE machines (id TEXT PRIMARY KEY, name TEXT NOT NULL)")
+}
+
+fn add_model(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE machines ADD COLUMN model TEXT NOT NULL DEFAULT ''")
+}
+
+/// Changes the table, then fails.
+fn half_done(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE machines ADD COLUMN kind TEXT")?;
+    conn.execute_batch("SELECT * FROM no_such_table")
+}
+
+const FIRST: Migration = Migration {
+    version: 1,
+    description: "create machines",
+    up: create_machines,
+};
+const SECOND: Migration = Migration {
+    version: 2,
+    description: "add model",
+    up: add_model,
+};
+const BROKEN: Migration = Migration {
+    version: 3,
+    description: "add kind",
+    up: half_done,
+};
+
+fn columns(conn: &Connection) -> Vec<String> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM pragma_table_info('machines')")
+        .unwrap();
+    let names = stmt.query_map([], |row| row.get(0)).unwrap();
+    names.collect::<rusqlite::Result<_>>().unwrap()
+}
+
+#[test]
+fn an_existing_database_is_upgraded_in_place() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    assert_eq!(run_migrations(&mut conn, &[FIRST]).unwrap(), 1);
+    conn.execute(
+        "INSERT INTO machines (id, name) VALUES ('mill', 'Mill')",
+        [],
+    )
+    .unwrap();
+
+    assert_eq!(run_migrations(&mut conn, &[FIRST, SECOND]).unwrap(), 2);
+    assert_eq!(columns(&conn), ["id", "name", "model"]);
+    let row: (String, String) = conn
+        .query_row(
+            "SELECT name, model FROM machines WHERE id = 'mill'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(row, ("Mill".to_string(), String::new()));
+
+    // Running again changes nothing
+    assert_eq!(run_migrations(&mut conn, &[FIRST, SECOND]).unwrap(), 2);
+}
+
+#[test]
+fn a_failing_migration_is_rolled_back() {
+    let mut conn = Connection::open_in_memory().unwrap();
+
+    let error = run_migrations(&mut conn, &[FIRST, SECOND, BROKEN]).unwrap_err();
+    assert!(
+        matches!(error, MigrationError::Failed { version: 3, .. }),
+        "{}",
+        error
+    );
+    // The steps before it stay, none of its own changes do
+    assert_eq!(schema_version(&conn).unwrap(), 2);
+    assert_eq!(columns(&conn), ["id", "name", "model"]);
+}
+
+#[test]
+fn a_database_from_a_newer_build_is_refused() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn, &[FIRST, SECOND]).unwrap();
+
+    let error = run_migrations(&mut conn, &[FIRST]).unwrap_err();
+    assert!(
+        matches!(
+            error,
+            MigrationError::DatabaseTooNew {
+                found: 2,
+                latest: 1
+            }
+        ),
+        "{}",
+        error
+    );
+    assert_eq!(schema_version(&conn).unwrap(), 2);
+}
+
+#[test]
+fn migrations_must_be_in_increasing_order() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    for migrations in [[SECOND, FIRST], [FIRST, FIRST]] {
+        let error = run_migrations(&mut conn, &migrations).unwrap_err();
+        assert!(
+            matches!(error, MigrationError::InvalidOrder { version: 1 }),
+            "{}",
+            error
+        );
+    }
+    // Nothing ran, not even the bookkeeping
+    let tables: i64 = conn
+        .query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(tables, 0);
+}
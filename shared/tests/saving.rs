@@ -0,0 +1,80 @@
+use shared::saving::{SaveStatus, SaveTracker, MAX_RETRY_DELAY, SAVE_DEBOUNCE};
+
+#[test]
+fn a_burst_of_edits_is_saved_once_it_settles() {
+    let mut saves = SaveTracker::default();
+    assert_eq!(saves.status(), SaveStatus::Saved);
+    assert_eq!(saves.due_at(), None);
+
+    saves.edited(10.0);
+    saves.edited(10.5);
+    assert_eq!(saves.status(), SaveStatus::Pending);
+    assert!(!saves.is_due(10.5 + SAVE_DEBOUNCE / 2.0));
+    assert!(saves.is_due(10.5 + SAVE_DEBOUNCE));
+
+    saves.started();
+    assert_eq!(saves.status(), SaveStatus::Saving);
+    assert!(!saves.is_due(100.0));
+    saves.succeeded();
+    assert!(!saves.is_dirty());
+    assert_eq!(saves.status(), SaveStatus::Saved);
+}
+
+#[test]
+fn edits_made_while_saving_stay_dirty() {
+    let mut saves = SaveTracker::default();
+    saves.edited(0.0);
+    saves.started();
+    saves.edited(0.2);
+    saves.succeeded();
+
+    assert!(saves.is_dirty());
+    assert_eq!(saves.status(), SaveStatus::Pending);
+    assert_eq!(saves.due_at(), Some(0.2 + SAVE_DEBOUNCE));
+}
+
+#[test]
+fn failed_saves_are_retried_later_each_time() {
+    let mut saves = SaveTracker::default();
+    saves.edited(0.0);
+    let mut delays = vec![];
+    let mut now = SAVE_DEBOUNCE;
+    for _ in 0..7 {
+        assert!(saves.is_due(now));
+        saves.started();
+        saves.failed(now, "offline".to_string());
+        let due_at = saves.due_at().unwrap();
+        delays.push(due_at - now);
+        now = due_at;
+    }
+    assert_eq!(
+        delays,
+        [2.0, 4.0, 8.0, 16.0, 32.0, MAX_RETRY_DELAY, MAX_RETRY_DELAY]
+    );
+    assert_eq!(
+        saves.status(),
+        SaveStatus::Failed {
+            error: "offline".to_string(),
+            retry_at: now,
+        }
+    );
+    assert!(saves.is_dirty());
+
+    saves.started();
+    saves.succeeded();
+    saves.edited(now + 1.0);
+    // A success starts the backoff over
+    assert_eq!(saves.due_at(), Some(now + 1.0 + SAVE_DEBOUNCE));
+}
+
+#[test]
+fn saving_now_skips_the_wait() {
+    let mut saves = SaveTracker::default();
+    saves.edited(5.0);
+    saves.started();
+    saves.failed(5.0, "offline".to_string());
+    assert!(!saves.is_due(5.5));
+
+    saves.save_now();
+    assert!(saves.is_due(5.5));
+}
@@ -0,0 +1,62 @@
+use shared::description::Description;
+use shared::slot::{LengthUnit, Overhang, Slot};
+
+#[test]
+fn legacy_empty_tuple_becomes_empty_slot() {
+    let slot: Slot = serde_json::from_str(r#"[null, null, null, null, {"text": "..."}]"#).unwrap();
+
+    assert_eq!(slot, Slot::default());
+}
+
+#[test]
+fn legacy_tuple_keeps_number_overhang_and_description() {
+    let slot: Slot =
+        serde_json::from_str(r#"["T7", null, null, 42.5, {"text": "Finishing"}]"#).unwrap();
+
+    assert_eq!(slot.tool_number.as_deref(), Some("T7"));
+    assert_eq!(slot.overhang, Some(Overhang::millimeters(42.5)));
+    assert_eq!(
+        slot.description,
+        Some(Description::new("Finishing".to_string()))
+    );
+    assert!(!slot.locked);
+    assert_eq!(slot.last_changed_by, None);
+}
+
+#[test]
+fn slot_round_trips() {
+    let mut slot = Slot {
+        tool_number: Some("T3".to_string()),
+        overhang: Some(Overhang {
+            value: 1.5,
+            unit: LengthUnit::Inch,
+        }),
+        locked: true,
+        ..Slot::default()
+    };
+    slot.touch("operator");
+
+    let json = serde_json::to_string(&slot).unwrap();
+
+    assert_eq!(serde_json::from_str::<Slot>(&json).unwrap(), slot);
+}
+
+#[test]
+fn missing_fields_use_defaults() {
+    let slot: Slot = serde_json::from_str(r#"{"tool_number": "T9"}"#).unwrap();
+
+    assert_eq!(slot.tool_number.as_deref(), Some("T9"));
+    assert_eq!(slot.overhang, None);
+    assert!(!slot.locked);
+}
+
+#[test]
+fn overhang_converts_inches() {
+    let overhang = Overhang {
+        value: 2.0,
+        unit: LengthUnit::Inch,
+    };
+
+    assert_eq!(overhang.to_millimeters(), 50.8);
+    assert_eq!(overhang.to_string(), "2 in");
+}
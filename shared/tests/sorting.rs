@@ -0,0 +1,111 @@
+use shared::magazine::Magazine;
+use shared::slot::{LengthUnit, Overhang, Slot};
+use shared::sorting::{SlotSort, SortDirection, SortField};
+use shared::tools::tool::{RotatingTool, Tool, TurningTool};
+
+fn rotating(cutting_diameter: f32) -> Slot {
+    Slot {
+        tool: Some(Tool::Rotating(RotatingTool {
+            cutting_diameter,
+            ..RotatingTool::default()
+        })),
+        ..Slot::default()
+    }
+}
+
+fn magazine(slots: Vec<Slot>) -> Magazine {
+    Magazine {
+        capacity: slots.len(),
+        slots,
+        ..Magazine::default()
+    }
+}
+
+fn sort(field: SortField, direction: SortDirection) -> SlotSort {
+    SlotSort { field, direction }
+}
+
+#[test]
+fn index_order_is_physical_order() {
+    let magazine = magazine(vec![rotating(8.0), Slot::default(), rotating(2.0)]);
+
+    assert_eq!(SlotSort::default().sorted_indices(&magazine), vec![0, 1, 2]);
+}
+
+#[test]
+fn sorts_by_tool_diameter_in_both_directions() {
+    let magazine = magazine(vec![rotating(8.0), rotating(2.0), rotating(12.0)]);
+
+    assert_eq!(
+        sort(SortField::ToolDiameter, SortDirection::Ascending).sorted_indices(&magazine),
+        vec![1, 0, 2]
+    );
+    assert_eq!(
+        sort(SortField::ToolDiameter, SortDirection::Descending).sorted_indices(&magazine),
+        vec![2, 0, 1]
+    );
+}
+
+#[test]
+fn ties_keep_slot_order() {
+    let magazine = magazine(vec![
+        rotating(6.0),
+        rotating(4.0),
+        rotating(6.0),
+        rotating(4.0),
+    ]);
+
+    assert_eq!(
+        sort(SortField::ToolDiameter, SortDirection::Ascending).sorted_indices(&magazine),
+        vec![1, 3, 0, 2]
+    );
+    assert_eq!(
+        sort(SortField::ToolDiameter, SortDirection::Descending).sorted_indices(&magazine),
+        vec![0, 2, 1, 3]
+    );
+}
+
+#[test]
+fn slots_without_a_value_come_last() {
+    let turning = Slot {
+        tool: Some(Tool::Turning(TurningTool::default())),
+        ..Slot::default()
+    };
+    let magazine = magazine(vec![Slot::default(), rotating(5.0), turning, rotating(3.0)]);
+
+    assert_eq!(
+        sort(SortField::ToolDiameter, SortDirection::Ascending).sorted_indices(&magazine),
+        vec![3, 1, 0, 2]
+    );
+    assert_eq!(
+        sort(SortField::ToolDiameter, SortDirection::Descending).sorted_indices(&magazine),
+        vec![1, 3, 0, 2]
+    );
+}
+
+#[test]
+fn overhang_compares_in_millimeters() {
+    let with_overhang = |value, unit| Slot {
+        overhang: Some(Overhang { value, unit }),
+        ..Slot::default()
+    };
+    let magazine = magazine(vec![
+        with_overhang(30.0, LengthUnit::Millimeter),
+        with_overhang(1.0, LengthUnit::Inch),
+    ]);
+
+    assert_eq!(
+        sort(SortField::Overhang, SortDirection::Ascending).sorted_indices(&magazine),
+        vec![1, 0]
+    );
+}
+
+#[test]
+fn sorting_does_not_move_slots() {
+    let magazine = magazine(vec![rotating(8.0), rotating(2.0)]);
+    let before = magazine.clone();
+
+    sort(SortField::ToolDiameter, SortDirection::Ascending).sorted_indices(&magazine);
+
+    assert_eq!(magazine, before);
+}
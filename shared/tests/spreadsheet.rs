@@ -0,0 +1,238 @@
+use shared::custom_traits::GetUuid;
+use shared::holders::holder::{Holder, RotatingHolder};
+use shared::inventory::HolderInventory;
+use shared::library::ToolLibrary;
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::placement::SlotAddress;
+use shared::slot::{LengthUnit, Overhang, Slot};
+use shared::spreadsheet::{
+    apply_import, export_machine, plan_import, read, Column, SpreadsheetError,
+};
+use shared::tools::tool::{RotatingTool, RotatingToolCategory, Tool};
+
+fn machine() -> Machine {
+    Machine {
+        magazines: vec![Magazine::new(0, 4), Magazine::new(1, 2)],
+        magazine_count: 2,
+        ..Machine::default()
+    }
+}
+
+fn end_mill() -> Tool {
+    Tool::Rotating(RotatingTool {
+        uuid: "end-mill".to_string(),
+        category: RotatingToolCategory::EndMill,
+        cutting_diameter: 12.5,
+        ..RotatingTool::default()
+    })
+}
+
+fn shrink_fit() -> Holder {
+    Holder::Rotating(RotatingHolder {
+        uuid: "shrink-fit".to_string(),
+        taper_type: "HSK-A63".to_string(),
+        tool_clamping_range: (6.0, 12.0),
+        coolant_through: true,
+        ..RotatingHolder::default()
+    })
+}
+
+/// Tool number, tool, holder and overhang of every slot.
+fn contents(machine: &Machine) -> Vec<Slot> {
+    machine
+        .magazines
+        .iter()
+        .flat_map(|magazine| &magazine.slots)
+        .map(|slot| Slot {
+            last_changed_at: None,
+            last_changed_by: None,
+            ..slot.clone()
+        })
+        .collect()
+}
+
+#[test]
+fn an_exported_machine_imports_into_an_empty_one() {
+    let mut source = machine();
+    source.magazines[1].slots[1] = Slot {
+        tool_number: Some("T12".to_string()),
+        tool: Some(end_mill()),
+        holder: Some(shrink_fit()),
+        overhang: Some(Overhang {
+            value: 1.5,
+            unit: LengthUnit::Inch,
+        }),
+        ..Slot::default()
+    };
+    let csv = export_machine(&source).unwrap();
+    assert!(csv.starts_with("magazine,slot,tool_number,tool_type,"));
+
+    let sheet = read(&csv).unwrap();
+    assert_eq!(sheet.row_count(), 6);
+    let mapping = sheet.guess_mapping();
+    assert!(!mapping.contains(&Column::Ignore));
+
+    let mut target = machine();
+    let mut library = ToolLibrary::default();
+    let mut inventory = HolderInventory::default();
+    let plan = plan_import(&sheet, &mapping, &target, &library, &inventory, 0).unwrap();
+    assert_eq!(plan.errors, vec![]);
+    assert_eq!(plan.unchanged, 5);
+    assert_eq!(plan.changes.len(), 1);
+    assert_eq!(plan.changes[0].address, SlotAddress::new(1, 1));
+
+    let shortages = apply_import(&plan, &mut target, &mut library, &mut inventory, "a").unwrap();
+    assert_eq!(shortages, vec![]);
+    assert_eq!(contents(&target), contents(&source));
+    assert_eq!(library.tools(), [end_mill()]);
+    assert_eq!(inventory.holders().len(), 1);
+
+    // Importing the same sheet again changes nothing
+    let plan = plan_import(&sheet, &mapping, &target, &library, &inventory, 0).unwrap();
+    assert_eq!((plan.changes.len(), plan.unchanged), (0, 6));
+}
+
+#[test]
+fn foreign_sheets_are_mapped_and_read_with_decimal_commas() {
+    let sheet = read(
+        "Plats;Nr;Typ;Diameter;Kommentar\n\
+         3;T7;rotating;12,5;Roughing\n\
+         1;T8;;;\n",
+    )
+    .unwrap();
+    assert_eq!(
+        sheet.guess_mapping(),
+        vec![Column::Ignore; 5],
+        "none of the headers are ours"
+    );
+    let mapping = vec![
+        Column::Slot,
+        Column::ToolNumber,
+        Column::ToolType,
+        Column::Tool("cutting_diameter".to_string()),
+        Column::Description,
+    ];
+
+    let target = machine();
+    let plan = plan_import(
+        &sheet,
+        &mapping,
+        &target,
+        &ToolLibrary::default(),
+        &HolderInventory::default(),
+        0,
+    )
+    .unwrap();
+
+    assert_eq!(plan.errors, vec![]);
+    let change = &plan.changes[1];
+    assert_eq!(change.address, SlotAddress::new(0, 0));
+    assert_eq!(change.after.tool_number.as_deref(), Some("T8"));
+    assert_eq!(change.after.tool, None);
+    let change = &plan.changes[0];
+    match &change.after.tool {
+        Some(Tool::Rotating(tool)) => assert_eq!(tool.cutting_diameter, 12.5),
+        tool => panic!("expected a rotating tool, got {:?}", tool),
+    }
+    assert_eq!(change.address, SlotAddress::new(0, 2));
+    assert_eq!(plan.changes.len(), 2);
+}
+
+#[test]
+fn bad_rows_are_reported_and_the_rest_planned() {
+    let mut target = machine();
+    target.magazines[0].slots[3].locked = true;
+    let sheet = read(
+        "magazine,slot,tool_number,tool_type,tool.cutting_diameter,tool.lead_angle\n\
+         1,1,T1,Rotating,ten,\n\
+         1,2,T2,Drilling,,\n\
+         2,1,T3,,3,\n\
+         1,4,T4,,,\n\
+         3,1,T5,,,\n\
+         1,3,T6,Rotating,,45\n\
+         2,2,T7,,,\n\
+         2,2,T8,,,\n",
+    )
+    .unwrap();
+
+    let plan = plan_import(
+        &sheet,
+        &sheet.guess_mapping(),
+        &target,
+        &ToolLibrary::default(),
+        &HolderInventory::default(),
+        0,
+    )
+    .unwrap();
+
+    let errors: Vec<String> = plan.errors.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Line 2: tool.cutting_diameter 'ten' is not a number",
+            "Line 3: 'Drilling' is not Rotating or Turning",
+            "Line 4: tool.cutting_diameter is filled in but tool_type is empty",
+            "Line 5: magazine 1, slot 4 is reserved",
+            "Line 6: Magazine 3 does not exist",
+            "Line 7: Rotating tools have no lead_angle",
+            "Line 9: magazine 2, slot 2 is already set by line 8",
+        ]
+    );
+    assert_eq!(plan.changes.len(), 1);
+    assert_eq!(plan.changes[0].line, 8);
+}
+
+#[test]
+fn known_tools_must_match_their_library_entry() {
+    let mut library = ToolLibrary::default();
+    library.add(end_mill()).unwrap();
+    let uuid = end_mill().get_uuid();
+    let target = machine();
+    let inventory = HolderInventory::default();
+    let header = "slot,tool_type,tool.uuid,tool.cutting_diameter\n";
+
+    // Unmapped fields come from the library, so the tool is the library's
+    let same = read(&format!("{header}1,Rotating,{uuid},12.5\n")).unwrap();
+    let plan = plan_import(
+        &same,
+        &same.guess_mapping(),
+        &target,
+        &library,
+        &inventory,
+        0,
+    )
+    .unwrap();
+    assert_eq!(plan.changes[0].after.tool, Some(end_mill()));
+
+    let changed = read(&format!("{header}1,Rotating,{uuid},10\n")).unwrap();
+    let plan = plan_import(
+        &changed,
+        &changed.guess_mapping(),
+        &target,
+        &library,
+        &inventory,
+        0,
+    )
+    .unwrap();
+    assert_eq!(plan.changes, vec![]);
+    assert!(plan.errors[0].message.contains("library entry"));
+
+    let no_slot = read(header).unwrap();
+    assert_eq!(
+        plan_import(
+            &no_slot,
+            &[
+                Column::Ignore,
+                Column::Ignore,
+                Column::Ignore,
+                Column::Ignore
+            ],
+            &target,
+            &library,
+            &inventory,
+            0
+        ),
+        Err(SpreadsheetError::NoSlotColumn)
+    );
+}
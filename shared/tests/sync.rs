@@ -0,0 +1,308 @@
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::placement::SlotAddress;
+use shared::slot::Slot;
+use shared::sync::{
+    self, Conflict, Resolution, RevisedMachine, SyncBase, SyncOperation, SyncOutcome,
+};
+
+fn machine(uuid: &str) -> Machine {
+    Machine {
+        uuid: uuid.to_string(),
+        name: uuid.to_string(),
+        magazine_count: 1,
+        magazines: vec![Magazine::new(0, 3)],
+        ..Machine::default()
+    }
+}
+
+fn numbered(tool_number: &str) -> Slot {
+    Slot {
+        tool_number: Some(tool_number.to_string()),
+        ..Slot::default()
+    }
+}
+
+fn base(machines: &[Machine]) -> SyncBase {
+    SyncBase {
+        machines: machines.iter().cloned().map(RevisedMachine::new).collect(),
+    }
+}
+
+#[test]
+fn changes_on_one_side_are_taken_without_conflicts() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+
+    let mut local = agreed.clone();
+    local.magazines[0].slots[0] = numbered("T1");
+    let mut remote = base.machines[0].clone();
+    remote.machine.magazines[0].slots[1] = numbered("T2");
+    remote.slot_revisions[0][1] = 1;
+    remote.machine.name = "Mill".to_string();
+    remote.revision = 1;
+
+    let plan = sync::plan(&base, &[local], &[remote]);
+
+    assert!(plan.conflicts.is_empty());
+    assert_eq!(plan.merged[0].name, "Mill");
+    assert_eq!(plan.merged[0].magazines[0].slots[0], numbered("T1"));
+    assert_eq!(plan.merged[0].magazines[0].slots[1], numbered("T2"));
+    assert_eq!(
+        plan.operations,
+        [SyncOperation::UpdateSlot {
+            machine_id: "mill".to_string(),
+            address: SlotAddress::new(0, 0),
+            slot: Box::new(numbered("T1")),
+            expected: 0,
+        }]
+    );
+
+    let finished = plan.finish(&base, &[SyncOutcome::Applied { revision: 1 }]);
+    assert_eq!(finished.machines[0].machine, plan.merged[0]);
+    assert_eq!(finished.machines[0].slot_revisions, [[1, 1, 0]]);
+    assert_eq!(sync::pending_changes(&finished, &plan.merged), 0);
+}
+
+#[test]
+fn a_slot_changed_on_both_sides_is_resolved_explicitly() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+
+    let mut local = agreed.clone();
+    local.magazines[0].slots[0] = numbered("T1");
+    let mut remote = base.machines[0].clone();
+    remote.machine.magazines[0].slots[0] = numbered("T9");
+    remote.slot_revisions[0][0] = 4;
+    assert_eq!(sync::pending_changes(&base, &[local.clone()]), 1);
+
+    let mut plan = sync::plan(&base, &[local.clone()], &[remote.clone()]);
+    assert!(plan.operations.is_empty());
+    assert!(matches!(
+        &plan.conflicts[..],
+        [Conflict::Slot { revision: 4, .. }]
+    ));
+    assert_eq!(
+        plan.conflicts[0].to_string(),
+        "mill, magazine 1, slot 1 was changed here and on the server"
+    );
+
+    let mut keep_remote = plan.clone();
+    keep_remote.resolve(0, Resolution::KeepRemote);
+    assert!(keep_remote.operations.is_empty());
+    assert_eq!(keep_remote.merged[0], remote.machine);
+
+    plan.resolve(0, Resolution::KeepLocal);
+    assert_eq!(plan.merged[0], local);
+    assert_eq!(
+        plan.operations,
+        [SyncOperation::UpdateSlot {
+            machine_id: "mill".to_string(),
+            address: SlotAddress::new(0, 0),
+            slot: Box::new(numbered("T1")),
+            expected: 4,
+        }]
+    );
+}
+
+#[test]
+fn machines_changed_on_both_sides_conflict_as_a_whole() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+
+    let mut local = agreed.clone();
+    local.magazines[0].capacity = 4;
+    local.magazines[0].slots.push(numbered("T4"));
+    let mut remote = base.machines[0].clone();
+    remote.machine.name = "Mill".to_string();
+    remote.revision = 2;
+
+    let mut plan = sync::plan(&base, &[local.clone()], &[remote.clone()]);
+    assert!(matches!(&plan.conflicts[..], [Conflict::Machine { .. }]));
+
+    plan.resolve(0, Resolution::KeepLocal);
+    let mut shape = local.clone();
+    shape.magazines[0].slots.clear();
+    assert_eq!(
+        plan.operations,
+        [
+            SyncOperation::UpdateMachine {
+                machine: Box::new(shape),
+                expected: 2,
+            },
+            SyncOperation::UpdateSlot {
+                machine_id: "mill".to_string(),
+                address: SlotAddress::new(0, 3),
+                slot: Box::new(numbered("T4")),
+                expected: 0,
+            },
+        ]
+    );
+
+    let finished = plan.finish(
+        &base,
+        &[
+            SyncOutcome::Applied { revision: 3 },
+            SyncOutcome::Applied { revision: 1 },
+        ],
+    );
+    assert_eq!(finished.machines[0].machine, local);
+    assert_eq!(finished.machines[0].revision, 3);
+    assert_eq!(finished.machines[0].slot_revisions, [[0, 0, 0, 1]]);
+}
+
+#[test]
+fn shrinking_a_magazine_conflicts_when_it_drops_a_changed_slot() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+
+    let mut local = agreed.clone();
+    local.magazines[0].capacity = 2;
+    local.magazines[0].slots.pop();
+    let mut remote = base.machines[0].clone();
+    remote.machine.magazines[0].slots[2] = numbered("T3");
+    remote.slot_revisions[0][2] = 1;
+
+    let plan = sync::plan(&base, &[local.clone()], &[remote]);
+    assert!(matches!(&plan.conflicts[..], [Conflict::Machine { .. }]));
+
+    // Without the remote change the magazine just shrinks
+    let plan = sync::plan(&base, &[local.clone()], &base.machines);
+    assert!(plan.conflicts.is_empty());
+    assert_eq!(plan.merged, [local]);
+}
+
+#[test]
+fn creations_and_deletions_follow_the_side_that_made_them() {
+    let kept = machine("mill");
+    let deleted_remotely = machine("lathe");
+    let deleted_locally = machine("router");
+    let base = base(&[
+        kept.clone(),
+        deleted_remotely.clone(),
+        deleted_locally.clone(),
+    ]);
+    let created_locally = machine("new");
+    let created_remotely = RevisedMachine::new(machine("other"));
+
+    let plan = sync::plan(
+        &base,
+        &[kept.clone(), deleted_remotely, created_locally.clone()],
+        &[
+            base.machines[0].clone(),
+            base.machines[2].clone(),
+            created_remotely.clone(),
+        ],
+    );
+
+    assert!(plan.conflicts.is_empty());
+    assert_eq!(
+        plan.merged,
+        [kept, created_locally.clone(), created_remotely.machine]
+    );
+    assert_eq!(
+        plan.operations,
+        [
+            SyncOperation::CreateMachine {
+                machine: Box::new(created_locally),
+            },
+            SyncOperation::DeleteMachine {
+                machine_id: "router".to_string(),
+                expected: 0,
+                expected_slots: vec![vec![0, 0, 0]],
+            },
+        ]
+    );
+}
+
+#[test]
+fn deleting_what_the_other_side_changed_conflicts() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+    let mut changed = agreed.clone();
+    changed.name = "Mill".to_string();
+
+    let mut plan = sync::plan(&base, &[changed.clone()], &[]);
+    assert!(matches!(
+        &plan.conflicts[..],
+        [Conflict::DeletedRemotely { .. }]
+    ));
+    plan.resolve(0, Resolution::KeepLocal);
+    assert_eq!(
+        plan.operations,
+        [SyncOperation::CreateMachine {
+            machine: Box::new(changed)
+        }]
+    );
+
+    let mut remote = base.machines[0].clone();
+    remote.revision = 1;
+    let mut plan = sync::plan(&base, &[], &[remote.clone()]);
+    assert!(matches!(
+        &plan.conflicts[..],
+        [Conflict::DeletedLocally { .. }]
+    ));
+    plan.resolve(0, Resolution::KeepRemote);
+    assert!(plan.operations.is_empty());
+    assert_eq!(plan.merged, [remote.machine]);
+}
+
+#[test]
+fn refused_operations_keep_the_old_base() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+    let mut local = agreed.clone();
+    local.magazines[0].slots[0] = numbered("T1");
+
+    let plan = sync::plan(&base, &[local.clone()], &base.machines);
+    let finished = plan.finish(&base, &[SyncOutcome::Conflict { current: None }]);
+
+    assert_eq!(finished, base);
+    assert_eq!(sync::pending_changes(&finished, &[local]), 1);
+}
+
+#[test]
+fn edits_made_while_resolving_are_merged_in_before_applying() {
+    let agreed = machine("mill");
+    let base = base(&[agreed.clone()]);
+    let mut local = agreed.clone();
+    local.magazines[0].slots[0] = numbered("T1");
+    let mut remote = base.machines[0].clone();
+    remote.machine.magazines[0].slots[0] = numbered("T9");
+    remote.machine.magazines[0].slots[2] = numbered("T8");
+    remote.slot_revisions[0][0] = 4;
+    remote.slot_revisions[0][2] = 4;
+
+    let mut plan = sync::plan(&base, &[local.clone()], &[remote]);
+    plan.resolve(0, Resolution::KeepRemote);
+    // Edited while the conflict waited
+    local.magazines[0].slots[1] = numbered("T5");
+    local.magazines[0].slots[2] = numbered("T6");
+
+    let mut replanned = plan.replan(&base, &[local]);
+    // The slot resolved before stays resolved, the one edited on both sides since is new
+    assert!(matches!(
+        &replanned.conflicts[..],
+        [Conflict::Slot { address, .. }] if *address == SlotAddress::new(0, 2)
+    ));
+    replanned.resolve(0, Resolution::KeepLocal);
+    let slots = &replanned.merged[0].magazines[0].slots;
+    assert_eq!(slots[..], [numbered("T9"), numbered("T5"), numbered("T6")]);
+    assert_eq!(
+        replanned.operations,
+        [
+            SyncOperation::UpdateSlot {
+                machine_id: "mill".to_string(),
+                address: SlotAddress::new(0, 1),
+                slot: Box::new(numbered("T5")),
+                expected: 0,
+            },
+            SyncOperation::UpdateSlot {
+                machine_id: "mill".to_string(),
+                address: SlotAddress::new(0, 2),
+                slot: Box::new(numbered("T6")),
+                expected: 4,
+            }
+        ]
+    );
+}
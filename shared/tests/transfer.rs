@@ -0,0 +1,322 @@
+use shared::compatibility::Issue;
+use shared::custom_traits::GetUuid;
+use shared::description::Description;
+use shared::history::{Edit, History};
+use shared::holders::holder::{Holder, RotatingHolder};
+use shared::inventory::{HolderInventory, InventoryError};
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::placement::{MachineSlot, PlacementError, SlotAddress};
+use shared::slot::{Overhang, Slot};
+use shared::tools::tool::{RotatingTool, Tool};
+use shared::transfer::{apply, free_slot, Transfer, TransferError};
+use shared::UserData;
+
+fn at(machine: usize, magazine: usize, slot: usize) -> MachineSlot {
+    MachineSlot {
+        machine,
+        address: SlotAddress::new(magazine, slot),
+    }
+}
+
+fn slot(machines: &[Machine], at: MachineSlot) -> &Slot {
+    &machines[at.machine].magazines[at.address.magazine].slots[at.address.slot]
+}
+
+/// Two machines with two magazines of three slots each. The first slot of the first
+/// machine holds T1 in the only copy of a holder.
+fn setup() -> (Vec<Machine>, HolderInventory) {
+    let machine = Machine {
+        magazines: vec![Magazine::new(0, 3), Magazine::new(1, 3)],
+        ..Machine::default()
+    };
+    let mut machines = vec![machine.clone(), machine];
+    let holder = Holder::Rotating(RotatingHolder::default());
+    let mut inventory = HolderInventory::default();
+    inventory.add(holder.clone()).unwrap();
+    machines[0].magazines[0].slots[0] = Slot {
+        tool_number: Some("T1".to_string()),
+        tool: Some(Tool::Rotating(RotatingTool::default())),
+        holder: Some(holder),
+        overhang: Some(Overhang::millimeters(30.0)),
+        ..Slot::default()
+    };
+    (machines, inventory)
+}
+
+/// Carries out `transfer` on `data` and records it in `history`, the way the application
+/// does.
+fn apply_recorded(
+    data: &mut UserData,
+    history: &mut History,
+    transfer: Transfer,
+) -> Result<(), TransferError> {
+    let before = data.clone();
+    apply(
+        &mut data.machines,
+        &data.holder_inventory,
+        transfer,
+        "a",
+        false,
+    )?;
+    history.record(Edit::between(&before, data).expect("the transfer changed something"));
+    Ok(())
+}
+
+#[test]
+fn move_carries_the_contents_across_machines_and_undoes() {
+    let (machines, holder_inventory) = setup();
+    let mut data = UserData {
+        machines,
+        holder_inventory,
+        ..UserData::default()
+    };
+    let before = data.clone();
+    let mut history = History::default();
+    let from = at(0, 0, 0);
+    let to = at(1, 1, 2);
+
+    apply_recorded(&mut data, &mut history, Transfer::Move { from, to }).unwrap();
+
+    assert!(slot(&data.machines, from).is_empty());
+    let moved = slot(&data.machines, to);
+    assert_eq!(moved.tool_number.as_deref(), Some("T1"));
+    assert_eq!(moved.overhang, Some(Overhang::millimeters(30.0)));
+    assert_eq!(moved.last_changed_by.as_deref(), Some("a"));
+
+    // Both machines change back in one step
+    history.undo(&mut data).unwrap();
+    assert_eq!(data, before);
+}
+
+#[test]
+fn move_needs_an_empty_target_and_swap_does_not() {
+    let (mut machines, inventory) = setup();
+    machines[0].magazines[0].slots[1].tool_number = Some("T2".to_string());
+    let first = at(0, 0, 0);
+    let second = at(0, 0, 1);
+
+    assert_eq!(
+        apply(
+            &mut machines,
+            &inventory,
+            Transfer::Move {
+                from: first,
+                to: second
+            },
+            "a",
+            false
+        ),
+        Err(TransferError::Occupied(second.address))
+    );
+    assert_eq!(
+        apply(
+            &mut machines,
+            &inventory,
+            Transfer::Move {
+                from: at(0, 1, 0),
+                to: at(0, 1, 1)
+            },
+            "a",
+            false
+        ),
+        Err(TransferError::Empty(SlotAddress::new(1, 0)))
+    );
+
+    apply(
+        &mut machines,
+        &inventory,
+        Transfer::Swap {
+            a: first,
+            b: second,
+        },
+        "a",
+        false,
+    )
+    .unwrap();
+    assert_eq!(slot(&machines, first).tool_number.as_deref(), Some("T2"));
+    assert_eq!(slot(&machines, second).tool_number.as_deref(), Some("T1"));
+}
+
+#[test]
+fn reserved_slots_keep_their_contents_and_reservation() {
+    let (mut machines, inventory) = setup();
+    machines[0].magazines[1].slots[0].locked = true;
+    let before = machines.clone();
+
+    let into_reserved = Transfer::Swap {
+        a: at(0, 0, 0),
+        b: at(0, 1, 0),
+    };
+    assert_eq!(
+        apply(&mut machines, &inventory, into_reserved, "a", false),
+        Err(PlacementError::Locked(SlotAddress::new(1, 0)).into())
+    );
+    assert_eq!(machines, before);
+
+    // The reservation stays with the slot when its neighbour moves in
+    machines[0].magazines[1].slots[1].locked = true;
+    machines[0].magazines[1].slots[0].locked = false;
+    apply(
+        &mut machines,
+        &inventory,
+        Transfer::Move {
+            from: at(0, 0, 0),
+            to: at(0, 1, 0),
+        },
+        "a",
+        false,
+    )
+    .unwrap();
+    assert!(!slot(&machines, at(0, 1, 0)).locked);
+    assert!(slot(&machines, at(0, 1, 1)).locked);
+}
+
+#[test]
+fn copy_needs_a_free_holder_copy() {
+    let (mut machines, mut inventory) = setup();
+    let copy = Transfer::Copy {
+        from: at(0, 0, 0),
+        to: at(1, 0, 0),
+    };
+
+    assert_eq!(
+        apply(&mut machines, &inventory, copy, "a", false),
+        Err(InventoryError::NoFreeCopy { owned: 1 }.into())
+    );
+
+    let uuid = inventory.holders()[0].get_uuid();
+    inventory.add_copy(&mut machines, &uuid).unwrap();
+    let mut data = UserData {
+        machines,
+        holder_inventory: inventory,
+        ..UserData::default()
+    };
+    let mut history = History::default();
+    apply_recorded(&mut data, &mut history, copy).unwrap();
+
+    let copied = slot(&data.machines, at(1, 0, 0));
+    assert_eq!(copied.tool_number, None);
+    assert_eq!(copied.tool, slot(&data.machines, at(0, 0, 0)).tool);
+    assert_eq!(data.holder_inventory.report(&data.machines)[0].free(), 0);
+
+    history.undo(&mut data).unwrap();
+    assert!(slot(&data.machines, at(1, 0, 0)).is_empty());
+    assert_eq!(data.holder_inventory.report(&data.machines)[0].free(), 1);
+}
+
+#[test]
+fn the_free_slot_of_a_machine_skips_full_and_reserved_slots() {
+    let (mut machines, _) = setup();
+    machines[1].magazines[0].slots[0].tool_number = Some("T2".to_string());
+    machines[1].magazines[0].slots[1].locked = true;
+
+    assert_eq!(free_slot(&machines, 0), Ok(at(0, 0, 1)));
+    assert_eq!(free_slot(&machines, 1), Ok(at(1, 0, 2)));
+
+    machines[1].magazines[0].slots[2].locked = true;
+    for slot in &mut machines[1].magazines[1].slots {
+        slot.tool_number = Some("T3".to_string());
+    }
+    assert_eq!(free_slot(&machines, 1), Err(TransferError::NoFreeSlot(1)));
+    assert_eq!(
+        free_slot(&machines, 2),
+        Err(PlacementError::NoSuchMachine(2).into())
+    );
+}
+
+#[test]
+fn a_slot_with_only_a_description_is_not_moved_over() {
+    let (mut machines, inventory) = setup();
+    let note = at(0, 0, 1);
+    machines[0].magazines[0].slots[1].description =
+        Some(Description::new("Keep for the probe".to_string()));
+
+    assert_eq!(
+        apply(
+            &mut machines,
+            &inventory,
+            Transfer::Move {
+                from: at(0, 0, 0),
+                to: note
+            },
+            "a",
+            false
+        ),
+        Err(TransferError::Occupied(note.address))
+    );
+    assert_eq!(free_slot(&machines, 0), Ok(at(0, 0, 2)));
+
+    // Swapping keeps the description, in the other slot
+    apply(
+        &mut machines,
+        &inventory,
+        Transfer::Swap {
+            a: at(0, 0, 0),
+            b: note,
+        },
+        "a",
+        false,
+    )
+    .unwrap();
+    let swapped = slot(&machines, at(0, 0, 0));
+    assert_eq!(swapped.tool, None);
+    assert_eq!(
+        swapped.description.as_ref().map(|d| d.text.as_str()),
+        Some("Keep for the probe")
+    );
+}
+
+#[test]
+fn moving_into_a_machine_that_cannot_take_the_assembly_is_blocked() {
+    let (mut machines, inventory) = setup();
+    if let Some(Holder::Rotating(holder)) = &mut machines[0].magazines[0].slots[0].holder {
+        holder.tool_clamping_range = (3.0, 6.0);
+        holder.taper_type = "SK40".to_string();
+    }
+    machines[1].capabilities.spindle_taper = "HSK-A63".to_string();
+    let before = machines.clone();
+    let across = Transfer::Move {
+        from: at(0, 0, 0),
+        to: at(1, 0, 0),
+    };
+
+    let refused = apply(&mut machines, &inventory, across, "a", true);
+    assert_eq!(
+        refused,
+        Err(TransferError::Incompatible {
+            to: at(1, 0, 0),
+            issue: Issue::TaperMismatch {
+                spindle: "HSK-A63".to_string(),
+                holder: "SK40".to_string(),
+            },
+        })
+    );
+    assert_eq!(machines, before);
+    // Swapping it over is refused as well
+    let swap = Transfer::Swap {
+        a: at(0, 0, 0),
+        b: at(1, 0, 0),
+    };
+    assert!(matches!(
+        apply(&mut machines, &inventory, swap, "a", true),
+        Err(TransferError::Incompatible { .. })
+    ));
+    assert_eq!(machines, before);
+
+    // Within the machine it came from it still moves, and without blocking it goes across
+    let within = Transfer::Move {
+        from: at(0, 0, 0),
+        to: at(0, 1, 0),
+    };
+    apply(&mut machines, &inventory, within, "a", true).unwrap();
+    let back = Transfer::Move {
+        from: at(0, 1, 0),
+        to: at(1, 0, 0),
+    };
+    apply(&mut machines, &inventory, back, "a", false).unwrap();
+    assert_eq!(
+        slot(&machines, at(1, 0, 0)).tool_number.as_deref(),
+        Some("T1")
+    );
+}
@@ -0,0 +1,149 @@
+use shared::description::Description;
+use shared::holders::holder::Holder;
+use shared::machine::MachineCapabilities;
+use shared::slot::{Overhang, Slot};
+use shared::tools::tool::{RotatingToolCategory, Tool, TurningToolCategory};
+use shared::upgrade::{
+    upgrade_user_data, user_data_from_json, UpgradeError, CURRENT_USER_DATA_VERSION,
+};
+use shared::UserData;
+
+const V0_EMPTY: &str = include_str!("fixtures/user_data_v0_empty.json");
+const V0_WITH_MACHINE: &str = include_str!("fixtures/user_data_v0_with_machine.json");
+const V1_WITH_MACHINE: &str = include_str!("fixtures/user_data_v1_with_machine.json");
+const V2_WITH_MACHINE: &str = include_str!("fixtures/user_data_v2_with_machine.json");
+
+#[test]
+fn empty_v0_blob_upgrades_to_current_version() {
+    let user_data = user_data_from_json(V0_EMPTY).unwrap();
+
+    assert_eq!(user_data.version, CURRENT_USER_DATA_VERSION);
+    assert!(user_data.machines.is_empty());
+    assert_eq!(user_data.selections.selected_machine, None);
+}
+
+#[test]
+fn v0_machine_keeps_its_contents_and_gets_a_uuid() {
+    let user_data = user_data_from_json(V0_WITH_MACHINE).unwrap();
+
+    assert_eq!(user_data.version, CURRENT_USER_DATA_VERSION);
+    assert_eq!(user_data.selections.selected_machine, Some(0));
+    assert!(user_data.settings.dark_mode);
+    assert_eq!(user_data.favorite_color.to_array(), [200, 30, 40, 255]);
+
+    let machine = &user_data.machines[0];
+    assert_eq!(machine.name, "DMU 50");
+    assert!(uuid::Uuid::parse_str(&machine.uuid).is_ok());
+
+    let magazine = &machine.magazines[0];
+    assert_eq!(magazine.capacity, 3);
+    assert_eq!(magazine.slots.len(), 3);
+
+    let slot = &magazine.slots[0];
+    assert_eq!(slot.tool_number.as_deref(), Some("T1"));
+    match &slot.tool {
+        Some(Tool::Rotating(tool)) => {
+            assert_eq!(tool.uuid, "8f0d5e2a-4a1b-4c52-9a57-2f6f3c1d9b10");
+            assert_eq!(tool.category, RotatingToolCategory::EndMill);
+        }
+        other => panic!("expected a rotating tool, got {:?}", other),
+    }
+    match &slot.holder {
+        Some(Holder::Rotating(holder)) => assert_eq!(holder.taper_type, "HSK-A63"),
+        other => panic!("expected a rotating holder, got {:?}", other),
+    }
+    assert_eq!(slot.overhang, Some(Overhang::millimeters(35.0)));
+    assert_eq!(
+        slot.description,
+        Some(Description::new("Roughing".to_string()))
+    );
+    assert!(!slot.locked);
+
+    match &magazine.slots[1].tool {
+        Some(Tool::Turning(tool)) => {
+            assert_eq!(tool.category, TurningToolCategory::ExternalTurningTool)
+        }
+        other => panic!("expected a turning tool, got {:?}", other),
+    }
+    assert!(magazine.slots[2].is_empty());
+    assert_eq!(magazine.slots[2].description, None);
+}
+
+#[test]
+fn v1_content_tuples_become_slots() {
+    let user_data = user_data_from_json(V1_WITH_MACHINE).unwrap();
+
+    assert_eq!(user_data.version, CURRENT_USER_DATA_VERSION);
+    let machine = &user_data.machines[0];
+    assert_eq!(machine.uuid, "6a2f0c3e-5d1b-4b7e-9f3a-2c8d1e4b7a90");
+    let slots = &machine.magazines[0].slots;
+    assert_eq!(slots.len(), 3);
+    assert_eq!(slots[0].tool_number.as_deref(), Some("T1"));
+    assert_eq!(slots[0].overhang, Some(Overhang::millimeters(35.0)));
+    assert_eq!(slots[1].tool_number.as_deref(), Some("T2"));
+    assert_eq!(slots[2], Slot::default());
+}
+
+#[test]
+fn v2_machines_get_unknown_capabilities() {
+    let user_data = user_data_from_json(V2_WITH_MACHINE).unwrap();
+
+    assert_eq!(user_data.version, CURRENT_USER_DATA_VERSION);
+    let machine = &user_data.machines[0];
+    assert_eq!(machine.name, "DMU 50");
+    assert_eq!(machine.capabilities, MachineCapabilities::default());
+    assert_eq!(machine.magazines[0].kind, None);
+}
+
+#[test]
+fn existing_machine_uuids_are_kept() {
+    let mut value: serde_json::Value = serde_json::from_str(V0_WITH_MACHINE).unwrap();
+    value["machines"][0]["uuid"] = "b3a1c2d4-0000-4000-8000-000000000001".into();
+
+    let user_data = upgrade_user_data(value).unwrap();
+
+    assert_eq!(
+        user_data.machines[0].uuid,
+        "b3a1c2d4-0000-4000-8000-000000000001"
+    );
+}
+
+#[test]
+fn current_blob_round_trips_unchanged() {
+    let user_data = user_data_from_json(V0_WITH_MACHINE).unwrap();
+    let json = serde_json::to_string(&user_data).unwrap();
+
+    assert_eq!(user_data_from_json(&json).unwrap(), user_data);
+}
+
+#[test]
+fn default_user_data_is_current() {
+    assert_eq!(UserData::default().version, CURRENT_USER_DATA_VERSION);
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    let mut value: serde_json::Value = serde_json::from_str(V0_EMPTY).unwrap();
+    value["version"] = (CURRENT_USER_DATA_VERSION + 1).into();
+
+    assert!(matches!(
+        upgrade_user_data(value),
+        Err(UpgradeError::TooNew { .. })
+    ));
+}
+
+#[test]
+fn malformed_blobs_are_rejected() {
+    assert!(matches!(
+        upgrade_user_data(serde_json::json!([1, 2, 3])),
+        Err(UpgradeError::NotAnObject)
+    ));
+    assert!(matches!(
+        upgrade_user_data(serde_json::json!({ "version": "one" })),
+        Err(UpgradeError::InvalidVersion)
+    ));
+    assert!(matches!(
+        user_data_from_json("{ not json"),
+        Err(UpgradeError::Deserialize(_))
+    ));
+}
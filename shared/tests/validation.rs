@@ -0,0 +1,116 @@
+use shared::holders::holder::{
+    EndMillSubcategory, Holder, RotatingHolder, RotatingHolderCategory, ThreadingSubcategory,
+    TurningHolder, TurningHolderCategory,
+};
+use shared::machine::Machine;
+use shared::magazine::Magazine;
+use shared::placement::{place_holder, place_tool, PlacementError, SlotAddress};
+use shared::tools::tool::{RotatingTool, RotatingToolCategory, Tool};
+
+fn end_mill() -> RotatingTool {
+    RotatingTool {
+        category: RotatingToolCategory::EndMill,
+        ..RotatingTool::default()
+    }
+}
+
+fn fields(errors: Vec<shared::validation::FieldError>) -> Vec<&'static str> {
+    errors.into_iter().map(|error| error.field).collect()
+}
+
+#[test]
+fn defaults_need_a_category() {
+    assert_eq!(
+        fields(RotatingTool::default().validate().unwrap_err()),
+        vec!["Category"]
+    );
+    assert!(end_mill().validate().is_ok());
+}
+
+#[test]
+fn tool_lengths_must_make_sense() {
+    let tool = RotatingTool {
+        cutting_diameter: 0.0,
+        usable_length: 20.0,
+        functional_length: 10.0,
+        ..end_mill()
+    };
+
+    assert_eq!(
+        fields(tool.validate().unwrap_err()),
+        vec!["Cutting Diameter", "Usable Length"]
+    );
+}
+
+#[test]
+fn weldon_flat_size_only_checked_for_weldon_flat_holders() {
+    let mut holder = RotatingHolder {
+        category: RotatingHolderCategory::EndMill(EndMillSubcategory::MillingChuck),
+        weldon_flat_size: 0.0,
+        ..RotatingHolder::default()
+    };
+    assert!(holder.validate().is_ok());
+
+    holder.category = RotatingHolderCategory::EndMill(EndMillSubcategory::WeldonFlat);
+    assert_eq!(
+        fields(holder.validate().unwrap_err()),
+        vec!["Weldon Flat Size"]
+    );
+}
+
+#[test]
+fn thread_pitch_range_only_checked_for_threading_holders() {
+    let mut holder = TurningHolder {
+        category: TurningHolderCategory::External(Default::default()),
+        thread_pitch_range: (2.0, 1.0),
+        ..TurningHolder::default()
+    };
+    assert!(holder.validate().is_ok());
+
+    holder.category = TurningHolderCategory::Threading(ThreadingSubcategory::External);
+    assert_eq!(
+        fields(holder.validate().unwrap_err()),
+        vec!["Thread Pitch Range"]
+    );
+}
+
+#[test]
+fn placing_into_a_reserved_slot_fails() {
+    let mut machine = Machine {
+        magazines: vec![Magazine::new(0, 2)],
+        ..Machine::default()
+    };
+    machine.magazines[0].slots[1].locked = true;
+
+    let placed = place_tool(
+        &mut machine,
+        SlotAddress::new(0, 0),
+        Tool::Rotating(end_mill()),
+        Some("T1".to_string()),
+        "operator",
+    );
+    assert_eq!(placed, Ok(None));
+    let slot = &machine.magazines[0].slots[0];
+    assert_eq!(slot.tool_number.as_deref(), Some("T1"));
+    assert_eq!(slot.last_changed_by.as_deref(), Some("operator"));
+
+    let address = SlotAddress::new(0, 1);
+    assert_eq!(
+        place_holder(
+            &mut machine,
+            address,
+            Holder::Rotating(RotatingHolder::default()),
+            "operator"
+        ),
+        Err(PlacementError::Locked(address))
+    );
+    assert_eq!(
+        place_holder(
+            &mut machine,
+            SlotAddress::new(1, 0),
+            Holder::Rotating(RotatingHolder::default()),
+            "operator"
+        ),
+        Err(PlacementError::NoSuchMagazine(1))
+    );
+}